@@ -0,0 +1,114 @@
+use egui_wgpu::ScreenDescriptor;
+use witer::{error::*, prelude::*};
+
+use self::common::{egui::EguiRenderer, gpu::GpuContext};
+
+mod common;
+
+/*
+  This example asks whether to save before closing. With `close_on_x`
+  disabled, `CloseRequested` no longer closes the window. The request stays
+  pending until the app answers it with `confirm_close` or `cancel_close`,
+  which the egui prompt does here.
+
+  Once the close is confirmed, the loop yields `LoopMessage::Exit` while the
+  window still exists, so that's where the surface is dropped.
+*/
+
+const CLEAR_COLOR: wgpu::Color = wgpu::Color {
+  r: 0.1,
+  g: 0.3,
+  b: 0.7,
+  a: 1.0,
+};
+
+fn main() -> Result<(), WindowError> {
+  common::init_log(env!("CARGO_CRATE_NAME"));
+
+  let window = Window::builder()
+    .with_title("Close Prompt Example")
+    .with_size(LogicalSize::new(800.0, 600.0))
+    .with_flow(Flow::Poll)
+    .with_close_on_x(false)
+    .build()?;
+
+  let mut gpu = Some(GpuContext::new(&window));
+  let mut egui_renderer = {
+    let gpu = gpu.as_ref().unwrap();
+    EguiRenderer::new(&gpu.device, gpu.config.format, None, 1, &window)
+  };
+  let mut unsaved_edits = 0;
+
+  for message in &window {
+    let _ = egui_renderer.handle_input(&window, &message);
+
+    match &message {
+      // nothing to lose, so there's no need to ask
+      Message::CloseRequested if unsaved_edits == 0 => window.confirm_close(),
+      Message::Resized(size) => {
+        if let Some(gpu) = &mut gpu {
+          gpu.resize(*size);
+        }
+      }
+      Message::Loop(LoopMessage::Exit(reason)) => {
+        tracing::info!("exiting: {reason:?}");
+        // tear down the surface before the window is destroyed
+        gpu = None;
+      }
+      _ => (),
+    }
+
+    let Some(gpu) = &mut gpu else {
+      continue;
+    };
+
+    gpu.render(&window, CLEAR_COLOR, |gpu, encoder, view| {
+      let screen_descriptor = ScreenDescriptor {
+        size_in_pixels: [gpu.config.width, gpu.config.height],
+        pixels_per_point: window.scale_factor() as f32,
+      };
+
+      egui_renderer.draw(
+        &gpu.device,
+        &gpu.queue,
+        encoder,
+        &window,
+        view,
+        screen_descriptor,
+        |ctx| {
+          egui::CentralPanel::default().show(ctx, |ui| {
+            ui.label(format!("unsaved edits: {unsaved_edits}"));
+            if ui.button("Edit").clicked() {
+              unsaved_edits += 1;
+            }
+          });
+
+          if !window.is_close_pending() {
+            return;
+          }
+          egui::Window::new("Save changes?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, (0.0, 0.0))
+            .show(ctx, |ui| {
+              ui.label(format!("There are {unsaved_edits} unsaved edits."));
+              ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                  tracing::info!("saved {unsaved_edits} edits");
+                  window.confirm_close();
+                }
+                if ui.button("Discard").clicked() {
+                  window.confirm_close();
+                }
+                if ui.button("Cancel").clicked() {
+                  window.cancel_close();
+                }
+              });
+            });
+        },
+      );
+    });
+  }
+
+  Ok(())
+}
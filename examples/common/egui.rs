@@ -2,7 +2,7 @@ use egui::{Context, ViewportId};
 use egui_wgpu::{Renderer, ScreenDescriptor};
 use wgpu::{CommandEncoder, Device, Queue, TextureFormat, TextureView};
 use witer::{
-  compat::egui::{EventResponse, State},
+  compat::egui::{clipboard::ClipboardBacking, EventResponse, State},
   Message,
   Window,
 };
@@ -26,7 +26,7 @@ impl EguiRenderer {
     let egui_state = State::new(
       egui_context.clone(),
       ViewportId::default(),
-      window,
+      ClipboardBacking::System,
       Some(window.scale_factor() as f32),
       None,
     );
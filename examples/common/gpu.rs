@@ -0,0 +1,219 @@
+use wgpu::{CommandEncoder, TextureView};
+use witer::prelude::*;
+
+/*
+  Shared wgpu boilerplate so the examples can focus on what witer is doing.
+  Draws a single triangle over the clear color, then hands the encoder and
+  view to the caller for anything else (e.g. egui).
+*/
+
+pub struct GpuContext {
+  pub surface: wgpu::Surface<'static>,
+  pub device: wgpu::Device,
+  pub queue: wgpu::Queue,
+  pub config: wgpu::SurfaceConfiguration,
+  render_pipeline: wgpu::RenderPipeline,
+}
+
+#[allow(unused)]
+impl GpuContext {
+  pub fn new(window: &Window) -> Self {
+    pollster::block_on(async {
+      let size = window.inner_size();
+
+      let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::all(),
+        ..Default::default()
+      });
+
+      let surface = instance.create_surface(window.clone()).unwrap();
+
+      let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+          power_preference: wgpu::PowerPreference::HighPerformance,
+          compatible_surface: Some(&surface),
+          force_fallback_adapter: false,
+        })
+        .await
+        .unwrap();
+
+      let (device, queue) = adapter
+        .request_device(
+          &wgpu::DeviceDescriptor {
+            required_features: wgpu::Features::empty(),
+            required_limits: wgpu::Limits::default(),
+            label: None,
+          },
+          None,
+        )
+        .await
+        .unwrap();
+
+      let surface_caps = surface.get_capabilities(&adapter);
+      let surface_format = surface_caps
+        .formats
+        .iter()
+        .copied()
+        .find(|f| f.is_srgb())
+        .unwrap_or(surface_caps.formats[0]);
+      let present_mode = match window.recommended_present_mode() {
+        PresentMode::Fifo => wgpu::PresentMode::Fifo,
+        PresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+        PresentMode::Immediate => wgpu::PresentMode::Immediate,
+      };
+      let present_mode = if surface_caps.present_modes.contains(&present_mode) {
+        present_mode
+      } else {
+        wgpu::PresentMode::Fifo
+      };
+      let config = wgpu::SurfaceConfiguration {
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        format: surface_format,
+        width: size.width,
+        height: size.height,
+        present_mode,
+        alpha_mode: surface_caps.alpha_modes[0],
+        view_formats: vec![],
+        desired_maximum_frame_latency: 2,
+      };
+      surface.configure(&device, &config);
+
+      let shader = device.create_shader_module(wgpu::include_wgsl!("shader.wgsl"));
+
+      let render_pipeline_layout =
+        device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+          label: Some("Render Pipeline Layout"),
+          bind_group_layouts: &[],
+          push_constant_ranges: &[],
+        });
+
+      let render_pipeline =
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+          label: Some("Render Pipeline"),
+          layout: Some(&render_pipeline_layout),
+          vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            buffers: &[],
+          },
+          fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: "fs_main",
+            targets: &[Some(wgpu::ColorTargetState {
+              format: config.format,
+              blend: Some(wgpu::BlendState::REPLACE),
+              write_mask: wgpu::ColorWrites::ALL,
+            })],
+          }),
+          primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: Some(wgpu::Face::Back),
+            // Setting this to anything other than Fill requires
+            // Features::NON_FILL_POLYGON_MODE
+            polygon_mode: wgpu::PolygonMode::Fill,
+            // Requires Features::DEPTH_CLIP_CONTROL
+            unclipped_depth: false,
+            // Requires Features::CONSERVATIVE_RASTERIZATION
+            conservative: false,
+          },
+          depth_stencil: None,
+          multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+          },
+          multiview: None,
+        });
+
+      Self {
+        surface,
+        device,
+        queue,
+        config,
+        render_pipeline,
+      }
+    })
+  }
+
+  pub fn size(&self) -> PhysicalSize {
+    PhysicalSize::new(self.config.width, self.config.height)
+  }
+
+  pub fn resize(&mut self, new_size: PhysicalSize) {
+    if new_size.is_any_zero() {
+      return;
+    }
+
+    self.config.width = new_size.width;
+    self.config.height = new_size.height;
+    self.surface.configure(&self.device, &self.config);
+  }
+
+  /// Configures the surface again with the current settings, e.g. after
+  /// [`Message::SurfaceInvalidated`].
+  pub fn reconfigure(&mut self) {
+    self.surface.configure(&self.device, &self.config);
+  }
+
+  /// Clears the surface, draws the triangle, then calls `draw` with the
+  /// encoder and view before submitting and presenting. Lost or outdated
+  /// surfaces are reconfigured and the frame is skipped.
+  pub fn render(
+    &mut self,
+    window: &Window,
+    clear_color: wgpu::Color,
+    draw: impl FnOnce(&mut Self, &mut CommandEncoder, &TextureView),
+  ) {
+    if window.inner_size().is_any_zero() {
+      return;
+    }
+
+    let output = match self.surface.get_current_texture() {
+      Ok(output) => output,
+      Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+        self.resize(window.inner_size());
+        return;
+      }
+      Err(error) => {
+        tracing::error!("{error}");
+        return;
+      }
+    };
+
+    let view = output
+      .texture
+      .create_view(&wgpu::TextureViewDescriptor::default());
+    let mut encoder =
+      self
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+          label: Some("Render Encoder"),
+        });
+    {
+      let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Render Pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+          view: &view,
+          resolve_target: None,
+          ops: wgpu::Operations {
+            load: wgpu::LoadOp::Clear(clear_color),
+            store: wgpu::StoreOp::Store,
+          },
+        })],
+        depth_stencil_attachment: None,
+        occlusion_query_set: None,
+        timestamp_writes: None,
+      });
+      render_pass.set_pipeline(&self.render_pipeline);
+      render_pass.draw(0..3, 0..1);
+    }
+
+    draw(self, &mut encoder, &view);
+
+    self.queue.submit(std::iter::once(encoder.finish()));
+    output.present();
+    window.notify_presented();
+  }
+}
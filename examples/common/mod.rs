@@ -1,6 +1,9 @@
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use witer::prelude::*;
 
+#[cfg(feature = "egui")]
 pub mod egui;
+pub mod gpu;
 
 pub fn init_log(crate_name: &'static str) {
   tracing_subscriber::registry()
@@ -15,3 +18,18 @@ pub fn init_log(crate_name: &'static str) {
     )
     .init();
 }
+
+#[allow(unused)]
+pub fn center_window(window: &Window) {
+  let window_size = window.outer_size();
+  let monitor = window.current_monitor();
+  let monitor_pos = monitor.position();
+  let monitor_size = monitor.size();
+  window.set_outer_position(
+    PhysicalPosition::new(
+      monitor_pos.x + (monitor_size.width as i32 - window_size.width as i32) / 2,
+      monitor_pos.y + (monitor_size.height as i32 - window_size.height as i32) / 2,
+    )
+    .into(),
+  );
+}
@@ -0,0 +1,161 @@
+use std::num::NonZeroU32;
+
+use witer::prelude::*;
+
+mod common;
+
+/*
+  This example builds a right-click context menu entirely out of witer
+  windows. The menu is an owned popup which asks to be dismissed when the user
+  clicks anywhere else, drawn on the CPU with `softbuffer`.
+
+  Right-click the window to open the menu and pick a color. The menu runs its
+  own loop on another thread and posts the picked item back to the main window
+  as an app message.
+*/
+
+const COLORS: [u32; 4] = [0xc04040, 0x40a040, 0x4060c0, 0xc0a040];
+const ITEM_HEIGHT: f64 = 28.0;
+const PICKED: u16 = 0;
+
+fn main() {
+  common::init_log(env!("CARGO_CRATE_NAME"));
+
+  let window = Window::builder()
+    .with_title("Context Menu Example")
+    .with_size(LogicalSize::new(800.0, 600.0))
+    .build()
+    .unwrap();
+
+  let menu = Window::builder()
+    .with_title("Context Menu")
+    .with_size(LogicalSize::new(160.0, ITEM_HEIGHT * COLORS.len() as f64))
+    .with_visibility(Visibility::Hidden)
+    .with_popup(true)
+    .with_auto_dismiss(true)
+    .with_owner(&window)
+    .build()
+    .unwrap();
+
+  let menu_thread = {
+    let window = window.clone();
+    let menu = menu.clone();
+    std::thread::Builder::new()
+      .name("menu".to_owned())
+      .spawn(move || run_menu(&window, &menu))
+      .unwrap()
+  };
+
+  let context = softbuffer::Context::new(window.clone()).unwrap();
+  let mut surface = softbuffer::Surface::new(&context, window.clone()).unwrap();
+  let mut color = 0x202020;
+
+  for message in &window {
+    if message.is_key(Key::Escape, KeyState::Pressed) {
+      window.close();
+    }
+
+    match message {
+      Message::MouseButton {
+        button: MouseButton::Right,
+        state: ButtonState::Released,
+        ..
+      } => {
+        menu.show_popup_at(window.cursor_screen_position(), PopupAnchor::TopLeft);
+      }
+      Message::App {
+        code: PICKED,
+        wparam: item,
+        ..
+      } => {
+        color = COLORS[item];
+        window.request_redraw();
+      }
+      Message::Paint | Message::Resized(_) => {
+        fill(&mut surface, window.inner_size(), |_| color);
+      }
+      _ => (),
+    }
+  }
+
+  // owned windows go before their owner
+  menu.close();
+  menu_thread.join().unwrap();
+}
+
+fn run_menu(window: &Window, menu: &Window) {
+  let context = softbuffer::Context::new(menu.clone()).unwrap();
+  let mut surface = softbuffer::Surface::new(&context, menu.clone()).unwrap();
+  let mut hovered = None;
+
+  for message in menu {
+    match message {
+      Message::CursorMove { position, .. } => {
+        let item = item_at(menu, position);
+        if item != hovered {
+          hovered = item;
+          menu.request_redraw();
+        }
+      }
+      Message::MouseButton {
+        button: MouseButton::Left,
+        state: ButtonState::Released,
+        position,
+        ..
+      } => {
+        if let Some(item) = item_at(menu, position) {
+          let _ = window.post_app_message(PICKED, item, 0);
+        }
+        menu.set_visibility(Visibility::Hidden);
+      }
+      Message::DismissRequested => menu.set_visibility(Visibility::Hidden),
+      Message::Paint | Message::Resized(_) => {
+        let item_height = ITEM_HEIGHT * menu.scale_factor();
+        fill(&mut surface, menu.inner_size(), |y| {
+          let item = ((y as f64 / item_height) as usize).min(COLORS.len() - 1);
+          if Some(item) == hovered {
+            // brighten the hovered item
+            COLORS[item] | 0x303030
+          } else {
+            COLORS[item]
+          }
+        });
+      }
+      _ => (),
+    }
+  }
+}
+
+fn item_at(menu: &Window, position: PhysicalPosition) -> Option<usize> {
+  let size = menu.inner_size();
+  if position.x < 0
+    || position.y < 0
+    || position.x >= size.width as i32
+    || position.y >= size.height as i32
+  {
+    return None;
+  }
+
+  let item = (position.y as f64 / (ITEM_HEIGHT * menu.scale_factor())) as usize;
+  (item < COLORS.len()).then_some(item)
+}
+
+/// Fills every row of the surface with the color returned for it.
+fn fill(
+  surface: &mut softbuffer::Surface<Window, Window>,
+  size: PhysicalSize,
+  color_of_row: impl Fn(u32) -> u32,
+) {
+  let (Some(width), Some(height)) =
+    (NonZeroU32::new(size.width), NonZeroU32::new(size.height))
+  else {
+    return;
+  };
+
+  surface.resize(width, height).unwrap();
+  let mut buffer = surface.buffer_mut().unwrap();
+  for (y, row) in buffer.chunks_mut(size.width as usize).enumerate() {
+    row.fill(color_of_row(y as u32));
+  }
+  buffer.present().unwrap();
+}
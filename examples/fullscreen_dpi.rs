@@ -0,0 +1,83 @@
+use witer::prelude::*;
+
+use self::common::gpu::GpuContext;
+
+mod common;
+
+/*
+  This example showcases borderless fullscreen on a high-DPI monitor. A
+  borderless fullscreen window is as large as the monitor in physical pixels,
+  so at 150% scaling a 3840x2160 monitor gives a 3840x2160 surface.
+
+  The surface is configured at that physical size so nothing gets upscaled,
+  while UI is laid out at the logical size (2560x1440 in the case above) so
+  text and buttons stay the same size they were in windowed mode. Scale
+  factors like 1.25 or 1.75 give logical sizes that aren't whole numbers, so
+  keep them as floats instead of rounding.
+
+  Press F11 to toggle fullscreen and Esc to close.
+*/
+
+fn main() {
+  common::init_log(env!("CARGO_CRATE_NAME"));
+
+  let window = Window::builder()
+    .with_title("Fullscreen DPI Example")
+    .with_flow(Flow::Poll)
+    .with_fullscreen(Some(Fullscreen::Borderless))
+    .with_reveal_after_first_present(true)
+    .build()
+    .unwrap();
+
+  let mut gpu = GpuContext::new(&window);
+  report_sizes(&window);
+
+  for message in &window {
+    match &message {
+      Message::Key {
+        key: Key::Escape,
+        state: KeyState::Pressed,
+        ..
+      } => window.close(),
+      Message::Key {
+        key: Key::F11,
+        state: KeyState::Pressed,
+        ..
+      } => match window.fullscreen() {
+        Some(_) => window.set_fullscreen(None),
+        None => window.set_fullscreen(Some(Fullscreen::Borderless)),
+      },
+      Message::Resized(new_size) => {
+        gpu.resize(*new_size);
+        report_sizes(&window);
+      }
+      Message::ScaleFactorChanged(_) => report_sizes(&window),
+      Message::SurfaceInvalidated(_) => gpu.reconfigure(),
+      _ => (),
+    }
+
+    gpu.render(&window, wgpu::Color::BLACK, |_, _, _| {});
+  }
+}
+
+fn report_sizes(window: &Window) {
+  let surface = window.inner_size();
+  let ui = match window.fullscreen() {
+    Some(_) => window.current_monitor().size_logical(),
+    None => {
+      let scale_factor = window.scale_factor();
+      LogicalSize::new(
+        surface.width as f64 / scale_factor,
+        surface.height as f64 / scale_factor,
+      )
+    }
+  };
+  tracing::info!(
+    "surface: {}x{} px, ui: {:.2}x{:.2} at {}x scale",
+    surface.width,
+    surface.height,
+    ui.width,
+    ui.height,
+    window.scale_factor(),
+  );
+}
@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use gilrs::{EventType, Gilrs};
+use witer::prelude::*;
+
+mod common;
+
+/*
+  This example reads controllers with `gilrs` on a thread of its own, and only
+  acts on them while the window has focus. gilrs sees controller input no
+  matter which window is in front, so without the check a game in the
+  background would keep reacting to the pad.
+
+  The input thread checks the window's focus gate, which is a single atomic
+  load, and sleeps on it while the window is unfocused instead of polling.
+  Input that arrived in the meantime is thrown away.
+*/
+
+fn main() {
+  common::init_log(env!("CARGO_CRATE_NAME"));
+
+  let window = Window::builder()
+    .with_title("Gamepad Example")
+    .with_size(LogicalSize::new(800.0, 600.0))
+    .build()
+    .unwrap();
+
+  let gate = window.focus_gate();
+  let input_thread = std::thread::Builder::new()
+    .name("gamepad".to_owned())
+    .spawn(move || run_gamepad(&gate))
+    .unwrap();
+
+  for message in &window {
+    if message.is_key(Key::Escape, KeyState::Pressed) {
+      window.close();
+    }
+  }
+
+  // the gate closes along with the window, which wakes the input thread
+  input_thread.join().unwrap();
+}
+
+fn run_gamepad(gate: &FocusGate) {
+  let mut gilrs = Gilrs::new().unwrap();
+
+  while !gate.is_closed() {
+    if !gate.is_focused() {
+      gate.wait_until(true, None);
+      // drop whatever came in while waiting
+      while gilrs.next_event().is_some() {}
+      continue;
+    }
+
+    while let Some(event) = gilrs.next_event() {
+      match event.event {
+        EventType::ButtonPressed(button, _) => {
+          println!("{}: pressed {button:?}", gilrs.gamepad(event.id).name());
+        }
+        EventType::AxisChanged(axis, value, _) if value.abs() > 0.5 => {
+          println!("{}: moved {axis:?} to {value:.2}", gilrs.gamepad(event.id).name());
+        }
+        _ => (),
+      }
+    }
+    std::thread::sleep(Duration::from_millis(4));
+  }
+}
@@ -0,0 +1,159 @@
+use std::{
+  process::ExitCode,
+  time::{Duration, Instant},
+};
+
+use witer::prelude::*;
+
+mod common;
+
+/*
+  This example measures how long messages take from reaching the window
+  thread to being yielded by the iterator, which is the lag the crate docs
+  promise to keep low. A helper thread posts app messages stamped with the
+  time they were sent, standing in for input, while the main thread simulates
+  different kinds of frames.
+
+  A table of the results is printed at the end. The example exits with a
+  failure if the 99th percentile of any scenario is over its bound, so it can
+  be run as a regression check for changes to how the threads synchronize.
+*/
+
+const STAMPED: u16 = 0;
+
+struct Scenario {
+  name: &'static str,
+  flow: Flow,
+  /// How long the main thread spends on each frame.
+  frame: Duration,
+  /// How many messages are posted, and how long to wait between them.
+  count: usize,
+  interval: Duration,
+  /// The highest acceptable 99th percentile.
+  bound: Duration,
+}
+
+const SCENARIOS: [Scenario; 4] = [
+  Scenario {
+    name: "wait, idle",
+    flow: Flow::Wait,
+    frame: Duration::ZERO,
+    count: 100,
+    interval: Duration::from_millis(10),
+    bound: Duration::from_millis(4),
+  },
+  Scenario {
+    name: "poll, 16ms frames",
+    flow: Flow::Poll,
+    frame: Duration::from_millis(16),
+    count: 250,
+    interval: Duration::from_millis(4),
+    bound: Duration::from_millis(32),
+  },
+  Scenario {
+    name: "poll, 100ms frames",
+    flow: Flow::Poll,
+    frame: Duration::from_millis(100),
+    count: 50,
+    interval: Duration::from_millis(20),
+    bound: Duration::from_millis(200),
+  },
+  Scenario {
+    name: "wait, burst of 1000",
+    flow: Flow::Wait,
+    frame: Duration::ZERO,
+    count: 1000,
+    interval: Duration::ZERO,
+    bound: Duration::from_millis(32),
+  },
+];
+
+fn main() -> ExitCode {
+  common::init_log(env!("CARGO_CRATE_NAME"));
+
+  let results: Vec<_> = SCENARIOS.iter().map(run).collect();
+
+  println!(
+    "{:<22} {:>8} {:>10} {:>10} {:>10} {:>10}",
+    "scenario", "samples", "p50", "p99", "max", "bound"
+  );
+  let mut passed = true;
+  for (scenario, latencies) in SCENARIOS.iter().zip(&results) {
+    let p99 = percentile(latencies, 0.99);
+    let ok = p99 <= scenario.bound;
+    passed &= ok;
+    println!(
+      "{:<22} {:>8} {:>10.2?} {:>10.2?} {:>10.2?} {:>10.2?} {}",
+      scenario.name,
+      latencies.len(),
+      percentile(latencies, 0.5),
+      p99,
+      latencies.last().copied().unwrap_or_default(),
+      scenario.bound,
+      if ok { "ok" } else { "FAILED" }
+    );
+  }
+
+  if passed {
+    ExitCode::SUCCESS
+  } else {
+    ExitCode::FAILURE
+  }
+}
+
+/// Returns the sorted latencies of every message posted during the scenario.
+fn run(scenario: &Scenario) -> Vec<Duration> {
+  let window = Window::builder()
+    .with_title(scenario.name)
+    .with_size(LogicalSize::new(320.0, 240.0))
+    .with_visibility(Visibility::Hidden)
+    .with_flow(scenario.flow)
+    .build()
+    .unwrap();
+
+  let start = Instant::now();
+  let sender = {
+    let window = window.clone();
+    let (count, interval) = (scenario.count, scenario.interval);
+    std::thread::spawn(move || {
+      for i in 0..count {
+        let sent = start.elapsed().as_nanos() as isize;
+        if window.post_app_message(STAMPED, i, sent).is_err() {
+          break;
+        }
+        std::thread::sleep(interval);
+      }
+    })
+  };
+
+  let mut latencies = Vec::with_capacity(scenario.count);
+  for message in &window {
+    match message {
+      Message::App {
+        code: STAMPED,
+        lparam: sent,
+        ..
+      } => {
+        let sent = Duration::from_nanos(sent as u64);
+        latencies.push(start.elapsed().saturating_sub(sent));
+        if latencies.len() == scenario.count {
+          window.close();
+        }
+      }
+      Message::Loop(LoopMessage::Empty) => std::thread::sleep(scenario.frame),
+      _ => (),
+    }
+  }
+
+  sender.join().unwrap();
+  latencies.sort();
+  latencies
+}
+
+fn percentile(sorted: &[Duration], fraction: f64) -> Duration {
+  if sorted.is_empty() {
+    return Duration::ZERO;
+  }
+  let index = ((sorted.len() - 1) as f64 * fraction).round() as usize;
+  sorted[index]
+}
@@ -0,0 +1,42 @@
+use witer::prelude::*;
+
+mod common;
+
+/*
+  This example showcases a window that doesn't touch the GPU at all. It only
+  logs the messages it receives, which makes it useful for checking that
+  witer itself works on machines without working graphics drivers.
+
+  Press F11 to toggle borderless fullscreen and Escape to close.
+*/
+
+fn main() {
+  common::init_log(env!("CARGO_CRATE_NAME"));
+
+  let window = Window::builder()
+    .with_title("No GPU Example")
+    .with_size(LogicalSize::new(800.0, 600.0))
+    .with_visibility(Visibility::Hidden)
+    .build()
+    .unwrap();
+
+  common::center_window(&window);
+  window.set_visibility(Visibility::Shown);
+
+  for message in &window {
+    if message.is_key(Key::Escape, KeyState::Pressed) {
+      window.close();
+    }
+
+    if message.is_key(Key::F11, KeyState::Pressed) {
+      match window.fullscreen() {
+        Some(_) => window.set_fullscreen(None),
+        None => window.set_fullscreen(Some(Fullscreen::Borderless)),
+      }
+    }
+
+    if !message.is_noise() {
+      tracing::info!("{message:?}");
+    }
+  }
+}
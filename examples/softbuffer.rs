@@ -0,0 +1,50 @@
+use std::num::NonZeroU32;
+
+use witer::prelude::*;
+
+mod common;
+
+/*
+  This example showcases drawing to a window on the CPU with `softbuffer`,
+  using the raw handles witer implements. It fills the window with a gradient
+  whenever it is resized or repainted.
+*/
+
+fn main() {
+  common::init_log(env!("CARGO_CRATE_NAME"));
+
+  let window = Window::builder()
+    .with_title("Softbuffer Example")
+    .with_size(LogicalSize::new(800.0, 600.0))
+    .build()
+    .unwrap();
+
+  let context = softbuffer::Context::new(window.clone()).unwrap();
+  let mut surface = softbuffer::Surface::new(&context, window.clone()).unwrap();
+
+  for message in &window {
+    if message.is_key(Key::Escape, KeyState::Pressed) {
+      window.close();
+    }
+
+    if let Message::Paint | Message::Resized(_) = message {
+      let size = window.inner_size();
+      let (Some(width), Some(height)) =
+        (NonZeroU32::new(size.width), NonZeroU32::new(size.height))
+      else {
+        continue;
+      };
+
+      surface.resize(width, height).unwrap();
+      let mut buffer = surface.buffer_mut().unwrap();
+      for y in 0..size.height {
+        for x in 0..size.width {
+          let red = x * 255 / size.width;
+          let green = y * 255 / size.height;
+          buffer[(y * size.width + x) as usize] = (red << 16) | (green << 8) | 0x80;
+        }
+      }
+      buffer.present().unwrap();
+    }
+  }
+}
@@ -0,0 +1,54 @@
+use ash::vk;
+use witer::{compat::surface::vulkan_create_info, prelude::*};
+
+mod common;
+
+/*
+  This example showcases creating a Vulkan surface for a window with `ash`,
+  for renderers that don't go through `wgpu`. It only creates the surface and
+  then logs messages until the window is closed.
+*/
+
+fn main() {
+  common::init_log(env!("CARGO_CRATE_NAME"));
+
+  let window = Window::builder()
+    .with_title("Vulkan Example")
+    .with_size(LogicalSize::new(800.0, 600.0))
+    .build()
+    .unwrap();
+
+  let entry = unsafe { ash::Entry::load() }.expect("failed to load the Vulkan loader");
+
+  let app_info = vk::ApplicationInfo::default().api_version(vk::API_VERSION_1_0);
+  let extensions = [
+    ash::khr::surface::NAME.as_ptr(),
+    ash::khr::win32_surface::NAME.as_ptr(),
+  ];
+  let instance_info = vk::InstanceCreateInfo::default()
+    .application_info(&app_info)
+    .enabled_extension_names(&extensions);
+  let instance = unsafe { entry.create_instance(&instance_info, None) }.unwrap();
+
+  let (hinstance, hwnd) = vulkan_create_info(&window).unwrap();
+  let surface_info = vk::Win32SurfaceCreateInfoKHR::default()
+    .hinstance(hinstance)
+    .hwnd(hwnd);
+  let win32_surface = ash::khr::win32_surface::Instance::new(&entry, &instance);
+  let surface =
+    unsafe { win32_surface.create_win32_surface(&surface_info, None) }.unwrap();
+
+  tracing::info!("created surface {surface:?}");
+
+  for message in &window {
+    if message.is_key(Key::Escape, KeyState::Pressed) {
+      window.close();
+    }
+  }
+
+  let surface_loader = ash::khr::surface::Instance::new(&entry, &instance);
+  unsafe {
+    surface_loader.destroy_surface(surface, None);
+    instance.destroy_instance(None);
+  }
+}
@@ -0,0 +1,139 @@
+/// Handles interfacing with the OS clipboard.
+///
+/// If the "clipboard" feature is off, or we cannot connect to the OS clipboard,
+/// then a fallback clipboard that just works within the same app is used
+/// instead. Images additionally need the "clipboard_image" feature to reach
+/// the OS clipboard.
+pub struct Clipboard {
+  #[cfg(feature = "clipboard")]
+  arboard: Option<arboard::Clipboard>,
+  /// Fallback manual clipboard.
+  clipboard: String,
+  /// Fallback manual image clipboard.
+  image: Option<ClipboardImage>,
+}
+
+/// An RGBA8 image on the clipboard.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClipboardImage {
+  pub width: usize,
+  pub height: usize,
+  /// Row-major RGBA8 pixels, `width * height * 4` bytes long.
+  pub bytes: Vec<u8>,
+}
+
+/// Where a [`Clipboard`] keeps its contents.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ClipboardBacking {
+  /// The OS clipboard if the "clipboard" feature is on and it can be reached,
+  /// in memory otherwise.
+  #[default]
+  System,
+  /// Only in memory, shared with nothing else. Never touches the OS clipboard,
+  /// so it is safe to use headless.
+  InMemory,
+}
+
+impl Default for Clipboard {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Clipboard {
+  /// Construct a new instance
+  pub fn new() -> Self {
+    Self::with_backing(ClipboardBacking::System)
+  }
+
+  pub fn with_backing(backing: ClipboardBacking) -> Self {
+    // without arboard everything is in memory anyway
+    #[cfg(not(feature = "clipboard"))]
+    let _ = backing;
+
+    Self {
+      #[cfg(feature = "clipboard")]
+      arboard: match backing {
+        ClipboardBacking::System => init_arboard(),
+        ClipboardBacking::InMemory => None,
+      },
+
+      clipboard: Default::default(),
+      image: None,
+    }
+  }
+
+  pub fn get(&mut self) -> Option<String> {
+    #[cfg(feature = "clipboard")]
+    if let Some(clipboard) = &mut self.arboard {
+      return match clipboard.get_text() {
+        Ok(text) => Some(text),
+        Err(err) => {
+          tracing::error!("arboard paste error: {err}");
+          None
+        }
+      };
+    }
+
+    Some(self.clipboard.clone())
+  }
+
+  pub fn set(&mut self, text: String) {
+    #[cfg(feature = "clipboard")]
+    if let Some(clipboard) = &mut self.arboard {
+      if let Err(err) = clipboard.set_text(text) {
+        tracing::error!("arboard copy/cut error: {err}");
+      }
+      return;
+    }
+
+    self.clipboard = text;
+  }
+
+  pub fn get_image(&mut self) -> Option<ClipboardImage> {
+    #[cfg(feature = "clipboard_image")]
+    if let Some(clipboard) = &mut self.arboard {
+      return match clipboard.get_image() {
+        Ok(image) => Some(ClipboardImage {
+          width: image.width,
+          height: image.height,
+          bytes: image.bytes.into_owned(),
+        }),
+        Err(err) => {
+          tracing::error!("arboard image paste error: {err}");
+          None
+        }
+      };
+    }
+
+    self.image.clone()
+  }
+
+  pub fn set_image(&mut self, image: ClipboardImage) {
+    #[cfg(feature = "clipboard_image")]
+    if let Some(clipboard) = &mut self.arboard {
+      if let Err(err) = clipboard.set_image(arboard::ImageData {
+        width: image.width,
+        height: image.height,
+        bytes: std::borrow::Cow::Owned(image.bytes),
+      }) {
+        tracing::error!("arboard image copy error: {err}");
+      }
+      return;
+    }
+
+    self.image = Some(image);
+  }
+}
+
+#[cfg(feature = "clipboard")]
+fn init_arboard() -> Option<arboard::Clipboard> {
+  tracing::trace!("Initializing arboard clipboard…");
+  match arboard::Clipboard::new() {
+    Ok(clipboard) => Some(clipboard),
+    Err(err) => {
+      tracing::warn!("Failed to initialize arboard clipboard: {err}");
+      None
+    }
+  }
+}
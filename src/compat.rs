@@ -1,5 +1,7 @@
-#[cfg(feature = "egui")]
+#[cfg(feature = "egui-input")]
 pub mod egui;
 
 #[cfg(feature = "opengl")]
 pub mod opengl;
+
+pub mod surface;
@@ -1,4 +1,4 @@
-#![cfg(feature = "egui")]
+#![cfg(feature = "egui-input")]
 // _TEMPORARY_ fix to shut up the compiler while I work on the port
 #![allow(unused)]
 #![allow(unused_variables)]
@@ -24,7 +24,7 @@ use egui::{
 pub use window_settings::WindowSettings;
 
 use self::window::message::{CursorMoveKind, Focus};
-use crate::{prelude::*, raw_window_handle::HasDisplayHandle};
+use crate::{prelude::*, utilities::is_printable_char};
 
 pub mod clipboard;
 mod window_settings;
@@ -76,6 +76,7 @@ pub struct State {
   egui_input: egui::RawInput,
   pointer_pos_in_points: Option<egui::Pos2>,
   any_pointer_button_down: bool,
+  #[cfg(feature = "egui-full")]
   current_cursor_icon: Option<egui::CursorIcon>,
 
   clipboard: clipboard::Clipboard,
@@ -95,14 +96,30 @@ pub struct State {
   input_method_editor_started: bool,
 
   allow_ime: bool,
+
+  /// If `true`, egui's visuals follow the effective theme of the window.
+  follow_system_theme: bool,
+
+  /// The theme the visuals were last set to, used for debouncing.
+  applied_theme: Option<EffectiveTheme>,
+
+  /// The part of the window this state covers, if not the whole window.
+  viewport_rect: Option<PhysicalRect>,
+
+  /// Set while a button pressed inside [`Self::viewport_rect`] is held, so
+  /// drags keep going outside of it.
+  pointer_captured: bool,
 }
 
 impl State {
-  /// Construct a new instance
+  /// Construct a new instance. Use [`ClipboardBacking::InMemory`] for tests
+  /// and headless builds.
+  ///
+  /// [`ClipboardBacking::InMemory`]: clipboard::ClipboardBacking::InMemory
   pub fn new(
     egui_ctx: egui::Context,
     viewport_id: ViewportId,
-    display_target: &dyn HasDisplayHandle,
+    clipboard: clipboard::ClipboardBacking,
     native_pixels_per_point: Option<f32>,
     max_texture_side: Option<usize>,
   ) -> Self {
@@ -118,11 +135,10 @@ impl State {
       egui_input,
       pointer_pos_in_points: None,
       any_pointer_button_down: false,
+      #[cfg(feature = "egui-full")]
       current_cursor_icon: None,
 
-      clipboard: clipboard::Clipboard::new(
-        display_target.display_handle().ok().map(|h| h.as_raw()),
-      ),
+      clipboard: clipboard::Clipboard::with_backing(clipboard),
 
       simulate_touch_screen: false,
       pointer_touch_id: None,
@@ -130,6 +146,12 @@ impl State {
       input_method_editor_started: false,
 
       allow_ime: false,
+
+      follow_system_theme: true,
+      applied_theme: None,
+
+      viewport_rect: None,
+      pointer_captured: false,
     };
 
     slf
@@ -172,6 +194,38 @@ impl State {
     self.allow_ime = allow;
   }
 
+  /// Whether egui switches between dark and light visuals along with
+  /// [`Window::effective_theme`]. Enabled by default. Disable this when using
+  /// custom visuals.
+  pub fn set_follow_system_theme(&mut self, follow: bool) {
+    self.follow_system_theme = follow;
+    self.applied_theme = None;
+  }
+
+  /// Limit this state to a part of the window, in physical pixels relative to
+  /// the client area, so several states can share one window (e.g. a side
+  /// panel and a canvas overlay). The rect becomes egui's screen rect, pointer
+  /// positions are made relative to it, and pointer events outside of it are
+  /// ignored. `None` covers the whole window.
+  ///
+  /// Only the state under the pointer sets the cursor icon. Every state copies
+  /// to the same system clipboard, so the last copy wins.
+  pub fn set_viewport_rect(&mut self, rect: Option<PhysicalRect>) {
+    self.viewport_rect = rect;
+  }
+
+  pub fn viewport_rect(&self) -> Option<PhysicalRect> {
+    self.viewport_rect
+  }
+
+  /// Whether a pointer event at `position` belongs to this state.
+  fn is_in_viewport(&self, position: PhysicalPosition) -> bool {
+    match self.viewport_rect {
+      Some(rect) => self.pointer_captured || rect.contains(position),
+      None => true,
+    }
+  }
+
   #[inline]
   pub fn egui_ctx(&self) -> &egui::Context {
     &self.egui_ctx
@@ -204,11 +258,24 @@ impl State {
   pub fn take_egui_input(&mut self, window: &Window) -> egui::RawInput {
     self.egui_input.time = Some(self.start_time.elapsed().as_secs_f64());
 
+    // egui 0.27 has no system theme input, so switch the visuals directly
+    let theme = window.effective_theme();
+    if self.follow_system_theme && self.applied_theme != Some(theme) {
+      self.egui_ctx.set_visuals(match theme {
+        EffectiveTheme::Dark => egui::Visuals::dark(),
+        EffectiveTheme::Light => egui::Visuals::light(),
+      });
+      self.applied_theme = Some(theme);
+    }
+
     // On Windows, a minimized window will have 0 width and height.
     // See: https://github.com/rust-windowing/winit/issues/208
     // This solves an issue where egui window positions would be changed when
     // minimizing on Windows.
-    let screen_size_in_pixels = screen_size_in_pixels(window);
+    let screen_size_in_pixels = match self.viewport_rect {
+      Some(rect) => egui::vec2(rect.size.width as f32, rect.size.height as f32),
+      None => screen_size_in_pixels(window),
+    };
     let screen_size_in_points =
       screen_size_in_pixels / pixels_per_point(&self.egui_ctx, window);
 
@@ -251,12 +318,28 @@ impl State {
         }
       }
       Message::MouseButton { state, button, .. } => {
+        if self.pointer_pos_in_points.is_none() {
+          // outside of the viewport, so it belongs to another state
+          return EventResponse {
+            repaint: false,
+            consumed: false,
+          };
+        }
+        if self.viewport_rect.is_some() {
+          self.pointer_captured = state.is_pressed();
+        }
         self.on_mouse_button_input(*button, *state);
         EventResponse {
           repaint: true,
           consumed: self.egui_ctx.wants_pointer_input(),
         }
       }
+      Message::MouseWheel { .. } if self.pointer_pos_in_points.is_none() => {
+        EventResponse {
+          repaint: false,
+          consumed: false,
+        }
+      }
       Message::MouseWheel { delta_x, delta_y } => {
         self.on_mouse_wheel(window, *delta_x, *delta_y);
         EventResponse {
@@ -269,75 +352,99 @@ impl State {
         ..
       } => {
         self.pointer_pos_in_points = None;
+        self.pointer_captured = false;
         self.egui_input.events.push(egui::Event::PointerGone);
         EventResponse {
           repaint: true,
           consumed: false,
         }
       }
+      Message::CursorMove { position, .. } if !self.is_in_viewport(*position) => {
+        let was_inside = self.pointer_pos_in_points.take().is_some();
+        if was_inside {
+          self.egui_input.events.push(egui::Event::PointerGone);
+        }
+        EventResponse {
+          repaint: was_inside,
+          consumed: false,
+        }
+      }
       Message::CursorMove { position, .. } => {
-        self.on_cursor_moved(window, *position);
+        let position = match self.viewport_rect {
+          Some(rect) => PhysicalPosition::new(
+            position.x - rect.position.x,
+            position.y - rect.position.y,
+          ),
+          None => *position,
+        };
+        self.on_cursor_moved(window, position);
         EventResponse {
           repaint: true,
           consumed: self.egui_ctx.is_using_pointer(),
         }
       }
       // WindowEvent::TouchpadPressure {device_id, pressure, stage, ..  } => {} // TODO
-      // Message::Touch(touch) => {
-      //   self.on_touch(window, touch);
-      //   let consumed = match touch.phase {
-      //     winit::event::TouchPhase::Started
-      //     | winit::event::TouchPhase::Ended
-      //     | winit::event::TouchPhase::Cancelled => self.egui_ctx.wants_pointer_input(),
-      //     winit::event::TouchPhase::Moved => self.egui_ctx.is_using_pointer(),
-      //   };
-      //   EventResponse {
-      //     repaint: true,
-      //     consumed,
-      //   }
-      // }
+      Message::Touch(points) => {
+        let mut consumed = false;
+        for point in points {
+          self.on_touch(window, point);
+          consumed |= match point.phase {
+            TouchPhase::Started | TouchPhase::Ended | TouchPhase::Cancelled => {
+              self.egui_ctx.wants_pointer_input()
+            }
+            TouchPhase::Moved => self.egui_ctx.is_using_pointer(),
+          };
+        }
+        EventResponse {
+          repaint: true,
+          consumed,
+        }
+      }
 
-      // WindowEvent::Ime(ime) => {
-      //   // on Mac even Cmd-C is pressed during ime, a `c` is pushed to Preedit.
-      //   // So no need to check is_mac_cmd.
-      //   //
-      //   // How winit produce `Ime::Enabled` and `Ime::Disabled` differs in MacOS
-      //   // and Windows.
-      //   //
-      //   // - On Windows, before and after each Commit will produce an Enable/Disabled
-      //   // event.
-      //   // - On MacOS, only when user explicit enable/disable ime. No Disabled
-      //   // after Commit.
-      //   //
-      //   // We use input_method_editor_started to manually insert CompositionStart
-      //   // between Commits.
-      //   match ime {
-      //     winit::event::Ime::Enabled | winit::event::Ime::Disabled => (),
-      //     winit::event::Ime::Commit(text) => {
-      //       self.input_method_editor_started = false;
-      //       self
-      //         .egui_input
-      //         .events
-      //         .push(egui::Event::CompositionEnd(text.clone()));
-      //     }
-      //     winit::event::Ime::Preedit(text, Some(_)) => {
-      //       if !self.input_method_editor_started {
-      //         self.input_method_editor_started = true;
-      //         self.egui_input.events.push(egui::Event::CompositionStart);
-      //       }
-      //       self
-      //         .egui_input
-      //         .events
-      //         .push(egui::Event::CompositionUpdate(text.clone()));
-      //     }
-      //     winit::event::Ime::Preedit(_, None) => {}
-      //   };
+      // Windows ends every composition with an empty preedit, including after
+      // a commit, so CompositionStart is tracked to pair each start with one
+      // end
+      Message::ImeStarted => EventResponse {
+        repaint: false,
+        consumed: false,
+      },
+      Message::ImePreedit { text, cursor_range } => {
+        if cursor_range.is_some() || !text.is_empty() {
+          if !self.input_method_editor_started {
+            self.input_method_editor_started = true;
+            self.egui_input.events.push(egui::Event::CompositionStart);
+          }
+          self
+            .egui_input
+            .events
+            .push(egui::Event::CompositionUpdate(text.clone()));
+        } else if std::mem::take(&mut self.input_method_editor_started) {
+          // cancelled
+          self
+            .egui_input
+            .events
+            .push(egui::Event::CompositionEnd(String::new()));
+        }
 
-      //   EventResponse {
-      //     repaint: true,
-      //     consumed: self.egui_ctx.wants_keyboard_input(),
-      //   }
-      // }
+        EventResponse {
+          repaint: true,
+          consumed: self.egui_ctx.wants_keyboard_input(),
+        }
+      }
+      Message::ImeCommit(text) => {
+        if !std::mem::take(&mut self.input_method_editor_started) {
+          self.egui_input.events.push(egui::Event::CompositionStart);
+        }
+        self
+          .egui_input
+          .events
+          .push(egui::Event::CompositionEnd(text.clone()));
+
+        EventResponse {
+          repaint: true,
+          consumed: self.egui_ctx.wants_keyboard_input(),
+        }
+      }
       Message::Key { key, state, .. } => {
         self.on_keyboard_input(key, state);
         // When pressing the Tab key, egui focuses the first focusable element, hence
@@ -388,17 +495,20 @@ impl State {
       //     consumed: false,
       //   }
       // }
-      // WindowEvent::DroppedFile(path) => {
-      //   self.egui_input.hovered_files.clear();
-      //   self.egui_input.dropped_files.push(egui::DroppedFile {
-      //     path: Some(path.clone()),
-      //     ..Default::default()
-      //   });
-      //   EventResponse {
-      //     repaint: true,
-      //     consumed: false,
-      //   }
-      // }
+      Message::FilesDropped(paths) => {
+        self.egui_input.hovered_files.clear();
+        self
+          .egui_input
+          .dropped_files
+          .extend(paths.iter().map(|path| egui::DroppedFile {
+            path: Some(path.clone()),
+            ..Default::default()
+          }));
+        EventResponse {
+          repaint: true,
+          consumed: false,
+        }
+      }
       Message::ModifiersChanged { shift, ctrl, alt, win } => {
         self.egui_input.modifiers.alt = alt.is_pressed();
         self.egui_input.modifiers.ctrl = ctrl.is_pressed();
@@ -417,7 +527,7 @@ impl State {
       // | WindowEvent::Occluded(_)
       | Message::Resized(_)
       | Message::Moved(_)
-      // | WindowEvent::ThemeChanged(_)
+      | Message::ThemeChanged(_)
       // | WindowEvent::TouchpadPressure { .. }
       | Message::CloseRequested => EventResponse {
         repaint: true,
@@ -429,6 +539,12 @@ impl State {
         Message::RawInput(_) |
         Message::Created { .. } |
         Message::BoundsChanged { .. } |
+        Message::SurfaceInvalidated(_) |
+        Message::App { .. } |
+        Message::DismissRequested |
+        Message::AccentColorChanged(_) |
+        Message::SecondInstanceLaunched(_) |
+        Message::AppCommand(_) |
         Message::Command |
         Message::SystemCommand => EventResponse {
         repaint: false,
@@ -537,70 +653,34 @@ impl State {
     }
   }
 
-  // fn on_touch(&mut self, window: &Window, touch: &winit::event::Touch) {
-  //   let pixels_per_point = pixels_per_point(&self.egui_ctx, window);
-
-  //   // Emit touch event
-  //   self.egui_input.events.push(egui::Event::Touch {
-  //     device_id:
-  // egui::TouchDeviceId(egui::epaint::util::hash(touch.device_id)),
-  //     id: egui::TouchId::from(touch.id),
-  //     phase: match touch.phase {
-  //       winit::event::TouchPhase::Started => egui::TouchPhase::Start,
-  //       winit::event::TouchPhase::Moved => egui::TouchPhase::Move,
-  //       winit::event::TouchPhase::Ended => egui::TouchPhase::End,
-  //       winit::event::TouchPhase::Cancelled => egui::TouchPhase::Cancel,
-  //     },
-  //     pos: egui::pos2(
-  //       touch.location.x as f32 / pixels_per_point,
-  //       touch.location.y as f32 / pixels_per_point,
-  //     ),
-  //     force: match touch.force {
-  //       Some(winit::event::Force::Normalized(force)) => Some(force as f32),
-  //       Some(winit::event::Force::Calibrated {
-  //         force,
-  //         max_possible_force,
-  //         ..
-  //       }) => Some((force / max_possible_force) as f32),
-  //       None => None,
-  //     },
-  //   });
-  //   // If we're not yet translating a touch or we're translating this very
-  //   // touch …
-  //   if self.pointer_touch_id.is_none() || self.pointer_touch_id.unwrap() ==
-  // touch.id {     // … emit PointerButton resp. PointerMoved events to emulate
-  // mouse     match touch.phase {
-  //       winit::event::TouchPhase::Started => {
-  //         self.pointer_touch_id = Some(touch.id);
-  //         // First move the pointer to the right location
-  //         self.on_cursor_moved(window, touch.location);
-  //         self.on_mouse_button_input(
-  //           winit::event::ElementState::Pressed,
-  //           winit::event::MouseButton::Left,
-  //         );
-  //       }
-  //       winit::event::TouchPhase::Moved => {
-  //         self.on_cursor_moved(window, touch.location);
-  //       }
-  //       winit::event::TouchPhase::Ended => {
-  //         self.pointer_touch_id = None;
-  //         self.on_mouse_button_input(
-  //           winit::event::ElementState::Released,
-  //           winit::event::MouseButton::Left,
-  //         );
-  //         // The pointer should vanish completely to not get any
-  //         // hover effects
-  //         self.pointer_pos_in_points = None;
-  //         self.egui_input.events.push(egui::Event::PointerGone);
-  //       }
-  //       winit::event::TouchPhase::Cancelled => {
-  //         self.pointer_touch_id = None;
-  //         self.pointer_pos_in_points = None;
-  //         self.egui_input.events.push(egui::Event::PointerGone);
-  //       }
-  //     }
-  //   }
-  // }
+  /// Windows already turns the primary contact into mouse messages, so unlike
+  /// `egui-winit` this doesn't also move the pointer.
+  fn on_touch(&mut self, window: &Window, point: &TouchPoint) {
+    let pixels_per_point = pixels_per_point(&self.egui_ctx, window);
+    let position = match self.viewport_rect {
+      Some(rect) => PhysicalPosition::new(
+        point.position.x - rect.position.x,
+        point.position.y - rect.position.y,
+      ),
+      None => point.position,
+    };
+
+    self.egui_input.events.push(egui::Event::Touch {
+      device_id: egui::TouchDeviceId(0),
+      id: egui::TouchId(point.id as u64),
+      phase: match point.phase {
+        TouchPhase::Started => egui::TouchPhase::Start,
+        TouchPhase::Moved => egui::TouchPhase::Move,
+        TouchPhase::Ended => egui::TouchPhase::End,
+        TouchPhase::Cancelled => egui::TouchPhase::Cancel,
+      },
+      pos: egui::pos2(
+        position.x as f32 / pixels_per_point,
+        position.y as f32 / pixels_per_point,
+      ),
+      force: point.force,
+    });
+  }
 
   fn on_mouse_wheel(&mut self, window: &Window, delta_x: f32, delta_y: f32) {
     let pixels_per_point = pixels_per_point(&self.egui_ctx, window);
@@ -697,7 +777,7 @@ impl State {
   /// Call with the output given by `egui`.
   ///
   /// This will, if needed:
-  /// * update the cursor
+  /// * update the cursor (with the "egui-full" feature)
   /// * copy text to the clipboard
   /// * open any clicked urls
   /// * update the IME
@@ -716,7 +796,10 @@ impl State {
       ime,
     } = platform_output;
 
+    #[cfg(feature = "egui-full")]
     self.set_cursor_icon(window, cursor_icon);
+    #[cfg(not(feature = "egui-full"))]
+    let _ = cursor_icon;
 
     if let Some(open_url) = open_url {
       open_url_in_browser(&open_url.url);
@@ -758,6 +841,7 @@ impl State {
     // }
   }
 
+  #[cfg(feature = "egui-full")]
   fn set_cursor_icon(&mut self, window: &Window, cursor_icon: egui::CursorIcon) {
     if self.current_cursor_icon == Some(cursor_icon) {
       // Prevent flickering near frame boundary when Windows OS tries to control
@@ -874,18 +958,6 @@ fn open_url_in_browser(_url: &str) {
   }
 }
 
-/// Winit sends special keys (backspace, delete, F1, …) as characters.
-/// Ignore those.
-/// We also ignore '\r', '\n', '\t'.
-/// Newlines are handled by the `Key::Enter` event.
-fn is_printable_char(chr: char) -> bool {
-  let is_in_private_use_area = '\u{e000}' <= chr && chr <= '\u{f8ff}'
-    || '\u{f0000}' <= chr && chr <= '\u{ffffd}'
-    || '\u{100000}' <= chr && chr <= '\u{10fffd}';
-
-  !is_in_private_use_area && !chr.is_ascii_control()
-}
-
 fn is_cut_command(modifiers: egui::Modifiers, keycode: egui::Key) -> bool {
   keycode == egui::Key::Cut
     || (modifiers.command && keycode == egui::Key::X)
@@ -1269,8 +1341,7 @@ fn translate_cursor(cursor_icon: egui::CursorIcon) -> Option<CursorIcon> {
 //       egui::viewport::WindowLevel::Normal => WindowLevel::Normal,
 //     }),
 //     ViewportCommand::Icon(icon) => {
-//       let winit_icon = icon.and_then(|icon| to_winit_icon(&icon));
-//       window.set_window_icon(winit_icon);
+//       window.set_window_icon(icon.and_then(|icon| to_window_icon(&icon)));
 //     }
 //     ViewportCommand::IMERect(rect) => {
 //       window.set_ime_cursor_area(
@@ -1298,12 +1369,8 @@ fn translate_cursor(cursor_icon: egui::CursorIcon) -> Option<CursorIcon> {
 //     ViewportCommand::RequestUserAttention(a) => {
 //       window.request_user_attention(match a {
 //         egui::UserAttentionType::Reset => None,
-//         egui::UserAttentionType::Critical => {
-//           Some(winit::window::UserAttentionType::Critical)
-//         }
-//         egui::UserAttentionType::Informational => {
-//           Some(winit::window::UserAttentionType::Informational)
-//         }
+//         egui::UserAttentionType::Critical => Some(UserAttention::Critical),
+//         egui::UserAttentionType::Informational => Some(UserAttention::Informational),
 //       });
 //     }
 //     ViewportCommand::SetTheme(t) => window.set_theme(match t {
@@ -1511,19 +1578,21 @@ fn translate_cursor(cursor_icon: egui::CursorIcon) -> Option<CursorIcon> {
 //   window_builder
 // }
 
-// fn to_winit_icon(icon: &egui::IconData) -> Option<winit::window::Icon> {
-//   if icon.is_empty() {
-//     None
-//   } else {
-//     match winit::window::Icon::from_rgba(icon.rgba.clone(), icon.width, icon.height) {
-//       Ok(winit_icon) => Some(winit_icon),
-//       Err(err) => {
-//         log::warn!("Invalid IconData: {err}");
-//         None
-//       }
-//     }
-//   }
-// }
+/// Converts the icon of a `ViewportCommand::Icon` for
+/// [`Window::set_window_icon`]. [`None`] if it is empty or invalid.
+pub fn to_window_icon(icon: &egui::IconData) -> Option<Icon> {
+  if icon.is_empty() {
+    None
+  } else {
+    match Icon::from_rgba(icon.rgba.clone(), icon.width, icon.height) {
+      Ok(window_icon) => Some(window_icon),
+      Err(err) => {
+        tracing::warn!("Invalid IconData: {err}");
+        None
+      }
+    }
+  }
+}
 
 // /// Applies what `create_winit_window_builder` couldn't
 // pub fn apply_viewport_builder_to_window(
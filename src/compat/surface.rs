@@ -0,0 +1,56 @@
+//! Helpers for hooking up renderers that create their surface from raw
+//! handles instead of going through `wgpu`, such as Vulkan (`ash`), `glutin`,
+//! or `softbuffer`.
+//!
+//! Every helper checks that the window still exists first, since a surface
+//! created for a destroyed window fails in ways that are hard to trace back.
+
+use windows::Win32::UI::WindowsAndMessaging::IsWindow;
+
+use crate::{error::WindowError, Window};
+
+/// Fails with [`WindowError::AlreadyClosed`] if the window has been
+/// destroyed.
+fn validate(window: &Window) -> Result<(), WindowError> {
+  let hwnd = window.hwnd();
+  if hwnd.0 == 0 || window.hinstance().0 == 0 || !unsafe { IsWindow(hwnd) }.as_bool() {
+    return Err(WindowError::AlreadyClosed);
+  }
+  Ok(())
+}
+
+/// The `(hinstance, hwnd)` pair for `VkWin32SurfaceCreateInfoKHR`
+/// (`VK_KHR_win32_surface`), as the pointer-sized integers `ash` expects.
+///
+/// ```ignore
+/// let (hinstance, hwnd) = witer::compat::surface::vulkan_create_info(&window)?;
+/// let info = vk::Win32SurfaceCreateInfoKHR::default()
+///   .hinstance(hinstance)
+///   .hwnd(hwnd);
+/// ```
+pub fn vulkan_create_info(window: &Window) -> Result<(isize, isize), WindowError> {
+  validate(window)?;
+  Ok((window.hinstance().0, window.hwnd().0))
+}
+
+/// The raw window and display handles of the window, as `glutin` takes them to
+/// create a `WindowSurface`.
+#[cfg(all(feature = "rwh_06", not(feature = "rwh_05")))]
+pub fn raw_handles(
+  window: &Window,
+) -> Result<(rwh_06::RawWindowHandle, rwh_06::RawDisplayHandle), WindowError> {
+  validate(window)?;
+  Ok((window.raw_window_handle(), window.raw_display_handle()))
+}
+
+/// The raw window and display handles of the window, as `glutin` takes them to
+/// create a `WindowSurface`.
+#[cfg(all(feature = "rwh_05", not(feature = "rwh_06")))]
+pub fn raw_handles(
+  window: &Window,
+) -> Result<(rwh_05::RawWindowHandle, rwh_05::RawDisplayHandle), WindowError> {
+  use rwh_05::{HasRawDisplayHandle, HasRawWindowHandle};
+
+  validate(window)?;
+  Ok((window.raw_window_handle(), window.raw_display_handle()))
+}
@@ -12,6 +12,16 @@ pub enum WindowError {
   IOError(#[from] io::Error),
   #[error("{0}")]
   Win32Error(#[from] windows::core::Error),
+  #[error("window already closed")]
+  AlreadyClosed,
+  /// The message queue of the window thread is at its limit of 10,000
+  /// messages, so the command was dropped.
+  #[error("window command queue is full")]
+  CommandQueueFull,
+  /// The pixels given to [`Icon::from_rgba`](crate::Icon::from_rgba) don't
+  /// make up an icon.
+  #[error("invalid icon: {0}")]
+  InvalidIcon(String),
 }
 
 #[macro_export]
@@ -34,35 +34,72 @@ pub use rwh_05 as raw_window_handle;
 #[cfg(all(feature = "rwh_06", not(feature = "rwh_05")))]
 pub use rwh_06 as raw_window_handle;
 
+pub mod clipboard;
 pub mod compat;
 pub mod error;
 pub mod prelude;
+pub mod single_instance;
 pub mod utilities;
 pub mod window;
 
 // re-exports
 pub use window::{
+  cursor::CursorState,
   data::{
+    AccentColors,
+    ClickActivation,
+    Color,
+    CursorBehavior,
     CursorMode,
+    DpiAwareness,
+    EffectiveTheme,
+    FlashMode,
     Flow,
     Fullscreen,
     LogicalPosition,
     LogicalSize,
+    Margins,
     PhysicalPosition,
+    PhysicalRect,
     PhysicalSize,
+    PopupAnchor,
     Position,
+    PresentMode,
+    PresentStats,
     Size,
+    StallPolicy,
     Theme,
+    UserAttention,
+    VideoMode,
     Visibility,
+    WindowButtons,
   },
+  device_context::DeviceContext,
+  diagnostics::Diagnostics,
+  focus_gate::FocusGate,
+  icon::Icon,
   input::{
+    app_command::AppCommand,
     key::Key,
     mouse::MouseButton,
     state::{ButtonState, KeyState, RawKeyState},
     Input,
   },
-  message::{LoopMessage, Message, RawInputMessage},
+  message::{
+    ExitReason,
+    LoopMessage,
+    Message,
+    MessageFilter,
+    RawInputMessage,
+    SurfaceInvalidationReason,
+    TouchPhase,
+    TouchPoint,
+    APP_MESSAGE_LAST,
+  },
+  persistence::WindowPlacement,
+  region::Region,
   settings::{WindowBuilder, WindowSettings},
+  subscription::{Control, MessageKindSet, SubscriptionId},
   Window,
 };
 
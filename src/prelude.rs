@@ -1,25 +1,60 @@
 pub use crate::window::{
   self,
+  cursor::CursorState,
   data::{
+    AccentColors,
+    ClickActivation,
+    Color,
+    CursorBehavior,
     CursorMode,
+    DpiAwareness,
+    EffectiveTheme,
+    FlashMode,
     Flow,
     Fullscreen,
     LogicalPosition,
     LogicalSize,
+    Margins,
     PhysicalPosition,
+    PhysicalRect,
     PhysicalSize,
+    PopupAnchor,
     Position,
+    PresentMode,
+    PresentStats,
     Size,
+    StallPolicy,
     Theme,
+    UserAttention,
+    VideoMode,
     Visibility,
+    WindowButtons,
   },
+  device_context::DeviceContext,
+  diagnostics::Diagnostics,
+  focus_gate::FocusGate,
+  icon::Icon,
   input::{
+    app_command::AppCommand,
     key::Key,
     mouse::MouseButton,
     state::{ButtonState, KeyState, RawKeyState},
     Input,
   },
-  message::{LoopMessage, Message, RawInputMessage},
+  message::{
+    ExitReason,
+    LoopMessage,
+    Message,
+    MessageFilter,
+    RawInputMessage,
+    SurfaceInvalidationReason,
+    TouchPhase,
+    TouchPoint,
+    APP_MESSAGE_LAST,
+  },
+  persistence::WindowPlacement,
+  region::Region,
   settings::{WindowBuilder, WindowSettings},
+  subscription::{Control, MessageKindSet, SubscriptionId},
   Window,
 };
@@ -0,0 +1,317 @@
+//! Keeping an app to a single running instance, where later launches hand
+//! their arguments to the first one instead of opening windows of their own.
+//!
+//! ```no_run
+//! use witer::{prelude::*, single_instance};
+//!
+//! let instance = single_instance::claim("com.example.my-app")?;
+//! if !instance.is_primary() {
+//!   let args: Vec<String> = std::env::args().collect();
+//!   if instance.notify_existing(&args) {
+//!     return Ok(());
+//!   }
+//! }
+//!
+//! let window = Window::builder().with_single_instance(&instance).build()?;
+//! for message in &window {
+//!   if let Message::SecondInstanceLaunched(_args) = &message {
+//!     window.focus_window();
+//!   }
+//! }
+//! # Ok::<(), witer::error::WindowError>(())
+//! ```
+
+use std::ffi::c_void;
+
+use windows::{
+  core::{w, HSTRING, PCWSTR},
+  Win32::{
+    Foundation::{
+      CloseHandle,
+      GetLastError,
+      BOOL,
+      ERROR_ALREADY_EXISTS,
+      HANDLE,
+      HWND,
+      LPARAM,
+      WPARAM,
+    },
+    System::{
+      DataExchange::COPYDATASTRUCT,
+      Threading::{CreateMutexW, GetCurrentProcessId},
+    },
+    UI::WindowsAndMessaging::{
+      self,
+      AllowSetForegroundWindow,
+      EnumWindows,
+      GetPropW,
+      GetWindowThreadProcessId,
+      RegisterWindowMessageW,
+      SendMessageTimeoutW,
+    },
+  },
+};
+
+use crate::error::WindowError;
+
+/// The window property marking the windows which receive later launches. Its
+/// value is the registered message of the app.
+pub(crate) const INSTANCE_PROPERTY: PCWSTR = w!("witer-single-instance");
+
+/// How long a later launch waits for the first instance to take its arguments.
+const NOTIFY_TIMEOUT_MS: u32 = 5000;
+
+/// Claims `app_id` for this process, unless another process already holds it.
+/// The claim lasts until the returned [`SingleInstance`] is dropped, so keep it
+/// for as long as the app runs. The claim covers the current login session,
+/// and the id should be unique to the app, e.g. a reverse domain name.
+pub fn claim(app_id: &str) -> Result<SingleInstance, WindowError> {
+  // backslashes separate the namespace in kernel object names
+  let app_id = app_id.replace('\\', "/");
+  let mutex_name = HSTRING::from(format!("Local\\witer-single-instance-{app_id}"));
+  let mutex = unsafe { CreateMutexW(None, false, &mutex_name) }?;
+  // read before anything else can overwrite the last error
+  let primary = unsafe { GetLastError() } != ERROR_ALREADY_EXISTS;
+
+  let message_name = HSTRING::from(format!("witer-single-instance-{app_id}"));
+  let message_id = unsafe { RegisterWindowMessageW(&message_name) };
+  if message_id == 0 {
+    let error = windows::core::Error::from_win32();
+    let _ = unsafe { CloseHandle(mutex) };
+    return Err(error.into());
+  }
+
+  Ok(SingleInstance {
+    mutex,
+    primary,
+    message_id,
+  })
+}
+
+/// A claim on an app id, made with [`claim`]. See the [module](self) for an
+/// example.
+#[derive(Debug)]
+pub struct SingleInstance {
+  mutex: HANDLE,
+  primary: bool,
+  message_id: u32,
+}
+
+// the mutex handle is only ever closed
+unsafe impl Send for SingleInstance {}
+unsafe impl Sync for SingleInstance {}
+
+impl SingleInstance {
+  /// Whether this process was the first to claim the app id.
+  pub fn is_primary(&self) -> bool {
+    self.primary
+  }
+
+  pub(crate) fn message_id(&self) -> u32 {
+    self.message_id
+  }
+
+  /// Hands `args` to a window of the first instance, built with
+  /// [`WindowBuilder::with_single_instance`](crate::WindowBuilder::with_single_instance),
+  /// which receives them as
+  /// [`Message::SecondInstanceLaunched`](crate::Message::SecondInstanceLaunched).
+  /// It is also allowed to take the foreground, so it can bring itself to the
+  /// front with [`Window::focus_window`](crate::Window::focus_window).
+  ///
+  /// Returns `false` if no such window was found, e.g. because the first
+  /// instance is still starting up, or if it didn't respond in time.
+  pub fn notify_existing(&self, args: &[String]) -> bool {
+    let Some(hwnd) = find_instance_window(self.message_id) else {
+      return false;
+    };
+
+    let mut process_id = 0;
+    unsafe { GetWindowThreadProcessId(hwnd, Some(&mut process_id)) };
+    // fails if this process can't give the foreground away, which is fine
+    let _ = unsafe { AllowSetForegroundWindow(process_id) };
+
+    let mut bytes = encode_args(args);
+    let data = COPYDATASTRUCT {
+      dwData: self.message_id as usize,
+      cbData: bytes.len() as u32,
+      lpData: if bytes.is_empty() {
+        std::ptr::null_mut()
+      } else {
+        bytes.as_mut_ptr() as *mut c_void
+      },
+    };
+    let mut result = 0;
+    let sent = unsafe {
+      SendMessageTimeoutW(
+        hwnd,
+        WindowsAndMessaging::WM_COPYDATA,
+        WPARAM(0),
+        LPARAM(&data as *const COPYDATASTRUCT as isize),
+        WindowsAndMessaging::SMTO_ABORTIFHUNG,
+        NOTIFY_TIMEOUT_MS,
+        Some(&mut result),
+      )
+    };
+    sent.0 != 0 && result != 0
+  }
+}
+
+impl Drop for SingleInstance {
+  fn drop(&mut self) {
+    if let Err(error) = unsafe { CloseHandle(self.mutex) } {
+      tracing::error!("{error}");
+    }
+  }
+}
+
+/// A top level window of another process marked with `message_id`.
+fn find_instance_window(message_id: u32) -> Option<HWND> {
+  struct Search {
+    message_id: u32,
+    process_id: u32,
+    found: Option<HWND>,
+  }
+
+  extern "system" fn visit(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let search = unsafe { &mut *(lparam.0 as *mut Search) };
+    let marker = unsafe { GetPropW(hwnd, INSTANCE_PROPERTY) };
+    if marker.0 as u32 != search.message_id || marker.0 == 0 {
+      return true.into();
+    }
+    let mut process_id = 0;
+    unsafe { GetWindowThreadProcessId(hwnd, Some(&mut process_id)) };
+    if process_id == search.process_id {
+      return true.into();
+    }
+    search.found = Some(hwnd);
+    false.into()
+  }
+
+  let mut search = Search {
+    message_id,
+    process_id: unsafe { GetCurrentProcessId() },
+    found: None,
+  };
+  // stopping early is reported as an error
+  let _ =
+    unsafe { EnumWindows(Some(visit), LPARAM(&mut search as *mut Search as isize)) };
+  search.found
+}
+
+/// Every argument followed by a null byte, as UTF-8.
+pub(crate) fn encode_args(args: &[String]) -> Vec<u8> {
+  let mut bytes = Vec::with_capacity(args.iter().map(|arg| arg.len() + 1).sum());
+  for arg in args {
+    bytes.extend_from_slice(arg.as_bytes());
+    bytes.push(0);
+  }
+  bytes
+}
+
+/// Reverses [`encode_args`]. Invalid UTF-8 from a foreign sender is replaced
+/// rather than rejected.
+pub(crate) fn decode_args(bytes: &[u8]) -> Vec<String> {
+  if bytes.is_empty() {
+    return Vec::new();
+  }
+  bytes
+    .strip_suffix(&[0])
+    .unwrap_or(bytes)
+    .split(|&byte| byte == 0)
+    .map(|arg| String::from_utf8_lossy(arg).into_owned())
+    .collect()
+}
+
+/// The arguments of a `WM_COPYDATA` sent by [`SingleInstance::notify_existing`]
+/// to a window marked with `message_id`, or [`None`] if it is something else.
+pub(crate) fn read_instance_args(
+  data: &COPYDATASTRUCT,
+  message_id: u32,
+) -> Option<Vec<String>> {
+  if message_id == 0 || data.dwData != message_id as usize {
+    return None;
+  }
+  let bytes = if data.lpData.is_null() || data.cbData == 0 {
+    &[][..]
+  } else {
+    unsafe { std::slice::from_raw_parts(data.lpData as *const u8, data.cbData as usize) }
+  };
+  Some(decode_args(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+  }
+
+  fn copy_data(message_id: u32, bytes: &mut [u8]) -> COPYDATASTRUCT {
+    COPYDATASTRUCT {
+      dwData: message_id as usize,
+      cbData: bytes.len() as u32,
+      lpData: bytes.as_mut_ptr() as *mut c_void,
+    }
+  }
+
+  #[test]
+  fn round_trip() {
+    for args in [
+      args(&[]),
+      args(&[""]),
+      args(&["", ""]),
+      args(&["a", ""]),
+      args(&["", "a"]),
+      args(&["app.exe", "--open", "C:\\some file.txt"]),
+      args(&["ünïcödé", "日本語", "🦀"]),
+    ] {
+      assert_eq!(decode_args(&encode_args(&args)), args, "{args:?}");
+    }
+  }
+
+  #[test]
+  fn encoding() {
+    assert_eq!(encode_args(&args(&[])), b"");
+    assert_eq!(encode_args(&args(&[""])), b"\0");
+    assert_eq!(encode_args(&args(&["a", ""])), b"a\0\0");
+    assert_eq!(encode_args(&args(&["ab", "c"])), b"ab\0c\0");
+  }
+
+  #[test]
+  fn missing_final_null_is_accepted() {
+    assert_eq!(decode_args(b"a\0b"), args(&["a", "b"]));
+  }
+
+  #[test]
+  fn invalid_utf8_is_replaced() {
+    assert_eq!(decode_args(b"a\xFFb\0c\0"), args(&["a\u{FFFD}b", "c"]));
+  }
+
+  #[test]
+  fn reads_arguments_tagged_with_the_message_id() {
+    let mut bytes = encode_args(&args(&["a", ""]));
+    let data = copy_data(42, &mut bytes);
+    assert_eq!(read_instance_args(&data, 42), Some(args(&["a", ""])));
+  }
+
+  #[test]
+  fn reads_empty_arguments() {
+    let data = COPYDATASTRUCT {
+      dwData: 42,
+      cbData: 0,
+      lpData: std::ptr::null_mut(),
+    };
+    assert_eq!(read_instance_args(&data, 42), Some(args(&[])));
+  }
+
+  #[test]
+  fn ignores_other_copy_data() {
+    let mut bytes = encode_args(&args(&["a"]));
+    let data = copy_data(42, &mut bytes);
+    assert_eq!(read_instance_args(&data, 43), None);
+    // windows without a single instance have no message id
+    let data = copy_data(0, &mut bytes);
+    assert_eq!(read_instance_args(&data, 0), None);
+  }
+}
@@ -1,5 +1,8 @@
 use std::{
+  ffi::{OsStr, OsString},
   ops::BitAnd,
+  os::windows::ffi::{OsStrExt, OsStringExt},
+  path::PathBuf,
   sync::{
     atomic::{AtomicBool, Ordering},
     OnceLock,
@@ -8,30 +11,139 @@ use std::{
 
 use cursor_icon::CursorIcon;
 use windows::{
-  core::{PCSTR, PCWSTR},
+  core::{BSTR, GUID, HSTRING, PCSTR, PCWSTR, PROPVARIANT},
   Win32::{
-    Devices::HumanInterfaceDevice,
-    Foundation::{HWND, NTSTATUS, RECT},
-    Graphics::Gdi::{GetDC, GetMonitorInfoW, HMONITOR, MONITORINFO, MONITORINFOEXW},
+    Devices::{
+      Display::{
+        self,
+        DisplayConfigGetDeviceInfo,
+        GetDisplayConfigBufferSizes,
+        QueryDisplayConfig,
+        DISPLAYCONFIG_DEVICE_INFO_HEADER,
+        DISPLAYCONFIG_MODE_INFO,
+        DISPLAYCONFIG_PATH_INFO,
+        DISPLAYCONFIG_RATIONAL,
+        DISPLAYCONFIG_SOURCE_DEVICE_NAME,
+      },
+      HumanInterfaceDevice,
+    },
+    Foundation::{BOOL, HINSTANCE, HWND, NTSTATUS, POINT, RECT, RPC_E_CHANGED_MODE},
+    Graphics::{
+      Dwm::{self, DwmExtendFrameIntoClientArea, DwmSetWindowAttribute},
+      Gdi::{
+        self,
+        ChangeDisplaySettingsExW,
+        DeleteObject,
+        EnumDisplaySettingsW,
+        GetMonitorInfoW,
+        MapWindowPoints,
+        MonitorFromPoint,
+        MonitorFromRect,
+        ScreenToClient,
+        SetWindowRgn,
+        DEVMODEW,
+        HMONITOR,
+        HRGN,
+        MONITORINFO,
+        MONITORINFOEXW,
+      },
+    },
     System::{
+      Com::{
+        CoInitializeEx,
+        StructuredStorage::{PropVariantChangeType, PVCHF_DEFAULT},
+        COINIT_APARTMENTTHREADED,
+      },
       LibraryLoader::{GetProcAddress, LoadLibraryA},
       SystemInformation::OSVERSIONINFOW,
+      Threading::{
+        self,
+        AttachThreadInput,
+        GetCurrentProcess,
+        GetCurrentThreadId,
+        SetProcessInformation,
+        PROCESS_POWER_THROTTLING_CURRENT_VERSION,
+        PROCESS_POWER_THROTTLING_EXECUTION_SPEED,
+        PROCESS_POWER_THROTTLING_STATE,
+      },
+      Variant::VT_LPWSTR,
     },
     UI::{
-      HiDpi::{self, GetDpiForMonitor, GetDpiForWindow},
+      Controls,
+      HiDpi::{
+        self,
+        AdjustWindowRectExForDpi,
+        AreDpiAwarenessContextsEqual,
+        GetAwarenessFromDpiAwarenessContext,
+        GetDpiAwarenessContextForProcess,
+        GetDpiForMonitor,
+        GetDpiForWindow,
+        GetSystemMetricsForDpi,
+        GetWindowDpiAwarenessContext,
+        SetThreadDpiAwarenessContext,
+        DPI_AWARENESS_CONTEXT,
+      },
       Input::{
         self,
         GetRawInputData,
+        Ime::{
+          self,
+          ImmGetCompositionStringW,
+          ImmGetContext,
+          ImmReleaseContext,
+          IME_COMPOSITION_STRING,
+        },
+        KeyboardAndMouse::{self, TrackMouseEvent, TRACKMOUSEEVENT},
+        Pointer::{
+          self,
+          GetPointerInfo,
+          GetPointerPenInfo,
+          POINTER_INFO,
+          POINTER_PEN_INFO,
+        },
         RegisterRawInputDevices,
+        Touch::{
+          self,
+          CloseTouchInputHandle,
+          GetTouchInputInfo,
+          HTOUCHINPUT,
+          TOUCHINPUT,
+        },
         HRAWINPUT,
         RAWINPUT,
         RAWINPUTDEVICE,
         RAWINPUTHEADER,
       },
+      Shell::{
+        DragFinish,
+        DragQueryFileW,
+        PropertiesSystem::{IPropertyStore, SHGetPropertyStoreForWindow, PROPERTYKEY},
+        HDROP,
+      },
       WindowsAndMessaging::{
         self,
         ClipCursor,
+        EnableMenuItem,
+        FlashWindowEx,
+        GetClientRect,
+        GetCursorPos,
+        GetForegroundWindow,
+        GetSystemMenu,
+        GetWindow,
+        GetWindowPlacement,
+        GetWindowRect,
+        GetWindowThreadProcessId,
+        IsWindowVisible,
+        LoadCursorW,
+        SetCursor,
+        SetWindowPlacement,
+        SetWindowPos,
         ShowCursor,
+        WindowFromPoint,
+        FLASHWINFO,
+        FLASHWINFO_FLAGS,
+        SET_WINDOW_POS_FLAGS,
+        WINDOWPLACEMENT,
         WINDOW_EX_STYLE,
         WINDOW_STYLE,
       },
@@ -41,10 +153,23 @@ use windows::{
 };
 
 use crate::{
-  prelude::{PhysicalPosition, PhysicalSize},
+  error::WindowError,
+  prelude::{LogicalSize, PhysicalPosition, PhysicalRect, PhysicalSize},
   window::{
-    data::{Fullscreen, Visibility},
+    data::{
+      AccentColors,
+      Color,
+      CursorMode,
+      DpiAwareness,
+      EffectiveTheme,
+      Margins,
+      Theme,
+      VideoMode,
+      Visibility,
+    },
     frame::Style,
+    message::{TouchPhase, TouchPoint},
+    region::Region,
   },
 };
 
@@ -161,23 +286,68 @@ pub fn is_dark_mode_supported() -> bool {
   })
 }
 
+/// Queried on every call, since the user can switch modes at any time.
 pub fn is_system_dark_mode_enabled() -> bool {
-  static IS_SYSTEM_DARK_MODE: OnceLock<bool> = OnceLock::new();
-  *IS_SYSTEM_DARK_MODE.get_or_init(|| {
-    let settings = UISettings::new().unwrap();
-    let foreground = settings
-      .GetColorValue(UIColorType::Foreground)
-      .unwrap_or_default();
-    is_color_light(&foreground)
+  let Ok(settings) = UISettings::new() else {
+    return false;
+  };
+  let foreground = settings
+    .GetColorValue(UIColorType::Foreground)
+    .unwrap_or_default();
+  is_color_light(&foreground)
+}
+
+/// The system accent color, or the default Windows blue if it can't be
+/// queried. Queried on every call, like [`is_system_dark_mode_enabled`].
+pub fn system_accent_color() -> Color {
+  system_accent_colors()
+    .map(|colors| colors.accent)
+    .unwrap_or(Color::new(0, 120, 215))
+}
+
+/// The system accent color along with its light and dark shades. Returns
+/// [`None`] if `UISettings` is unavailable.
+pub fn system_accent_colors() -> Option<AccentColors> {
+  let settings = UISettings::new().ok()?;
+  let color = |color_type: UIColorType| -> Option<Color> {
+    let color = settings.GetColorValue(color_type).ok()?;
+    Some(Color::new(color.R, color.G, color.B))
+  };
+
+  Some(AccentColors {
+    accent: color(UIColorType::Accent)?,
+    light_1: color(UIColorType::AccentLight1)?,
+    light_2: color(UIColorType::AccentLight2)?,
+    light_3: color(UIColorType::AccentLight3)?,
+    dark_1: color(UIColorType::AccentDark1)?,
+    dark_2: color(UIColorType::AccentDark2)?,
+    dark_3: color(UIColorType::AccentDark3)?,
   })
 }
 
+/// Resolves the theme a window asked for to the one it is actually shown in.
+pub(crate) fn resolve_theme(theme: Theme) -> EffectiveTheme {
+  match theme {
+    Theme::Auto if is_system_dark_mode_enabled() => EffectiveTheme::Dark,
+    Theme::Dark if is_dark_mode_supported() => EffectiveTheme::Dark,
+    _ => EffectiveTheme::Light,
+  }
+}
+
 #[inline]
 fn is_color_light(clr: &windows::UI::Color) -> bool {
   ((5 * clr.G as u32) + (2 * clr.R as u32) + clr.B as u32) > (8 * 128)
 }
 
 pub(crate) fn get_window_style(info: &Style) -> WINDOW_STYLE {
+  if info.popup {
+    let mut style = WindowsAndMessaging::WS_POPUP | WindowsAndMessaging::WS_CLIPSIBLINGS;
+    if let Visibility::Shown = info.visibility {
+      style |= WindowsAndMessaging::WS_VISIBLE;
+    }
+    return style;
+  }
+
   let mut style = WindowsAndMessaging::WS_CAPTION
     | WindowsAndMessaging::WS_BORDER
     | WindowsAndMessaging::WS_CLIPSIBLINGS
@@ -193,11 +363,19 @@ pub(crate) fn get_window_style(info: &Style) -> WINDOW_STYLE {
     style |= WindowsAndMessaging::WS_VISIBLE;
   }
 
-  if let Some(Fullscreen::Borderless) = info.fullscreen {
+  if info.fullscreen.is_some() {
     style &= !WindowsAndMessaging::WS_OVERLAPPEDWINDOW;
     style |= WindowsAndMessaging::WS_POPUP;
   }
 
+  if !info.enabled_buttons.minimize {
+    style &= !WindowsAndMessaging::WS_MINIMIZEBOX;
+  }
+
+  if !info.enabled_buttons.maximize {
+    style &= !WindowsAndMessaging::WS_MAXIMIZEBOX;
+  }
+
   if let Visibility::Hidden = info.decorations {
     style &= !(WindowsAndMessaging::WS_CAPTION | WindowsAndMessaging::WS_BORDER);
   }
@@ -209,7 +387,7 @@ pub(crate) fn get_window_ex_style(info: &Style) -> WINDOW_EX_STYLE {
   let mut style =
     WindowsAndMessaging::WS_EX_WINDOWEDGE | WindowsAndMessaging::WS_EX_APPWINDOW;
 
-  if let Some(Fullscreen::Borderless) = info.fullscreen {
+  if info.fullscreen.is_some() {
     style &= !WindowsAndMessaging::WS_EX_OVERLAPPEDWINDOW;
   }
 
@@ -217,15 +395,305 @@ pub(crate) fn get_window_ex_style(info: &Style) -> WINDOW_EX_STYLE {
     style &= !WindowsAndMessaging::WS_EX_WINDOWEDGE;
   }
 
+  if !info.focusable {
+    style |= WindowsAndMessaging::WS_EX_NOACTIVATE;
+  }
+
+  if info.no_redirection_bitmap {
+    style |= WindowsAndMessaging::WS_EX_NOREDIRECTIONBITMAP;
+  }
+
+  // the window receives WM_DROPFILES
+  style |= WindowsAndMessaging::WS_EX_ACCEPTFILES;
+
+  if info.transparency_color_key.is_some() {
+    style |= WindowsAndMessaging::WS_EX_LAYERED;
+  }
+
+  // keeps popups off the taskbar and out of alt+tab
+  if info.popup {
+    style &=
+      !(WindowsAndMessaging::WS_EX_APPWINDOW | WindowsAndMessaging::WS_EX_WINDOWEDGE);
+    style |= WindowsAndMessaging::WS_EX_TOOLWINDOW;
+  }
+
   style
 }
 
+/// The outer size of a window with `style` whose client area is `inner_size`.
+pub(crate) fn outer_size_for_inner(
+  inner_size: PhysicalSize,
+  style: &Style,
+  dpi: u32,
+) -> PhysicalSize {
+  // the client area covers the frame
+  if style.borderless_shadow && !style.popup && style.fullscreen.is_none() {
+    return inner_size;
+  }
+
+  let mut window_rect = RECT {
+    top: 0,
+    left: 0,
+    right: inner_size.width as i32,
+    bottom: inner_size.height as i32,
+  };
+  if let Err(error) = unsafe {
+    AdjustWindowRectExForDpi(
+      &mut window_rect,
+      get_window_style(style),
+      false,
+      get_window_ex_style(style),
+      dpi,
+    )
+  } {
+    tracing::error!("{error}");
+    return inner_size;
+  }
+
+  PhysicalSize {
+    width: (window_rect.right - window_rect.left) as u32,
+    height: (window_rect.bottom - window_rect.top) as u32,
+  }
+}
+
+/// The width and height of the sizing frame of a resizeable window.
+pub(crate) fn sizing_frame(dpi: u32) -> (i32, i32) {
+  let padding =
+    unsafe { GetSystemMetricsForDpi(WindowsAndMessaging::SM_CXPADDEDBORDER, dpi) };
+  unsafe {
+    (
+      GetSystemMetricsForDpi(WindowsAndMessaging::SM_CXSIZEFRAME, dpi) + padding,
+      GetSystemMetricsForDpi(WindowsAndMessaging::SM_CYSIZEFRAME, dpi) + padding,
+    )
+  }
+}
+
+/// Covers `monitor_rect` with the window in a single `SetWindowPos`, raising
+/// it above the taskbar if it has focus. The style must already be updated so
+/// the frame change is applied in the same step. Some Windows 10 setups leave
+/// the window 1px short or under the taskbar; in that case the position is
+/// forced again without letting the window adjust it.
+pub(crate) fn set_borderless_fullscreen_pos(hwnd: HWND, monitor_rect: RECT) {
+  let is_foreground = unsafe { GetForegroundWindow() } == hwnd;
+  let (insert_after, z_flags) = if is_foreground {
+    (WindowsAndMessaging::HWND_TOP, SET_WINDOW_POS_FLAGS(0))
+  } else {
+    (HWND::default(), WindowsAndMessaging::SWP_NOZORDER)
+  };
+
+  let set_pos = |flags: SET_WINDOW_POS_FLAGS| unsafe {
+    SetWindowPos(
+      hwnd,
+      insert_after,
+      monitor_rect.left,
+      monitor_rect.top,
+      monitor_rect.right - monitor_rect.left,
+      monitor_rect.bottom - monitor_rect.top,
+      flags
+        | z_flags
+        | WindowsAndMessaging::SWP_FRAMECHANGED
+        | WindowsAndMessaging::SWP_NOOWNERZORDER,
+    )
+  };
+
+  if let Err(error) = set_pos(SET_WINDOW_POS_FLAGS(0)) {
+    tracing::error!("{error}");
+    return;
+  }
+
+  let mut window_rect = RECT::default();
+  let _ = unsafe { GetWindowRect(hwnd, &mut window_rect) };
+  if window_rect != monitor_rect {
+    tracing::debug!("fullscreen window does not cover the monitor, forcing position");
+    if let Err(error) = set_pos(WindowsAndMessaging::SWP_NOSENDCHANGING) {
+      tracing::error!("{error}");
+    }
+  }
+}
+
+/// Opts the process out of (or back into) execution speed throttling, which
+/// Windows 10 1709+ applies to processes it considers to be in the
+/// background. Fails on older versions, where there is nothing to opt out of.
+pub(crate) fn set_execution_speed_throttling(throttle: bool) {
+  let state = PROCESS_POWER_THROTTLING_STATE {
+    Version: PROCESS_POWER_THROTTLING_CURRENT_VERSION,
+    ControlMask: if throttle {
+      0 // let the system decide
+    } else {
+      PROCESS_POWER_THROTTLING_EXECUTION_SPEED
+    },
+    StateMask: 0,
+  };
+  if let Err(error) = unsafe {
+    SetProcessInformation(
+      GetCurrentProcess(),
+      Threading::ProcessPowerThrottling,
+      std::ptr::addr_of!(state) as *const std::ffi::c_void,
+      std::mem::size_of::<PROCESS_POWER_THROTTLING_STATE>() as u32,
+    )
+  } {
+    tracing::debug!("power throttling hint unsupported: {error}");
+  }
+}
+
+/// Maps a point within `border` pixels of the edges of `window_rect` to the
+/// matching resize hit-test code, or `HTCLIENT` otherwise.
+pub(crate) fn resize_border_hit_test(
+  window_rect: PhysicalRect,
+  point: PhysicalPosition,
+  border: i32,
+) -> u32 {
+  let left = point.x < window_rect.left() + border;
+  let right = point.x >= window_rect.right() - border;
+  let top = point.y < window_rect.top() + border;
+  let bottom = point.y >= window_rect.bottom() - border;
+
+  match (left, right, top, bottom) {
+    (true, _, true, _) => WindowsAndMessaging::HTTOPLEFT,
+    (_, true, true, _) => WindowsAndMessaging::HTTOPRIGHT,
+    (true, _, _, true) => WindowsAndMessaging::HTBOTTOMLEFT,
+    (_, true, _, true) => WindowsAndMessaging::HTBOTTOMRIGHT,
+    (true, ..) => WindowsAndMessaging::HTLEFT,
+    (_, true, ..) => WindowsAndMessaging::HTRIGHT,
+    (_, _, true, _) => WindowsAndMessaging::HTTOP,
+    (_, _, _, true) => WindowsAndMessaging::HTBOTTOM,
+    _ => WindowsAndMessaging::HTCLIENT,
+  }
+}
+
+/// The maximized bounds of a window on a monitor in the form `MINMAXINFO`
+/// expects: positioned relative to the monitor and covering its work area.
+/// `frame` is the invisible resize border the window keeps outside the work
+/// area while maximized.
+pub(crate) fn maximized_bounds(
+  monitor: PhysicalRect,
+  work_area: PhysicalRect,
+  frame: PhysicalSize,
+) -> PhysicalRect {
+  PhysicalRect::new(
+    PhysicalPosition::new(
+      work_area.left() - monitor.left() - frame.width as i32,
+      work_area.top() - monitor.top() - frame.height as i32,
+    ),
+    PhysicalSize::new(
+      work_area.size.width + 2 * frame.width,
+      work_area.size.height + 2 * frame.height,
+    ),
+  )
+}
+
+/// Maps a resize hit-test code to the cursor shown over it, or `None` for
+/// codes that don't resize the window.
+pub(crate) fn hit_test_cursor_icon(code: u32) -> Option<CursorIcon> {
+  match code {
+    WindowsAndMessaging::HTLEFT => Some(CursorIcon::WResize),
+    WindowsAndMessaging::HTRIGHT => Some(CursorIcon::EResize),
+    WindowsAndMessaging::HTTOP => Some(CursorIcon::NResize),
+    WindowsAndMessaging::HTBOTTOM => Some(CursorIcon::SResize),
+    WindowsAndMessaging::HTTOPLEFT => Some(CursorIcon::NwResize),
+    WindowsAndMessaging::HTTOPRIGHT => Some(CursorIcon::NeResize),
+    WindowsAndMessaging::HTBOTTOMLEFT => Some(CursorIcon::SwResize),
+    WindowsAndMessaging::HTBOTTOMRIGHT => Some(CursorIcon::SeResize),
+    _ => None,
+  }
+}
+
+/// `PKEY_AppUserModel_ID`
+const APP_USER_MODEL_ID_KEY: PROPERTYKEY = PROPERTYKEY {
+  fmtid: GUID::from_u128(0x9f4c2855_9f79_4b39_a8d0_e1d42de1d5f3),
+  pid: 5,
+};
+
+/// Sets or removes the AppUserModelID property of a single window, which
+/// decides its taskbar group, jump list, and pinning. Must be called on the
+/// window's thread.
+pub(crate) fn set_window_app_user_model_id(
+  hwnd: HWND,
+  id: Option<&str>,
+) -> windows::core::Result<()> {
+  // the property store is a COM object, so COM has to be up on this thread.
+  // a thread already in another apartment works just as well.
+  let result = unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED) };
+  if result.is_err() && result != RPC_E_CHANGED_MODE {
+    return Err(result.into());
+  }
+
+  let store: IPropertyStore = unsafe { SHGetPropertyStoreForWindow(hwnd) }?;
+  let value = match id {
+    Some(id) => {
+      // the shell only accepts VT_LPWSTR for this property
+      let mut value = PROPVARIANT::new();
+      unsafe {
+        PropVariantChangeType(
+          &mut value,
+          &PROPVARIANT::from(id),
+          PVCHF_DEFAULT,
+          VT_LPWSTR,
+        )
+      }?;
+      value
+    }
+    // an empty value removes the property
+    None => PROPVARIANT::new(),
+  };
+  unsafe {
+    store.SetValue(&APP_USER_MODEL_ID_KEY, &value)?;
+    store.Commit()
+  }
+}
+
+/// Reads back the AppUserModelID property of a window, if it has one.
+pub(crate) fn window_app_user_model_id(hwnd: HWND) -> Option<String> {
+  let store: IPropertyStore = unsafe { SHGetPropertyStoreForWindow(hwnd) }.ok()?;
+  let value = unsafe { store.GetValue(&APP_USER_MODEL_ID_KEY) }.ok()?;
+  if value.is_empty() {
+    return None;
+  }
+  BSTR::try_from(&value).ok().map(|id| id.to_string())
+}
+
 pub(crate) fn set_cursor_clip(rect: Option<&RECT>) {
   if let Err(_e) = unsafe { ClipCursor(rect.map(|r| r as _)) } {
     tracing::error!("{_e}");
   }
 }
 
+/// Confines the cursor to the client area of the window, or releases it.
+pub(crate) fn set_cursor_mode(hwnd: HWND, mode: CursorMode) {
+  match mode {
+    CursorMode::Normal => {
+      set_cursor_clip(None);
+    }
+    CursorMode::Confined => {
+      let mut client_rect = RECT::default();
+      if let Err(error) = unsafe { GetClientRect(hwnd, &mut client_rect) } {
+        tracing::error!("{error}");
+        return;
+      }
+      // the clip rect is in screen coordinates
+      let mut points = [
+        POINT {
+          x: client_rect.left,
+          y: client_rect.top,
+        },
+        POINT {
+          x: client_rect.right,
+          y: client_rect.bottom,
+        },
+      ];
+      unsafe { MapWindowPoints(hwnd, HWND::default(), &mut points) };
+      let screen_rect = RECT {
+        left: points[0].x,
+        top: points[0].y,
+        right: points[1].x,
+        bottom: points[1].y,
+      };
+
+      set_cursor_clip(Some(&screen_rect));
+    }
+  };
+}
+
 pub(crate) fn set_cursor_visibility(visible: Visibility) {
   let hidden = visible == Visibility::Hidden;
   static HIDDEN: AtomicBool = AtomicBool::new(false);
@@ -235,25 +703,376 @@ pub(crate) fn set_cursor_visibility(visible: Visibility) {
   }
 }
 
+pub(crate) fn extend_frame_into_client_area(hwnd: HWND, margins: Margins) {
+  let margins = Controls::MARGINS {
+    cxLeftWidth: margins.left,
+    cxRightWidth: margins.right,
+    cyTopHeight: margins.top,
+    cyBottomHeight: margins.bottom,
+  };
+  if let Err(_e) = unsafe { DwmExtendFrameIntoClientArea(hwnd, &margins) } {
+    tracing::error!("{_e}");
+  }
+}
+
+/// Shares the input state of the window's thread with the thread of its owner,
+/// if that is another thread, so mouse capture covers the owner's windows too.
+/// Returns the owner's thread to detach from later.
+pub(crate) fn attach_input_to_owner(hwnd: HWND) -> Option<u32> {
+  let owner = unsafe { GetWindow(hwnd, WindowsAndMessaging::GW_OWNER) };
+  if owner.0 == 0 {
+    return None;
+  }
+
+  let current_thread = unsafe { GetCurrentThreadId() };
+  let owner_thread = unsafe { GetWindowThreadProcessId(owner, None) };
+  if owner_thread == 0 || owner_thread == current_thread {
+    return None;
+  }
+
+  unsafe { AttachThreadInput(current_thread, owner_thread, true) }
+    .as_bool()
+    .then_some(owner_thread)
+}
+
+pub(crate) fn detach_input(thread: u32) {
+  let _ = unsafe { AttachThreadInput(GetCurrentThreadId(), thread, false) };
+}
+
+/// Clips the window to `region`, or restores the default rectangular region.
+pub(crate) fn set_window_region(hwnd: HWND, region: Option<&Region>, scale_factor: f64) {
+  let hrgn = match region {
+    Some(region) => match region.create(scale_factor) {
+      Some(hrgn) => hrgn,
+      None => {
+        tracing::error!("failed to create window region");
+        return;
+      }
+    },
+    None => HRGN::default(),
+  };
+
+  // the system owns the region once it is set
+  if unsafe { SetWindowRgn(hwnd, hrgn, true) } == 0 {
+    tracing::error!("{}", windows::core::Error::from_win32());
+    if !hrgn.is_invalid() {
+      unsafe { DeleteObject(hrgn) };
+    }
+  }
+}
+
+pub(crate) fn set_dark_titlebar(hwnd: HWND, dark: bool) {
+  let dark_mode = BOOL::from(dark);
+  if let Err(_error) = unsafe {
+    DwmSetWindowAttribute(
+      hwnd,
+      Dwm::DWMWA_USE_IMMERSIVE_DARK_MODE,
+      std::ptr::addr_of!(dark_mode) as *const std::ffi::c_void,
+      std::mem::size_of::<BOOL>() as u32,
+    )
+  } {
+    tracing::error!("{_error}");
+  }
+}
+
+/// Grays out the close button and the Close entry of the system menu. Alt+F4
+/// and other close requests still arrive as `WM_CLOSE`.
+pub(crate) fn set_close_button_enabled(hwnd: HWND, enabled: bool) {
+  let menu = unsafe { GetSystemMenu(hwnd, false) };
+  let flags = if enabled {
+    WindowsAndMessaging::MF_ENABLED
+  } else {
+    WindowsAndMessaging::MF_GRAYED
+  };
+  unsafe {
+    EnableMenuItem(
+      menu,
+      WindowsAndMessaging::SC_CLOSE,
+      WindowsAndMessaging::MF_BYCOMMAND | flags,
+    )
+  };
+}
+
+/// Switches the display named `device` to `mode` for as long as the process
+/// runs. A bit depth or refresh rate of `0` keeps the current one.
+pub(crate) fn set_display_mode(
+  device: &[u16; 32],
+  mode: VideoMode,
+) -> Result<(), WindowError> {
+  let mut devmode = DEVMODEW {
+    dmSize: std::mem::size_of::<DEVMODEW>() as u16,
+    dmPelsWidth: mode.size.width,
+    dmPelsHeight: mode.size.height,
+    dmFields: Gdi::DM_PELSWIDTH | Gdi::DM_PELSHEIGHT,
+    ..Default::default()
+  };
+  if mode.bit_depth != 0 {
+    devmode.dmBitsPerPel = mode.bit_depth as u32;
+    devmode.dmFields |= Gdi::DM_BITSPERPEL;
+  }
+  if mode.refresh_rate != 0 {
+    devmode.dmDisplayFrequency = mode.refresh_rate;
+    devmode.dmFields |= Gdi::DM_DISPLAYFREQUENCY;
+  }
+
+  let result = unsafe {
+    ChangeDisplaySettingsExW(
+      PCWSTR(device.as_ptr()),
+      Some(&devmode),
+      None,
+      Gdi::CDS_FULLSCREEN,
+      None,
+    )
+  };
+  if result == Gdi::DISP_CHANGE_SUCCESSFUL {
+    Ok(())
+  } else {
+    Err(WindowError::Error(format!(
+      "failed to change the display mode to {}x{} at {} bits and {} Hz: {}",
+      mode.size.width, mode.size.height, mode.bit_depth, mode.refresh_rate, result.0
+    )))
+  }
+}
+
+/// Reads one display mode of the display named `device`, or [`None`] past the
+/// last one.
+fn display_mode(
+  device: &[u16; 32],
+  index: Gdi::ENUM_DISPLAY_SETTINGS_MODE,
+) -> Option<VideoMode> {
+  let mut devmode = DEVMODEW {
+    dmSize: std::mem::size_of::<DEVMODEW>() as u16,
+    ..Default::default()
+  };
+  unsafe { EnumDisplaySettingsW(PCWSTR(device.as_ptr()), index, &mut devmode) }
+    .as_bool()
+    .then(|| VideoMode {
+      size: PhysicalSize::new(devmode.dmPelsWidth, devmode.dmPelsHeight),
+      bit_depth: devmode.dmBitsPerPel as u16,
+      // 0 and 1 both mean the hardware default
+      refresh_rate: match devmode.dmDisplayFrequency {
+        0 | 1 => 0,
+        hz => hz,
+      },
+    })
+}
+
+/// Reverts the display named `device` to the mode saved in the registry.
+pub(crate) fn restore_display_mode(device: &[u16; 32]) {
+  let result = unsafe {
+    ChangeDisplaySettingsExW(PCWSTR(device.as_ptr()), None, None, Gdi::CDS_TYPE(0), None)
+  };
+  if result != Gdi::DISP_CHANGE_SUCCESSFUL {
+    tracing::error!("failed to restore the display mode: {}", result.0);
+  }
+}
+
+/// Whether the cursor is over the client area of the window and not covered by
+/// another window.
+pub(crate) fn is_cursor_over_client_area(hwnd: HWND) -> bool {
+  let mut pt = POINT::default();
+  if unsafe { GetCursorPos(&mut pt) }.is_err() || unsafe { WindowFromPoint(pt) } != hwnd {
+    return false;
+  }
+  let mut rect = RECT::default();
+  if unsafe { GetClientRect(hwnd, &mut rect) }.is_err() {
+    return false;
+  }
+  let _ = unsafe { ScreenToClient(hwnd, &mut pt) };
+  PhysicalRect::from(rect).contains(PhysicalPosition::new(pt.x, pt.y))
+}
+
+/// Asks for `WM_MOUSELEAVE` once the cursor leaves the window.
+pub(crate) fn track_mouse_leave(hwnd: HWND) {
+  if let Err(error) = unsafe {
+    TrackMouseEvent(&mut TRACKMOUSEEVENT {
+      cbSize: std::mem::size_of::<TRACKMOUSEEVENT>() as u32,
+      dwFlags: KeyboardAndMouse::TME_LEAVE,
+      hwndTrack: hwnd,
+      dwHoverTime: Controls::HOVER_DEFAULT,
+    })
+  } {
+    tracing::error!("{error}");
+  }
+}
+
+/// Flashes the title bar and taskbar button. `FLASHW_STOP` stops any flashing
+/// and removes the highlight.
+pub(crate) fn flash_window(hwnd: HWND, flags: FLASHWINFO_FLAGS, count: u32) {
+  let info = FLASHWINFO {
+    cbSize: std::mem::size_of::<FLASHWINFO>() as u32,
+    hwnd,
+    dwFlags: flags,
+    uCount: count,
+    dwTimeout: 0,
+  };
+  // the return value is the previous flash state, not an error
+  unsafe { FlashWindowEx(&info) };
+}
+
+/// `WINDOWPLACEMENT` uses workspace coordinates, which are offset from screen
+/// coordinates by any taskbar docked to the top or left of the monitor.
+fn workspace_offset(rect: &RECT) -> PhysicalPosition {
+  let monitor =
+    Monitor::new(unsafe { MonitorFromRect(rect, Gdi::MONITOR_DEFAULTTONEAREST) });
+  let work_area = monitor.work_area();
+  let monitor_rect = monitor.rect();
+  PhysicalPosition::new(
+    work_area.left() - monitor_rect.left(),
+    work_area.top() - monitor_rect.top(),
+  )
+}
+
+fn window_placement(hwnd: HWND) -> Option<WINDOWPLACEMENT> {
+  let mut placement = WINDOWPLACEMENT {
+    length: std::mem::size_of::<WINDOWPLACEMENT>() as u32,
+    ..Default::default()
+  };
+  match unsafe { GetWindowPlacement(hwnd, &mut placement) } {
+    Ok(()) => Some(placement),
+    Err(e) => {
+      tracing::error!("{e}");
+      None
+    }
+  }
+}
+
+/// The outer bounds the window restores to, in screen coordinates.
+pub(crate) fn normal_rect(hwnd: HWND) -> Option<PhysicalRect> {
+  let rect = window_placement(hwnd)?.rcNormalPosition;
+  let offset = workspace_offset(&rect);
+  Some(PhysicalRect::from(RECT {
+    left: rect.left + offset.x,
+    top: rect.top + offset.y,
+    right: rect.right + offset.x,
+    bottom: rect.bottom + offset.y,
+  }))
+}
+
+/// Sets the outer bounds the window restores to without changing whether it
+/// is maximized, minimized, or hidden.
+pub(crate) fn set_normal_rect(hwnd: HWND, rect: PhysicalRect) {
+  let Some(mut placement) = window_placement(hwnd) else {
+    return;
+  };
+
+  let rect = RECT::from(rect);
+  let offset = workspace_offset(&rect);
+  placement.rcNormalPosition = RECT {
+    left: rect.left - offset.x,
+    top: rect.top - offset.y,
+    right: rect.right - offset.x,
+    bottom: rect.bottom - offset.y,
+  };
+  placement.flags = Default::default();
+  if !unsafe { IsWindowVisible(hwnd) }.as_bool() {
+    // the show state of a hidden window still reads as shown
+    placement.showCmd = WindowsAndMessaging::SW_HIDE.0 as u32;
+  }
+
+  if let Err(e) = unsafe { SetWindowPlacement(hwnd, &placement) } {
+    tracing::error!("{e}");
+  }
+}
+
+/// Whether a window with the outer bounds `rect` could still be grabbed by its
+/// title bar, i.e. the middle of its top edge is on a connected monitor.
+pub(crate) fn is_rect_on_screen(rect: PhysicalRect) -> bool {
+  let title_bar = POINT {
+    x: rect.left() + rect.size.width as i32 / 2,
+    y: rect.top() + (rect.size.height as i32).min(16),
+  };
+  !unsafe { MonitorFromPoint(title_bar, Gdi::MONITOR_DEFAULTTONULL) }.is_invalid()
+}
+
 pub const BASE_DPI: u32 = 96;
 
 pub fn dpi_to_scale_factor(dpi: u32) -> f64 {
   dpi as f64 / BASE_DPI as f64
 }
 
+/// The DPI of the window, or [`BASE_DPI`] if `hwnd` isn't a valid window,
+/// e.g. one that was destroyed in the meantime.
 pub fn hwnd_dpi(hwnd: HWND) -> u32 {
-  let hdc = unsafe { GetDC(hwnd) };
-  if hdc.is_invalid() {
-    panic!("device context was invalid");
-  }
-
   match unsafe { GetDpiForWindow(hwnd) } {
     0 => BASE_DPI, // 0 is returned if hwnd is invalid
     dpi => dpi,
   }
 }
 
-pub fn register_all_mice_and_keyboards_for_raw_input(hwnd: HWND) -> bool {
+/// Sets the DPI awareness of the calling thread, returning the previous
+/// context to restore, or `None` if nothing was changed.
+pub(crate) fn set_thread_dpi_awareness(
+  awareness: DpiAwareness,
+) -> Option<DPI_AWARENESS_CONTEXT> {
+  let contexts: &[DPI_AWARENESS_CONTEXT] = match awareness {
+    DpiAwareness::PerMonitorV2 => &[
+      HiDpi::DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2,
+      HiDpi::DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE,
+    ],
+    DpiAwareness::PerMonitor => &[HiDpi::DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE],
+    DpiAwareness::System => &[HiDpi::DPI_AWARENESS_CONTEXT_SYSTEM_AWARE],
+    DpiAwareness::Unaware => &[HiDpi::DPI_AWARENESS_CONTEXT_UNAWARE],
+    DpiAwareness::Inherit => &[],
+  };
+
+  for &context in contexts {
+    // null is returned if the context isn't supported by this version of
+    // windows
+    let previous = unsafe { SetThreadDpiAwarenessContext(context) };
+    if previous.0 != 0 {
+      return Some(previous);
+    }
+  }
+
+  if !contexts.is_empty() {
+    tracing::warn!("failed to set thread dpi awareness to {awareness:?}");
+  }
+
+  None
+}
+
+pub(crate) fn restore_thread_dpi_awareness(previous: Option<DPI_AWARENESS_CONTEXT>) {
+  if let Some(previous) = previous {
+    unsafe { SetThreadDpiAwarenessContext(previous) };
+  }
+}
+
+fn dpi_awareness_from_context(context: DPI_AWARENESS_CONTEXT) -> DpiAwareness {
+  if unsafe {
+    AreDpiAwarenessContextsEqual(
+      context,
+      HiDpi::DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2,
+    )
+  }
+  .as_bool()
+  {
+    return DpiAwareness::PerMonitorV2;
+  }
+
+  match unsafe { GetAwarenessFromDpiAwarenessContext(context) } {
+    HiDpi::DPI_AWARENESS_PER_MONITOR_AWARE => DpiAwareness::PerMonitor,
+    HiDpi::DPI_AWARENESS_SYSTEM_AWARE => DpiAwareness::System,
+    _ => DpiAwareness::Unaware,
+  }
+}
+
+/// The DPI awareness the window was created with.
+pub fn hwnd_dpi_awareness(hwnd: HWND) -> DpiAwareness {
+  dpi_awareness_from_context(unsafe { GetWindowDpiAwarenessContext(hwnd) })
+}
+
+/// The default DPI awareness of the process, used by threads which haven't
+/// set their own. Requires Windows 10 1803.
+pub fn process_dpi_awareness() -> DpiAwareness {
+  dpi_awareness_from_context(unsafe {
+    GetDpiAwarenessContextForProcess(GetCurrentProcess())
+  })
+}
+
+pub fn register_all_mice_and_keyboards_for_raw_input(
+  hwnd: HWND,
+) -> Result<(), WindowError> {
   // RIDEV_DEVNOTIFY: receive hotplug events
   // RIDEV_INPUTSINK: receive events even if we're not in the foreground
   // RIDEV_REMOVE: don't receive device events (requires NULL hwndTarget)
@@ -277,10 +1096,207 @@ pub fn register_all_mice_and_keyboards_for_raw_input(hwnd: HWND) -> bool {
   register_raw_input_devices(&devices)
 }
 
-pub fn register_raw_input_devices(devices: &[RAWINPUTDEVICE]) -> bool {
+pub fn register_raw_input_devices(devices: &[RAWINPUTDEVICE]) -> Result<(), WindowError> {
   let device_size = std::mem::size_of::<RAWINPUTDEVICE>() as u32;
 
-  unsafe { RegisterRawInputDevices(devices, device_size) }.is_err()
+  unsafe { RegisterRawInputDevices(devices, device_size) }?;
+  Ok(())
+}
+
+/// Reads the raw bytes of one part of the current IME composition.
+fn ime_composition_bytes(hwnd: HWND, kind: IME_COMPOSITION_STRING) -> Option<Vec<u8>> {
+  let himc = unsafe { ImmGetContext(hwnd) };
+  if himc.is_invalid() {
+    return None;
+  }
+  // a negative length is an error, like IMM_ERROR_NODATA
+  let len = unsafe { ImmGetCompositionStringW(himc, kind, None, 0) };
+  let bytes = (len >= 0).then(|| {
+    let mut buffer = vec![0u8; len as usize];
+    let copied = unsafe {
+      ImmGetCompositionStringW(himc, kind, Some(buffer.as_mut_ptr().cast()), len as u32)
+    };
+    buffer.truncate(copied.max(0) as usize);
+    buffer
+  });
+  unsafe { ImmReleaseContext(hwnd, himc) };
+  bytes
+}
+
+fn ime_composition_text(hwnd: HWND, kind: IME_COMPOSITION_STRING) -> Option<Vec<u16>> {
+  let bytes = ime_composition_bytes(hwnd, kind)?;
+  Some(
+    bytes
+      .chunks_exact(2)
+      .map(|pair| u16::from_ne_bytes([pair[0], pair[1]]))
+      .collect(),
+  )
+}
+
+/// The byte offset in `text` of the UTF-16 offset `index`.
+fn utf16_to_byte_offset(text: &str, index: usize) -> usize {
+  let mut utf16_len = 0;
+  for (offset, chr) in text.char_indices() {
+    if utf16_len >= index {
+      return offset;
+    }
+    utf16_len += chr.len_utf16();
+  }
+  text.len()
+}
+
+/// The committed text of the current IME composition.
+pub(crate) fn ime_result_text(hwnd: HWND) -> Option<String> {
+  let text = ime_composition_text(hwnd, Ime::GCS_RESULTSTR)?;
+  Some(String::from_utf16_lossy(&text))
+}
+
+/// The text being composed and the byte range of the clause being converted,
+/// or of the cursor if there is no such clause.
+pub(crate) fn ime_preedit(hwnd: HWND) -> Option<(String, Option<(usize, usize)>)> {
+  let wide = ime_composition_text(hwnd, Ime::GCS_COMPSTR)?;
+  let text = String::from_utf16_lossy(&wide);
+
+  // one attribute per UTF-16 unit
+  let attributes = ime_composition_bytes(hwnd, Ime::GCS_COMPATTR).unwrap_or_default();
+  let is_target = |attribute: &u8| {
+    matches!(
+      *attribute as u32,
+      Ime::ATTR_TARGET_CONVERTED | Ime::ATTR_TARGET_NOTCONVERTED
+    )
+  };
+  let target = attributes.iter().position(is_target).map(|start| {
+    let end = attributes[start..]
+      .iter()
+      .position(|attribute| !is_target(attribute))
+      .map_or(attributes.len(), |len| start + len);
+    (start, end)
+  });
+
+  let range = target
+    .or_else(|| {
+      let himc = unsafe { ImmGetContext(hwnd) };
+      if himc.is_invalid() {
+        return None;
+      }
+      let cursor = unsafe { ImmGetCompositionStringW(himc, Ime::GCS_CURSORPOS, None, 0) };
+      unsafe { ImmReleaseContext(hwnd, himc) };
+      (cursor >= 0).then_some((cursor as usize, cursor as usize))
+    })
+    .map(|(start, end)| {
+      (utf16_to_byte_offset(&text, start), utf16_to_byte_offset(&text, end))
+    });
+
+  Some((text, range))
+}
+
+/// Collects the paths of a `WM_DROPFILES` drop and releases it.
+pub(crate) fn read_dropped_files(hdrop: HDROP) -> Vec<PathBuf> {
+  // 0xFFFFFFFF queries the number of files
+  let count = unsafe { DragQueryFileW(hdrop, u32::MAX, None) };
+  let mut paths = Vec::with_capacity(count as usize);
+  for index in 0..count {
+    // the length excludes the null terminator
+    let len = unsafe { DragQueryFileW(hdrop, index, None) } as usize;
+    let mut buffer = vec![0u16; len + 1];
+    let copied = unsafe { DragQueryFileW(hdrop, index, Some(&mut buffer)) } as usize;
+    if copied == 0 {
+      continue;
+    }
+    paths.push(OsString::from_wide(&buffer[..copied]).into());
+  }
+  unsafe { DragFinish(hdrop) };
+  paths
+}
+
+/// Collects the `count` contacts of a `WM_TOUCH` message and closes its handle.
+pub(crate) fn read_touch_input(
+  hwnd: HWND,
+  handle: HTOUCHINPUT,
+  count: usize,
+) -> Vec<TouchPoint> {
+  let mut inputs = vec![TOUCHINPUT::default(); count];
+  let result = unsafe {
+    GetTouchInputInfo(handle, &mut inputs, std::mem::size_of::<TOUCHINPUT>() as i32)
+  };
+  if let Err(error) = unsafe { CloseTouchInputHandle(handle) } {
+    tracing::error!("{error}");
+  }
+  if let Err(error) = result {
+    tracing::error!("{error}");
+    return Vec::new();
+  }
+
+  inputs
+    .iter()
+    .filter_map(|input| {
+      let phase = if is_flag_set(input.dwFlags, Touch::TOUCHEVENTF_DOWN) {
+        TouchPhase::Started
+      } else if is_flag_set(input.dwFlags, Touch::TOUCHEVENTF_UP) {
+        TouchPhase::Ended
+      } else if is_flag_set(input.dwFlags, Touch::TOUCHEVENTF_MOVE) {
+        TouchPhase::Moved
+      } else {
+        return None;
+      };
+      // hundredths of a pixel in screen coordinates
+      let mut point = POINT {
+        x: (input.x as f64 / 100.0).round() as i32,
+        y: (input.y as f64 / 100.0).round() as i32,
+      };
+      unsafe { ScreenToClient(hwnd, &mut point) };
+      Some(TouchPoint {
+        id: input.dwID,
+        position: PhysicalPosition::new(point.x, point.y),
+        phase,
+        force: None,
+      })
+    })
+    .collect()
+}
+
+/// Reads the pen contact behind a `WM_POINTERDOWN`, `WM_POINTERUPDATE`, or
+/// `WM_POINTERUP`. [`None`] for other pointer types and for a pen hovering
+/// above the tablet.
+pub(crate) fn read_pen_pointer(
+  hwnd: HWND,
+  msg: u32,
+  pointer_id: u32,
+) -> Option<TouchPoint> {
+  let mut info = POINTER_INFO::default();
+  unsafe { GetPointerInfo(pointer_id, &mut info) }.ok()?;
+  if info.pointerType != WindowsAndMessaging::PT_PEN {
+    return None;
+  }
+
+  let flags = info.pointerFlags;
+  let phase = if is_flag_set(flags, Pointer::POINTER_FLAG_CANCELED) {
+    TouchPhase::Cancelled
+  } else if msg == WindowsAndMessaging::WM_POINTERDOWN {
+    TouchPhase::Started
+  } else if msg == WindowsAndMessaging::WM_POINTERUP {
+    TouchPhase::Ended
+  } else if is_flag_set(flags, Pointer::POINTER_FLAG_INCONTACT) {
+    TouchPhase::Moved
+  } else {
+    return None;
+  };
+
+  let mut pen_info = POINTER_PEN_INFO::default();
+  let force = unsafe { GetPointerPenInfo(pointer_id, &mut pen_info) }
+    .ok()
+    .filter(|_| is_flag_set(pen_info.penMask, WindowsAndMessaging::PEN_MASK_PRESSURE))
+    // reported from 0 to 1024
+    .map(|_| pen_info.pressure as f32 / 1024.0);
+
+  let mut point = info.ptPixelLocation;
+  unsafe { ScreenToClient(hwnd, &mut point) };
+  Some(TouchPoint {
+    id: pointer_id,
+    position: PhysicalPosition::new(point.x, point.y),
+    phase,
+    force,
+  })
 }
 
 pub fn read_raw_input(handle: HRAWINPUT) -> Option<RAWINPUT> {
@@ -305,6 +1321,51 @@ pub fn read_raw_input(handle: HRAWINPUT) -> Option<RAWINPUT> {
   Some(data)
 }
 
+/// Windows sends special keys (backspace, escape, …) as characters, and some
+/// keys produce characters in the private use areas. Returns `false` for
+/// those, as well as for '\r', '\n', and '\t'.
+pub fn is_printable_char(chr: char) -> bool {
+  let is_in_private_use_area = ('\u{e000}'..='\u{f8ff}').contains(&chr)
+    || ('\u{f0000}'..='\u{ffffd}').contains(&chr)
+    || ('\u{100000}'..='\u{10fffd}').contains(&chr);
+
+  !is_in_private_use_area && !chr.is_ascii_control()
+}
+
+/// Longest window text, in UTF-16 code units, that is shown in full. Longer
+/// text gets cut off by the title bar and taskbar wherever they see fit.
+const MAX_WINDOW_TEXT_LEN: usize = 255;
+
+/// Joins the title and subtitle with `separator` if both are non-empty, then
+/// shortens the result with an ellipsis if it is too long, never splitting a
+/// surrogate pair. Unpaired surrogates are kept as they are.
+pub(crate) fn compose_window_text(
+  title: &OsStr,
+  separator: &OsStr,
+  subtitle: &OsStr,
+) -> HSTRING {
+  let mut text: Vec<u16> = title.encode_wide().collect();
+  if !title.is_empty() && !subtitle.is_empty() {
+    text.extend(separator.encode_wide());
+  }
+  text.extend(subtitle.encode_wide());
+
+  if text.len() > MAX_WINDOW_TEXT_LEN {
+    // leave room for the ellipsis
+    text.truncate(MAX_WINDOW_TEXT_LEN - 1);
+    if text
+      .last()
+      .is_some_and(|unit| (0xD800..0xDC00).contains(unit))
+    {
+      // the low surrogate was cut off
+      text.pop();
+    }
+    text.push('…' as u16);
+  }
+
+  HSTRING::from_wide(&text).unwrap_or_default()
+}
+
 pub fn is_flag_set<T: Copy + BitAnd<T, Output = T> + PartialEq<T>>(
   var: T,
   flag: T,
@@ -312,13 +1373,55 @@ pub fn is_flag_set<T: Copy + BitAnd<T, Output = T> + PartialEq<T>>(
   (var & flag) == flag
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Monitor {
   hmonitor: HMONITOR,
+  cache: Option<MonitorCache>,
+}
+
+/// Monitor data captured at the time of the query.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct MonitorCache {
+  rect: PhysicalRect,
+  work_area: PhysicalRect,
+  scale_factor: f64,
+  refresh_rate: Option<u32>,
 }
 
 impl Monitor {
   pub fn new(hmonitor: HMONITOR) -> Self {
-    Self { hmonitor }
+    Self {
+      hmonitor,
+      cache: None,
+    }
+  }
+
+  /// Queries everything up front so later getters don't hit the system.
+  pub(crate) fn cached(hmonitor: HMONITOR) -> Self {
+    let monitor = Self::new(hmonitor);
+    let info = monitor.monitor_info();
+    Self {
+      cache: Some(MonitorCache {
+        rect: info
+          .map(|info| info.monitorInfo.rcMonitor.into())
+          .unwrap_or_default(),
+        work_area: info
+          .map(|info| info.monitorInfo.rcWork.into())
+          .unwrap_or_default(),
+        scale_factor: monitor.scale_factor(),
+        refresh_rate: monitor.refresh_rate(),
+      }),
+      ..monitor
+    }
+  }
+
+  pub(crate) fn hmonitor(&self) -> HMONITOR {
+    self.hmonitor
+  }
+
+  /// The GDI device name of the monitor, e.g. `\\.\DISPLAY1`.
+  pub(crate) fn device_name(&self) -> Option<[u16; 32]> {
+    self.monitor_info().map(|info| info.szDevice)
   }
 
   fn monitor_info(&self) -> Option<MONITORINFOEXW> {
@@ -338,41 +1441,203 @@ impl Monitor {
     }
   }
 
-  pub fn position(&self) -> PhysicalPosition {
-    let info = self.monitor_info();
-    info
-      .map(|info| {
-        let rect = info.monitorInfo.rcMonitor;
-        PhysicalPosition {
-          x: rect.left,
-          y: rect.top,
-        }
-      })
+  pub(crate) fn rect(&self) -> PhysicalRect {
+    if let Some(cache) = &self.cache {
+      return cache.rect;
+    }
+    self
+      .monitor_info()
+      .map(|info| info.monitorInfo.rcMonitor.into())
       .unwrap_or_default()
   }
 
+  pub fn position(&self) -> PhysicalPosition {
+    self.rect().position
+  }
+
   pub fn size(&self) -> PhysicalSize {
-    let info = self.monitor_info();
-    info
-      .map(|info| {
-        let rect = info.monitorInfo.rcMonitor;
-        PhysicalSize {
-          width: (rect.right - rect.left) as u32,
-          height: (rect.bottom - rect.top) as u32,
-        }
-      })
+    self.rect().size
+  }
+
+  /// The size of the monitor divided by its scale factor. This is the size to
+  /// lay out UI in while covering the monitor, whereas the swapchain or
+  /// surface should still be [`size`](Self::size) so that nothing is
+  /// upscaled. It isn't rounded, so at scale factors like `1.25` or `1.75` it
+  /// may not be a whole number.
+  pub fn size_logical(&self) -> LogicalSize {
+    let size = self.size();
+    let scale_factor = self.scale_factor();
+    LogicalSize::new(size.width as f64 / scale_factor, size.height as f64 / scale_factor)
+  }
+
+  /// The area of the monitor not covered by the taskbar or docked toolbars.
+  pub fn work_area(&self) -> PhysicalRect {
+    if let Some(cache) = &self.cache {
+      return cache.work_area;
+    }
+    self
+      .monitor_info()
+      .map(|info| info.monitorInfo.rcWork.into())
       .unwrap_or_default()
   }
 
+  /// The scale factor of the monitor, or `1.0` if the monitor was
+  /// disconnected in the meantime.
   pub fn scale_factor(&self) -> f64 {
+    if let Some(cache) = &self.cache {
+      return cache.scale_factor;
+    }
+
     let mut dpi_x = 0;
     let mut _dpi_y = 0;
-    unsafe {
+    match unsafe {
       GetDpiForMonitor(self.hmonitor, HiDpi::MDT_EFFECTIVE_DPI, &mut dpi_x, &mut _dpi_y)
+    } {
+      Ok(()) => dpi_to_scale_factor(dpi_x),
+      Err(_) => 1.0,
+    }
+  }
+
+  /// Every display mode the monitor supports, largest and fastest first.
+  pub fn video_modes(&self) -> impl Iterator<Item = VideoMode> {
+    let Some(info) = self.monitor_info() else {
+      return Vec::new().into_iter();
+    };
+
+    let mut modes = Vec::new();
+    for index in 0.. {
+      let Some(mode) =
+        display_mode(&info.szDevice, Gdi::ENUM_DISPLAY_SETTINGS_MODE(index))
+      else {
+        break;
+      };
+      // the same mode is listed again for each scaling and orientation
+      if !modes.contains(&mode) {
+        modes.push(mode);
+      }
+    }
+    modes.sort_by_key(|mode| {
+      std::cmp::Reverse((
+        mode.size.width,
+        mode.size.height,
+        mode.refresh_rate,
+        mode.bit_depth,
+      ))
+    });
+    modes.into_iter()
+  }
+
+  /// The display mode the monitor is in right now.
+  pub fn video_mode(&self) -> Option<VideoMode> {
+    let info = self.monitor_info()?;
+    display_mode(&info.szDevice, Gdi::ENUM_CURRENT_SETTINGS)
+  }
+
+  /// The refresh rate of the current display mode in hertz, or [`None`] if it
+  /// couldn't be queried or is the hardware default.
+  pub fn refresh_rate(&self) -> Option<u32> {
+    if let Some(cache) = &self.cache {
+      return cache.refresh_rate;
     }
-    .unwrap();
 
-    dpi_to_scale_factor(dpi_x)
+    let info = self.monitor_info()?;
+    let mut mode = DEVMODEW {
+      dmSize: std::mem::size_of::<DEVMODEW>() as u16,
+      ..Default::default()
+    };
+    let status = unsafe {
+      EnumDisplaySettingsW(
+        PCWSTR(info.szDevice.as_ptr()),
+        Gdi::ENUM_CURRENT_SETTINGS,
+        &mut mode,
+      )
+    };
+
+    // 0 and 1 both mean the hardware default
+    match mode.dmDisplayFrequency {
+      _ if !status.as_bool() => None,
+      0 | 1 => None,
+      hz => Some(hz),
+    }
+  }
+
+  /// The exact refresh rate of the current display mode in millihertz, e.g.
+  /// `59940` for 59.94 Hz, which [`refresh_rate`](Self::refresh_rate) rounds
+  /// to whole hertz. [`None`] if it couldn't be queried.
+  pub fn refresh_rate_millihertz(&self) -> Option<u32> {
+    let info = self.monitor_info()?;
+    display_config_refresh_rate(&info.szDevice)
+  }
+}
+
+/// The refresh rate in millihertz of the active display path whose source is
+/// the GDI device `device`, as the display configuration reports it.
+fn display_config_refresh_rate(device: &[u16]) -> Option<u32> {
+  let flags = Display::QDC_ONLY_ACTIVE_PATHS;
+  let (mut path_count, mut mode_count) = (0, 0);
+  unsafe { GetDisplayConfigBufferSizes(flags, &mut path_count, &mut mode_count) }
+    .ok()
+    .ok()?;
+  let mut paths = vec![DISPLAYCONFIG_PATH_INFO::default(); path_count as usize];
+  let mut modes = vec![DISPLAYCONFIG_MODE_INFO::default(); mode_count as usize];
+  unsafe {
+    QueryDisplayConfig(
+      flags,
+      &mut path_count,
+      paths.as_mut_ptr(),
+      &mut mode_count,
+      modes.as_mut_ptr(),
+      None,
+    )
+  }
+  .ok()
+  .ok()?;
+  paths.truncate(path_count as usize);
+
+  let trim = |name: &[u16]| {
+    name
+      .iter()
+      .take_while(|&&c| c != 0)
+      .copied()
+      .collect::<Vec<_>>()
+  };
+  let device = trim(device);
+  paths.iter().find_map(|path| {
+    let mut source = DISPLAYCONFIG_SOURCE_DEVICE_NAME {
+      header: DISPLAYCONFIG_DEVICE_INFO_HEADER {
+        r#type: Display::DISPLAYCONFIG_DEVICE_INFO_GET_SOURCE_NAME,
+        size: std::mem::size_of::<DISPLAYCONFIG_SOURCE_DEVICE_NAME>() as u32,
+        adapterId: path.sourceInfo.adapterId,
+        id: path.sourceInfo.id,
+      },
+      ..Default::default()
+    };
+    // returns a Win32 error code rather than an HRESULT
+    if unsafe { DisplayConfigGetDeviceInfo(&mut source.header) } != 0 {
+      return None;
+    }
+    if trim(&source.viewGdiDeviceName) != device {
+      return None;
+    }
+    rational_to_millihertz(path.targetInfo.refreshRate)
+  })
+}
+
+/// Converts a refresh rate given as a fraction of hertz to millihertz, rounded
+/// to the nearest.
+fn rational_to_millihertz(rate: DISPLAYCONFIG_RATIONAL) -> Option<u32> {
+  let numerator = rate.Numerator as u64 * 1000;
+  let denominator = rate.Denominator as u64;
+  (denominator != 0).then(|| ((numerator + denominator / 2) / denominator) as u32)
+}
+
+/// Loads the system cursor for `icon` and makes it the current cursor.
+pub(crate) fn set_os_cursor_icon(icon: CursorIcon) {
+  match unsafe { LoadCursorW(HINSTANCE::default(), to_windows_cursor(icon)) } {
+    Ok(hcursor) => {
+      unsafe { SetCursor(hcursor) };
+    }
+    Err(error) => tracing::error!("{error}"),
   }
 }
 
@@ -407,3 +1672,75 @@ pub(crate) fn to_windows_cursor(cursor: CursorIcon) -> PCWSTR {
     _ => WindowsAndMessaging::IDC_ARROW, // use arrow for the missing cases.
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn resize_codes_map_to_resize_cursors() {
+    let expected = [
+      (WindowsAndMessaging::HTLEFT, CursorIcon::WResize),
+      (WindowsAndMessaging::HTRIGHT, CursorIcon::EResize),
+      (WindowsAndMessaging::HTTOP, CursorIcon::NResize),
+      (WindowsAndMessaging::HTBOTTOM, CursorIcon::SResize),
+      (WindowsAndMessaging::HTTOPLEFT, CursorIcon::NwResize),
+      (WindowsAndMessaging::HTTOPRIGHT, CursorIcon::NeResize),
+      (WindowsAndMessaging::HTBOTTOMLEFT, CursorIcon::SwResize),
+      (WindowsAndMessaging::HTBOTTOMRIGHT, CursorIcon::SeResize),
+    ];
+    for (code, icon) in expected {
+      assert_eq!(hit_test_cursor_icon(code), Some(icon), "code {code}");
+    }
+  }
+
+  #[test]
+  fn other_codes_keep_the_cursor() {
+    for code in [
+      WindowsAndMessaging::HTCLIENT,
+      WindowsAndMessaging::HTCAPTION,
+      WindowsAndMessaging::HTNOWHERE,
+      WindowsAndMessaging::HTBORDER,
+    ] {
+      assert_eq!(hit_test_cursor_icon(code), None, "code {code}");
+    }
+  }
+
+  #[test]
+  fn resize_border_cursors() {
+    let window_rect =
+      PhysicalRect::new(PhysicalPosition::new(0, 0), PhysicalSize::new(100, 100));
+    let cursor_at = |x, y| {
+      hit_test_cursor_icon(resize_border_hit_test(
+        window_rect,
+        PhysicalPosition::new(x, y),
+        5,
+      ))
+    };
+
+    assert_eq!(cursor_at(0, 0), Some(CursorIcon::NwResize));
+    assert_eq!(cursor_at(99, 99), Some(CursorIcon::SeResize));
+    assert_eq!(cursor_at(4, 50), Some(CursorIcon::WResize));
+    assert_eq!(cursor_at(95, 50), Some(CursorIcon::EResize));
+    assert_eq!(cursor_at(50, 4), Some(CursorIcon::NResize));
+    assert_eq!(cursor_at(50, 95), Some(CursorIcon::SResize));
+    // just inside the border
+    assert_eq!(cursor_at(5, 50), None);
+    assert_eq!(cursor_at(94, 94), None);
+  }
+
+  #[test]
+  fn fractional_refresh_rates_in_millihertz() {
+    let rate = |numerator, denominator| {
+      rational_to_millihertz(DISPLAYCONFIG_RATIONAL {
+        Numerator: numerator,
+        Denominator: denominator,
+      })
+    };
+
+    assert_eq!(rate(60000, 1001), Some(59940));
+    assert_eq!(rate(144, 1), Some(144000));
+    assert_eq!(rate(59951, 1000), Some(59951));
+    assert_eq!(rate(0, 0), None);
+  }
+}
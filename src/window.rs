@@ -1,7 +1,16 @@
 use std::{
   collections::VecDeque,
-  sync::{mpsc::SyncSender, Arc, Condvar, Mutex},
+  ffi::{OsStr, OsString},
+  sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    mpsc::SyncSender,
+    Arc,
+    Condvar,
+    Mutex,
+    OnceLock,
+  },
   thread::JoinHandle,
+  time::{Duration, Instant},
 };
 
 use cursor_icon::CursorIcon;
@@ -31,12 +40,15 @@ use windows::{
   Win32::{
     Foundation::*,
     Graphics::{
-      Dwm::{self, DwmSetWindowAttribute},
+      Dwm::{DwmGetCompositionTimingInfo, DWM_TIMING_INFO},
       Gdi::{
         self,
+        ClientToScreen,
         EnumDisplayMonitors,
         MonitorFromPoint,
+        MonitorFromRect,
         MonitorFromWindow,
+        ScreenToClient,
         HDC,
         HMONITOR,
       },
@@ -44,22 +56,33 @@ use windows::{
     System::LibraryLoader::GetModuleHandleW,
     UI::{
       HiDpi::{
-        AdjustWindowRectExForDpi,
+        GetDpiForWindow,
+        GetSystemMetricsForDpi,
         SetProcessDpiAwarenessContext,
         DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE,
         DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2,
       },
+      Shell::SetCurrentProcessExplicitAppUserModelID,
       WindowsAndMessaging::{
         self,
         CreateWindowExW,
+        DestroyWindow,
         DispatchMessageW,
         GetClientRect,
         GetCursorPos,
         GetMessageW,
+        GetWindowLongW,
         GetWindowRect,
+        IsWindow,
         LoadCursorW,
+        MsgWaitForMultipleObjects,
+        PeekMessageW,
+        PostMessageW,
+        PostQuitMessage,
         RegisterClassExW,
         TranslateMessage,
+        UnregisterClassW,
+        WaitMessage,
         MSG,
         WNDCLASSEXW,
       },
@@ -69,28 +92,74 @@ use windows::{
 
 use self::{
   command::Command,
-  data::{CursorMode, Fullscreen, PhysicalSize, Position},
-  message::LoopMessage,
+  cursor::CursorState,
+  data::{
+    Color,
+    CursorBehavior,
+    CursorMode,
+    EffectiveTheme,
+    FlashMode,
+    Fullscreen,
+    LogicalPosition,
+    Margins,
+    PhysicalRect,
+    PhysicalSize,
+    PopupAnchor,
+    Position,
+    PresentMode,
+    PresentStats,
+    UserAttention,
+    WindowButtons,
+  },
+  device_context::DeviceContext,
+  diagnostics::Diagnostics,
+  focus_gate::FocusGate,
+  icon::Icon,
+  message::{
+    app_message_id,
+    filter_message,
+    ExitReason,
+    Focus,
+    LoopMessage,
+    MessageFilter,
+  },
   settings::WindowBuilder,
   stage::Stage,
+  subscription::{Control, MessageKindSet, SubscriptionId},
 };
 use crate::{
   error::WindowError,
   prelude::{ButtonState, Key, KeyState, MouseButton},
   utilities::{
+    compose_window_text,
     get_window_ex_style,
     get_window_style,
     hwnd_dpi,
-    is_dark_mode_supported,
-    is_system_dark_mode_enabled,
+    hwnd_dpi_awareness,
+    normal_rect,
+    outer_size_for_inner,
+    restore_thread_dpi_awareness,
+    set_execution_speed_throttling,
+    set_thread_dpi_awareness,
+    window_app_user_model_id,
     Monitor,
   },
   window::{
-    data::{Flow, Internal, PhysicalPosition, Size, SyncData, Theme, Visibility},
+    data::{
+      Data,
+      DpiAwareness,
+      Flow,
+      Internal,
+      PhysicalPosition,
+      Size,
+      SyncData,
+      Theme,
+      Visibility,
+    },
     frame::Style,
-    input::Input,
     message::Message,
     procedure::CreateInfo,
+    region::Region,
     settings::WindowSettings,
   },
 };
@@ -98,23 +167,86 @@ use crate::{
 mod command;
 pub mod cursor;
 pub mod data;
+pub mod device_context;
+pub mod diagnostics;
+pub mod focus_gate;
 pub mod frame;
+pub mod icon;
 pub mod input;
 pub mod message;
 pub mod monitor;
+pub mod persistence;
 pub mod procedure;
+pub mod region;
 pub mod settings;
 pub mod stage;
+pub mod subscription;
 
 /// Main window class. Uses internal mutability. Window is destroyed on drop. Cloning does not create a new window,
 /// but instead clones the smart pointer handle to the same window.
+///
+/// Shutting down always happens in this order:
+/// 1. [`Window::close`] or [`Window::confirm_close`] marks the window as
+///    closing.
+/// 2. The iterator yields [`LoopMessage::Exit`], then ends on the next call.
+///    The window still exists at this point, so surfaces can be torn down.
+/// 3. Once the last handle is dropped, `WM_DESTROY` runs on the window thread.
+/// 4. The window thread is joined before the drop returns.
+///
+/// Dropping the last handle at any point, including mid-iteration or right
+/// after [`LoopMessage::Exit`], skips ahead to step 3. The window is always
+/// destroyed and its thread joined on the thread that drops the last handle.
 #[allow(unused)]
-#[derive(Clone)]
 pub struct Window(Arc<Internal>);
 
+impl Clone for Window {
+  fn clone(&self) -> Self {
+    Self::new_handle(self.0.clone())
+  }
+}
+
+impl Drop for Window {
+  fn drop(&mut self) {
+    // the window procedure holds a reference of its own until the loop ends,
+    // so handles are counted instead of references. Only one drop can see the
+    // count reach zero, even when the last handles drop on different threads.
+    let is_last_handle = self.0.handles.fetch_sub(1, Ordering::AcqRel) == 1;
+    if is_last_handle && self.0.data.lock().unwrap().stage != Stage::Setup {
+      self.0.release_procedure();
+    }
+  }
+}
+
+impl std::fmt::Debug for Window {
+  /// Never blocks: if the window thread holds the data lock, the state is
+  /// printed as `<locked>` instead.
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let mut debug = f.debug_struct("Window");
+    debug
+      .field("hwnd", &self.0.hwnd)
+      .field("inner_size", &self.inner_size());
+    match self.0.data.try_lock() {
+      Ok(data) => debug
+        .field("title", &data.title)
+        .field("stage", &data.stage)
+        .field("style", &data.style)
+        .field("scale_factor", &data.scale_factor),
+      Err(_) => debug.field("data", &format_args!("<locked>")),
+    };
+    debug.finish()
+  }
+}
+
 impl Window {
   pub const WINDOW_SUBCLASS_ID: usize = 0;
 
+  /// Wraps `internal` in a new handle, counted towards
+  /// [`Internal::handles`].
+  pub(crate) fn new_handle(internal: Arc<Internal>) -> Self {
+    internal.handles.fetch_add(1, Ordering::Relaxed);
+    Self(internal)
+  }
+
   /// Create a new [`WindowBuilder`] to set up a [`Window`].
   ///
   /// [`WindowBuilder::with_size`] is relative to the whole window frame,
@@ -131,6 +263,66 @@ impl Window {
     position: impl Into<Option<Position>>,
     settings: WindowSettings,
   ) -> Result<Self, WindowError> {
+    let create_info = Self::create_info(title, size, position, settings, false);
+    let title = create_info.title.clone();
+
+    let (window_sender, window_receiver) = std::sync::mpsc::sync_channel(0);
+
+    let thread = Some(Self::window_loop(window_sender, create_info)?);
+
+    tracing::trace!("[`{}`]: waiting for window loop to hand back window", &title);
+
+    let window = match window_receiver.recv() {
+      Ok(Ok(window)) => window,
+      // the thread has nothing left to do after failing
+      Ok(Err(error)) => {
+        let _ = thread.and_then(|thread| thread.join().ok());
+        return Err(error);
+      }
+      Err(_) => {
+        return Err(WindowError::Error(
+          "window thread exited before creating the window".to_owned(),
+        ))
+      }
+    };
+
+    tracing::trace!("[`{}`]: received window from window loop", &title);
+
+    window.0.set_thread(thread);
+
+    tracing::trace!("[`{}`]: created window", &title);
+
+    Ok(window)
+  }
+
+  pub(crate) fn new_on_current_thread(
+    title: impl Into<String>,
+    size: impl Into<Size>,
+    position: impl Into<Option<Position>>,
+    settings: WindowSettings,
+  ) -> Result<Self, WindowError> {
+    let create_info = Self::create_info(title, size, position, settings, true);
+    let title = create_info.title.clone();
+
+    let window = Self::create_hwnd(create_info)?;
+
+    tracing::trace!("[`{}`]: created window on the current thread", &title);
+
+    Ok(window)
+  }
+
+  fn create_info(
+    title: impl Into<String>,
+    size: impl Into<Size>,
+    position: impl Into<Option<Position>>,
+    mut settings: WindowSettings,
+    current_thread: bool,
+  ) -> CreateInfo {
+    if settings.reveal_after_first_present {
+      // shown by `Window::notify_presented` instead
+      settings.visibility = Visibility::Hidden;
+    }
+
     let title: String = title.into();
     let size: Size = size.into();
     let position: Option<Position> = position.into();
@@ -141,57 +333,67 @@ impl Window {
       message: Arc::new(Mutex::new(None)),
       new_message: Arc::new((Mutex::new(false), Condvar::new())),
       next_frame: Arc::new((Mutex::new(false), Condvar::new())),
+      queue: Arc::new(Mutex::new(VecDeque::new())),
+      current_thread,
+      message_buffer: settings.message_buffer,
+      stall_timeout: settings.stall_timeout,
+      stall_policy: settings.stall_policy,
+      stalled: Arc::new(AtomicBool::new(false)),
+      focus_gate: FocusGate::default(),
     };
 
-    let create_info = CreateInfo {
-      title: title.clone(),
+    CreateInfo {
+      title,
       size,
       position,
       settings: settings.clone(),
+      class_name: String::new(),
       class_atom: 0,
       window: None,
-      sync: sync.clone(),
+      sync,
       style: Style {
         visibility: settings.visibility,
         decorations: settings.decorations,
+        borderless_shadow: settings.borderless_shadow,
         fullscreen: settings.fullscreen,
         resizeable: settings.resizeable,
+        enabled_buttons: settings.enabled_buttons,
+        focusable: settings.focusable,
+        popup: settings.popup,
+        no_redirection_bitmap: settings.no_redirection_bitmap,
+        transparency_color_key: settings.transparency_color_key,
         minimized: false,
         maximized: false,
         active: false,
         focused: false,
       },
-    };
-
-    let (window_sender, window_receiver) = std::sync::mpsc::sync_channel(0);
-
-    let thread = Some(Self::window_loop(window_sender, create_info)?);
-
-    tracing::trace!("[`{}`]: waiting for window loop to hand back window", &title);
-
-    let window = window_receiver.recv().unwrap();
-
-    tracing::trace!("[`{}`]: received window from window loop", &title);
-
-    window.0.set_thread(thread);
-
-    tracing::trace!("[`{}`]: created window", &title);
-
-    Ok(window)
+    }
   }
 
   fn window_loop(
-    window_sender: SyncSender<Self>,
+    window_sender: SyncSender<Result<Self, WindowError>>,
     create_info: CreateInfo,
   ) -> Result<JoinHandle<Result<(), WindowError>>, WindowError> {
     let thread_handle = std::thread::Builder::new()
       .name("window".to_owned())
       .spawn(move || -> Result<(), WindowError> {
+        procedure::OWNS_MESSAGE_PUMP.set(true);
         let title = create_info.title.clone();
-        let window = Self::create_hwnd(create_info)?;
+        let window = match Self::create_hwnd(create_info) {
+          Ok(window) => window,
+          Err(error) => {
+            tracing::trace!("[`{}`]: sending creation error to main thread", title);
+            window_sender
+              .send(Err(error))
+              .expect("failed to send window error");
+            return Ok(());
+          }
+        };
 
         tracing::trace!("[`{}`]: sending window back to main thread", title);
-        window_sender.send(window).expect("failed to send window");
+        window_sender
+          .send(Ok(window))
+          .expect("failed to send window");
 
         tracing::trace!("[`{}`]: pumping messages", title);
         while Self::message_pump() {}
@@ -208,40 +410,26 @@ impl Window {
 
     let hinstance: HINSTANCE = unsafe { GetModuleHandleW(None)? }.into();
     debug_assert_ne!(hinstance.0, 0);
-    let title = HSTRING::from(create_info.title.clone());
-    let window_class = title.clone();
-
-    let wc = WNDCLASSEXW {
-      cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
-      style: WindowsAndMessaging::CS_VREDRAW
-        | WindowsAndMessaging::CS_HREDRAW
-        | WindowsAndMessaging::CS_DBLCLKS
-        | WindowsAndMessaging::CS_OWNDC,
-      cbWndExtra: std::mem::size_of::<WNDCLASSEXW>() as i32,
-      lpfnWndProc: Some(procedure::wnd_proc),
-      hInstance: hinstance,
-      hCursor: unsafe { LoadCursorW(None, WindowsAndMessaging::IDC_ARROW)? },
-      lpszClassName: PCWSTR(window_class.as_ptr()),
-      ..Default::default()
-    };
+    let title =
+      compose_window_text(OsStr::new(&create_info.title), OsStr::new(""), OsStr::new(""));
+
+    let owner = create_info.settings.owner.unwrap_or_default();
+    if create_info.settings.owner.is_some() && !unsafe { IsWindow(owner) }.as_bool() {
+      return Err(WindowError::AlreadyClosed);
+    }
 
     tracing::trace!("[`{}`]: registering window class", &create_info.title);
 
-    {
-      create_info.class_atom = unsafe { RegisterClassExW(&wc) };
-      debug_assert_ne!(create_info.class_atom, 0);
-    }
+    let (window_class, class_atom) = Self::register_class(hinstance)?;
+    create_info.class_name = window_class.to_string_lossy();
+    create_info.class_atom = class_atom;
 
     tracing::trace!("[`{}`]: creating window handle", &create_info.title);
 
-    if unsafe {
-      SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2)
-    }
-    .is_err()
-    {
-      unsafe { SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE) }
-        .unwrap();
-    }
+    Self::set_process_dpi_awareness();
+    // the window keeps the awareness it was created with, so the thread can go
+    // back to what it had right after
+    let previous_awareness = set_thread_dpi_awareness(create_info.settings.dpi_awareness);
 
     let hwnd = unsafe {
       CreateWindowExW(
@@ -253,28 +441,124 @@ impl Window {
         WindowsAndMessaging::CW_USEDEFAULT,
         WindowsAndMessaging::CW_USEDEFAULT,
         WindowsAndMessaging::CW_USEDEFAULT,
-        None,
+        owner,
         None,
         hinstance,
         Some(std::ptr::addr_of_mut!(create_info) as _),
       )
     };
 
+    restore_thread_dpi_awareness(previous_awareness);
+
     tracing::trace!("[`{}`]: window handle created", &create_info.title);
 
-    if hwnd.0 == 0 {
-      Err(WindowError::Win32Error(windows::core::Error::from_win32()))
-    } else {
-      let window = create_info.window.take().unwrap();
+    // read before anything else can overwrite the last error
+    let create_error = (hwnd.0 == 0).then(windows::core::Error::from_win32);
+
+    match (create_info.window.take(), create_error) {
+      (Some(window), None) => Ok(window),
+      (window, create_error) => {
+        tracing::trace!("[`{}`]: rolling back window creation", &create_info.title);
+
+        if let Some(window) = window {
+          // the handle is already gone, so there's nothing for the window's
+          // own teardown to do
+          let mut data = window.0.data.lock().unwrap();
+          data.stage = Stage::Destroyed;
+          data.procedure_released = true;
+        }
+        if hwnd.0 != 0 {
+          if let Err(error) = unsafe { DestroyWindow(hwnd) } {
+            tracing::error!("{error}");
+          }
+        }
+        Self::unregister_class(class_atom, hinstance);
+
+        Err(match create_error {
+          Some(error) => WindowError::Win32Error(error),
+          None => WindowError::Error(
+            "window creation stopped before the window was ready".to_owned(),
+          ),
+        })
+      }
+    }
+  }
+
+  fn unregister_class(class_atom: u16, hinstance: HINSTANCE) {
+    if let Err(error) =
+      unsafe { UnregisterClassW(PCWSTR(class_atom as *const u16), hinstance) }
+    {
+      tracing::error!("{error}");
+    }
+  }
 
-      Ok(window)
+  /// Registers a new window class, namespaced to this copy of the crate so it
+  /// doesn't collide with classes registered by other libraries (or other
+  /// copies of `witer`) in the same process.
+  fn register_class(hinstance: HINSTANCE) -> Result<(HSTRING, u16), WindowError> {
+    static CLASS_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    loop {
+      let id = CLASS_COUNTER.fetch_add(1, Ordering::Relaxed);
+      let class_name = HSTRING::from(format!(
+        "witer-{}-{:p}-{id}",
+        env!("CARGO_PKG_VERSION"),
+        &CLASS_COUNTER
+      ));
+
+      let wc = WNDCLASSEXW {
+        cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+        style: WindowsAndMessaging::CS_VREDRAW
+          | WindowsAndMessaging::CS_HREDRAW
+          | WindowsAndMessaging::CS_DBLCLKS
+          | WindowsAndMessaging::CS_OWNDC,
+        cbWndExtra: std::mem::size_of::<WNDCLASSEXW>() as i32,
+        lpfnWndProc: Some(procedure::wnd_proc),
+        hInstance: hinstance,
+        hCursor: unsafe { LoadCursorW(None, WindowsAndMessaging::IDC_ARROW)? },
+        lpszClassName: PCWSTR(class_name.as_ptr()),
+        ..Default::default()
+      };
+
+      let class_atom = unsafe { RegisterClassExW(&wc) };
+      if class_atom != 0 {
+        return Ok((class_name, class_atom));
+      }
+
+      let error = windows::core::Error::from_win32();
+      if error.code() != ERROR_CLASS_ALREADY_EXISTS.to_hresult() {
+        return Err(WindowError::Win32Error(error));
+      }
+
+      tracing::warn!("window class `{class_name}` already exists, retrying");
     }
   }
 
+  /// Sets the DPI awareness of the process once. If the host process has
+  /// already set it, the existing awareness is kept.
+  fn set_process_dpi_awareness() {
+    static DPI_AWARENESS: OnceLock<()> = OnceLock::new();
+    DPI_AWARENESS.get_or_init(|| {
+      if unsafe {
+        SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2)
+      }
+      .is_err()
+      {
+        if let Err(_error) = unsafe {
+          SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE)
+        } {
+          tracing::warn!("failed to set process dpi awareness: {_error}");
+        }
+      }
+    });
+  }
+
   fn message_pump() -> bool {
     let mut msg = MSG::default();
     if unsafe { GetMessageW(&mut msg, None, 0, 0).as_bool() } {
       unsafe {
+        // queues WM_CHAR behind the key message being dispatched, which is what
+        // guarantees `Message::Key` is delivered before its `Message::Text`
         TranslateMessage(&msg);
         DispatchMessageW(&msg);
       }
@@ -284,49 +568,280 @@ impl Window {
     }
   }
 
-  fn take_message(&self) -> Option<Message> {
+  /// Dispatches all pending messages of the current thread without blocking.
+  /// Returns `false` if the thread is quitting, in which case the quit message
+  /// is posted again for the owner of the thread.
+  fn peek_message_pump() -> bool {
+    let mut msg = MSG::default();
+    while unsafe { PeekMessageW(&mut msg, None, 0, 0, WindowsAndMessaging::PM_REMOVE) }
+      .as_bool()
+    {
+      if msg.message == WindowsAndMessaging::WM_QUIT {
+        unsafe { PostQuitMessage(msg.wParam.0 as i32) };
+        return false;
+      }
+      unsafe {
+        TranslateMessage(&msg);
+        DispatchMessageW(&msg);
+      }
+    }
+    true
+  }
+
+  /// How long a [`Flow::Poll`] loop should wait for a message, if at all.
+  fn minimized_poll_wait(&self) -> Option<Duration> {
+    let data = self.0.data.lock().unwrap();
+    let interval = data.minimized_poll_interval;
+    let throttle =
+      matches!(data.flow, Flow::Poll) && data.style.minimized && !interval.is_zero();
+    throttle.then_some(interval)
+  }
+
+  /// [`LoopMessage::Idle`] if it wasn't sent since the last message and no
+  /// redraw is pending. Only call this once the queue is empty.
+  fn idle_message(&self) -> Option<Message> {
+    let mut data = self.0.data.lock().unwrap();
+    if data.idle || data.requested_redraw {
+      return None;
+    }
+    data.idle = true;
+    Some(Message::Loop(LoopMessage::Idle))
+  }
+
+  fn take_queued_message(&self, timeout: Option<Duration>) -> Option<Message> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let mut throttled = false;
+    loop {
+      if let Some(message) = self.0.sync.take_queued() {
+        return Some(message);
+      }
+
+      if !Self::peek_message_pump() {
+        // the thread is quitting, so the window can't keep pumping messages
+        self.close_with(ExitReason::AppRequested);
+        return Some(Message::Loop(LoopMessage::Empty));
+      }
+
+      if self.0.sync.has_queued() {
+        continue;
+      }
+
+      if let Some(idle) = self.idle_message() {
+        return Some(idle);
+      }
+
+      let flow = self.0.data.lock().unwrap().flow;
+      match (flow, deadline) {
+        (Flow::Wait, None) => {
+          if let Err(_error) = unsafe { WaitMessage() } {
+            tracing::error!("{_error}");
+            return Some(Message::Loop(LoopMessage::Empty));
+          }
+        }
+        (Flow::Wait, Some(deadline)) => {
+          let remaining = deadline.saturating_duration_since(Instant::now());
+          let millis = remaining.as_millis().min(u32::MAX as u128 - 1) as u32;
+          let result = unsafe {
+            MsgWaitForMultipleObjects(
+              None,
+              false,
+              millis,
+              WindowsAndMessaging::QS_ALLINPUT,
+            )
+          };
+          if result == WAIT_TIMEOUT {
+            return None;
+          }
+        }
+        (Flow::Poll, _) => match self.minimized_poll_wait() {
+          Some(interval) if !throttled => {
+            throttled = true;
+            let millis = interval.as_millis().min(u32::MAX as u128 - 1) as u32;
+            unsafe {
+              MsgWaitForMultipleObjects(
+                None,
+                false,
+                millis,
+                WindowsAndMessaging::QS_ALLINPUT,
+              )
+            };
+          }
+          _ => return Some(Message::Loop(LoopMessage::Empty)),
+        },
+      }
+    }
+  }
+
+  /// Returns `None` if `timeout` elapsed before a message arrived.
+  fn take_message(&self, timeout: Option<Duration>) -> Option<Message> {
+    if self.0.sync.current_thread {
+      return self.take_queued_message(timeout);
+    }
+
+    if !self.0.sync.has_queued() {
+      if let Some(idle) = self.idle_message() {
+        return Some(idle);
+      }
+    }
+
     let flow = self.0.data.lock().unwrap().flow;
     if let Flow::Wait = flow {
       let (lock, cvar) = self.0.sync.new_message.as_ref();
-      let mut new = cvar.wait_while(lock.lock().unwrap(), |new| !*new).unwrap();
+      let mut new = match timeout {
+        Some(timeout) => {
+          let (new, result) = cvar
+            .wait_timeout_while(lock.lock().unwrap(), timeout, |new| {
+              !*new && !self.0.sync.has_queued()
+            })
+            .unwrap();
+          if result.timed_out() {
+            return None;
+          }
+          new
+        }
+        None => cvar
+          .wait_while(lock.lock().unwrap(), |new| !*new && !self.0.sync.has_queued())
+          .unwrap(),
+      };
+      *new = false;
+    } else if let Some(interval) = self.minimized_poll_wait() {
+      let (lock, cvar) = self.0.sync.new_message.as_ref();
+      let (mut new, _) = cvar
+        .wait_timeout_while(lock.lock().unwrap(), interval, |new| {
+          !*new && !self.0.sync.has_queued()
+        })
+        .unwrap();
       *new = false;
     }
 
     self
       .0
       .sync
-      .message
-      .lock()
-      .unwrap()
-      .take()
+      .take_queued()
       .or(Some(Message::Loop(LoopMessage::Empty)))
   }
 
+  /// Like iterating over the window, but in [`Flow::Wait`] gives up after
+  /// `timeout` and returns `None`, so periodic work can run without polling.
+  /// Also returns `None` once the loop has exited, which can be told apart
+  /// with [`Window::is_closing`].
+  pub fn next_message_timeout(&self, timeout: Duration) -> Option<Message> {
+    self.next_message_inner(Some(timeout))
+  }
+
+  /// Queues messages describing the current state of the window, so it can be
+  /// set up from the message stream alone. [`Message::Created`] stays first.
+  fn queue_initial_state_messages(&self) {
+    if !self.0.data.lock().unwrap().initial_state_messages {
+      return;
+    }
+
+    let created = self.0.sync.message.lock().unwrap().take();
+    let scale_factor = self.scale_factor();
+    let inner_size = self.inner_size();
+    let focus = if self.has_focus() {
+      Focus::Gained
+    } else {
+      Focus::Lost
+    };
+
+    let mut data = self.0.data.lock().unwrap();
+    data.initial_messages.extend(created);
+    data.initial_messages.extend([
+      Message::ScaleFactorChanged(scale_factor),
+      Message::Resized(inner_size),
+      Message::Focus(focus),
+    ]);
+  }
+
   fn next_message(&self) -> Option<Message> {
-    let current_stage = self.0.data.lock().unwrap().stage;
+    self.next_message_inner(None)
+  }
+
+  fn next_message_inner(&self, timeout: Option<Duration>) -> Option<Message> {
+    assert!(
+      !self.0.data.lock().unwrap().subscriptions.is_dispatching(),
+      "message callbacks may not take messages from their window"
+    );
+
+    loop {
+      let message = self.next_unfiltered_message(timeout)?;
+      let filter = self.0.data.lock().unwrap().message_filter;
+      let Some(message) = filter_message(message, filter) else {
+        continue;
+      };
+      if self.dispatch_to_subscribers(&message) == Control::Continue {
+        return Some(message);
+      }
+    }
+  }
+
+  fn dispatch_to_subscribers(&self, message: &Message) -> Control {
+    let mut taken = {
+      let mut data = self.0.data.lock().unwrap();
+      if data.subscriptions.is_empty() {
+        return Control::Continue;
+      }
+      data.subscriptions.begin()
+    };
+    // no lock is held, so callbacks can use the window
+    let control = subscription::dispatch(&mut taken, message);
+    self.0.data.lock().unwrap().subscriptions.finish(taken);
+    control
+  }
+
+  fn next_unfiltered_message(&self, timeout: Option<Duration>) -> Option<Message> {
+    let current_stage = {
+      let mut data = self.0.data.lock().unwrap();
+      data.monitor = None;
+      data.stage
+    };
 
     self.0.sync.signal_next_frame();
 
     let next = match current_stage {
       Stage::Setup | Stage::Ready | Stage::Destroyed => None,
       Stage::Looping => {
-        let message = self.take_message();
+        let initial = self.0.data.lock().unwrap().initial_messages.pop_front();
+        let message = initial.or_else(|| self.take_message(timeout));
+        if let Some(message) = &message {
+          let mut data = self.0.data.lock().unwrap();
+          data.input.deliver(message);
+          #[cfg(feature = "diagnostics")]
+          {
+            if data.message_history.len() == diagnostics::MESSAGE_HISTORY_LEN {
+              data.message_history.pop_front();
+            }
+            data.message_history.push_back(message.clone());
+          }
+          if !matches!(message, Message::Loop(_)) {
+            data.idle = false;
+          }
+        }
         if let Some(Message::CloseRequested) = message {
-          let x = self.0.data.lock().unwrap().close_on_x;
+          // with the close button disabled, the app decides what to do
+          let x = {
+            let data = self.0.data.lock().unwrap();
+            data.close_on_x && data.style.enabled_buttons.close
+          };
           if x {
-            self.close();
+            self.close_with(ExitReason::UserClosed);
+          } else if !self.is_closing() {
+            self.0.data.lock().unwrap().close_pending = true;
           }
         }
         message
       }
       Stage::Closing => {
-        let _ = self.take_message();
-        self.0.data.lock().unwrap().stage = Stage::ExitLoop;
-        Some(Message::Loop(LoopMessage::Exit))
+        let _ = self.take_message(timeout);
+        let mut data = self.0.data.lock().unwrap();
+        data.stage = Stage::ExitLoop;
+        let reason = data.exit_reason.clone().unwrap_or(ExitReason::AppRequested);
+        Some(Message::Loop(LoopMessage::Exit(reason)))
       }
       Stage::ExitLoop => {
         tracing::trace!("[`{}`]: exiting loop", self.title());
-        Command::Exit.post(self.0.hwnd);
+        self.0.release_procedure();
         None
       }
     };
@@ -340,20 +855,84 @@ impl Window {
     self.0.data.lock().unwrap().style.visibility
   }
 
-  pub fn theme(&self) -> Theme {
+  /// The theme asked for with [`Window::set_theme`], which may be
+  /// [`Theme::Auto`].
+  pub fn requested_theme(&self) -> Theme {
     self.0.data.lock().unwrap().theme
   }
 
+  /// The theme the window is shown in right now.
+  pub fn effective_theme(&self) -> EffectiveTheme {
+    self.0.data.lock().unwrap().effective_theme
+  }
+
+  #[deprecated(note = "use `requested_theme` or `effective_theme` instead")]
+  pub fn theme(&self) -> Theme {
+    self.requested_theme()
+  }
+
+  pub fn resize_border(&self) -> Option<u32> {
+    let data = self.0.data.lock().unwrap();
+    data
+      .resize_border
+      .map(|border| (border * data.scale_factor).round() as u32)
+  }
+
+  pub fn game_mode_hint(&self) -> bool {
+    self.0.data.lock().unwrap().game_mode_hint
+  }
+
+  pub fn manages_titlebar_theme(&self) -> bool {
+    self.0.data.lock().unwrap().manage_titlebar_theme
+  }
+
   pub fn flow(&self) -> Flow {
     self.0.data.lock().unwrap().flow
   }
 
+  /// The title, with anything that isn't valid Unicode replaced. See
+  /// [`Window::title_os`] for the exact title.
   pub fn title(&self) -> String {
-    self.0.data.lock().unwrap().title.to_string()
+    self
+      .0
+      .data
+      .lock()
+      .unwrap()
+      .title
+      .to_string_lossy()
+      .into_owned()
   }
 
+  pub fn title_os(&self) -> OsString {
+    self.0.data.lock().unwrap().title.clone()
+  }
+
+  /// The subtitle, with anything that isn't valid Unicode replaced. See
+  /// [`Window::subtitle_os`] for the exact subtitle.
   pub fn subtitle(&self) -> String {
-    self.0.data.lock().unwrap().subtitle.to_string()
+    self
+      .0
+      .data
+      .lock()
+      .unwrap()
+      .subtitle
+      .to_string_lossy()
+      .into_owned()
+  }
+
+  pub fn subtitle_os(&self) -> OsString {
+    self.0.data.lock().unwrap().subtitle.clone()
+  }
+
+  pub fn title_separator(&self) -> String {
+    self
+      .0
+      .data
+      .lock()
+      .unwrap()
+      .title_separator
+      .to_string_lossy()
+      .into_owned()
   }
 
   pub fn outer_size(&self) -> PhysicalSize {
@@ -365,6 +944,22 @@ impl Window {
     }
   }
 
+  /// The outer size of the window the last time it was neither minimized nor
+  /// fullscreen. Unlike [`Window::outer_size`], this stays meaningful while
+  /// minimized, e.g. for saving the window layout.
+  pub fn normal_size(&self) -> PhysicalSize {
+    let data = self.0.data.lock().unwrap();
+    data.last_windowed_size.as_physical(data.scale_factor)
+  }
+
+  /// The outer position of the window the last time it was neither minimized
+  /// nor fullscreen. Unlike [`Window::outer_position`], this stays meaningful
+  /// while minimized.
+  pub fn normal_position(&self) -> PhysicalPosition {
+    let data = self.0.data.lock().unwrap();
+    data.last_windowed_position.as_physical(data.scale_factor)
+  }
+
   pub fn inner_size(&self) -> PhysicalSize {
     let mut client_rect = RECT::default();
     let _ = unsafe { GetClientRect(self.0.hwnd, &mut client_rect) };
@@ -396,21 +991,126 @@ impl Window {
     self.0.data.lock().unwrap().style.fullscreen
   }
 
+  /// The device context of the client area, for OpenGL and GDI interop.
+  /// Returns [`None`] if the window was already destroyed. See
+  /// [`DeviceContext`] for what sharing the window's private context means.
+  pub fn device_context(&self) -> Option<DeviceContext<'_>> {
+    DeviceContext::new(self.0.hwnd)
+  }
+
+  pub(crate) fn hwnd(&self) -> HWND {
+    self.0.hwnd
+  }
+
+  pub(crate) fn hinstance(&self) -> HINSTANCE {
+    self.0.hinstance
+  }
+
   pub fn cursor_screen_position(&self) -> PhysicalPosition {
     let mut pt = POINT::default();
     let _ = unsafe { GetCursorPos(std::ptr::addr_of_mut!(pt)) };
     PhysicalPosition { x: pt.x, y: pt.y }
   }
 
+  /// Whether the cursor is over the client area, as of the last cursor
+  /// message the window thread handled.
+  pub fn is_cursor_inside(&self) -> bool {
+    self.0.data.lock().unwrap().cursor.inside_window
+  }
+
+  /// The cursor as the window thread last saw it, taken in one go.
+  pub fn cursor_state(&self) -> CursorState {
+    CursorState::from(&self.0.data.lock().unwrap().cursor)
+  }
+
+  /// The cursor position relative to the top-left corner of the client area.
+  /// May lie outside the client area.
+  pub fn cursor_client_position(&self) -> PhysicalPosition {
+    self.screen_to_client(self.cursor_screen_position())
+  }
+
+  /// The outer bounds the window restores to when it is maximized or
+  /// minimized, in screen coordinates. Otherwise these are its current bounds.
+  pub fn normal_rect(&self) -> PhysicalRect {
+    normal_rect(self.0.hwnd).unwrap_or_default()
+  }
+
+  /// Set the outer bounds the window restores to without restoring it, e.g.
+  /// to bring back a saved layout of a maximized window. Sets the current
+  /// bounds if the window is neither maximized nor minimized.
+  pub fn set_normal_rect(&self, position: PhysicalPosition, size: PhysicalSize) {
+    Command::SetNormalRect(PhysicalRect::new(position, size)).post(self.0.hwnd);
+  }
+
+  /// Converts a position on the screen to one relative to the client area.
+  pub fn screen_to_client(&self, position: PhysicalPosition) -> PhysicalPosition {
+    let mut pt = POINT {
+      x: position.x,
+      y: position.y,
+    };
+    let _ = unsafe { ScreenToClient(self.0.hwnd, std::ptr::addr_of_mut!(pt)) };
+    PhysicalPosition { x: pt.x, y: pt.y }
+  }
+
+  /// Converts a position relative to the client area to one on the screen.
+  pub fn client_to_screen(&self, position: PhysicalPosition) -> PhysicalPosition {
+    let mut pt = POINT {
+      x: position.x,
+      y: position.y,
+    };
+    let _ = unsafe { ClientToScreen(self.0.hwnd, std::ptr::addr_of_mut!(pt)) };
+    PhysicalPosition { x: pt.x, y: pt.y }
+  }
+
+  /// Like [`Window::screen_to_client`], scaled by the window's scale factor on
+  /// both sides.
+  pub fn screen_to_client_logical(&self, position: LogicalPosition) -> LogicalPosition {
+    let scale_factor = self.scale_factor();
+    self
+      .screen_to_client(position.as_physical(scale_factor))
+      .as_logical(scale_factor)
+  }
+
+  /// Like [`Window::client_to_screen`], scaled by the window's scale factor on
+  /// both sides.
+  pub fn client_to_screen_logical(&self, position: LogicalPosition) -> LogicalPosition {
+    let scale_factor = self.scale_factor();
+    self
+      .client_to_screen(position.as_physical(scale_factor))
+      .as_logical(scale_factor)
+  }
+
   pub fn has_focus(&self) -> bool {
     let style = &self.0.data.lock().unwrap().style;
     style.focused && style.active
   }
 
+  /// A handle for other threads to check whether the window has keyboard
+  /// focus, or to sleep until it does, without going through the window.
+  pub fn focus_gate(&self) -> FocusGate {
+    self.0.sync.focus_gate.clone()
+  }
+
   pub fn scale_factor(&self) -> f64 {
     self.0.data.lock().unwrap().scale_factor
   }
 
+  /// The DPI awareness the window was created with, which may differ from the
+  /// awareness of the process. See [`WindowSettings::with_dpi_awareness`].
+  pub fn dpi_awareness(&self) -> DpiAwareness {
+    hwnd_dpi_awareness(self.0.hwnd)
+  }
+
+  /// Name of the window class registered for this window.
+  pub fn class_name(&self) -> &str {
+    &self.0.class_name
+  }
+
+  /// Atom of the window class registered for this window.
+  pub fn class_atom(&self) -> u16 {
+    self.0.class_atom
+  }
+
   unsafe extern "system" fn monitor_enum_proc(
     hmonitor: HMONITOR,
     _hdc: HDC,
@@ -436,10 +1136,19 @@ impl Window {
     monitors.into_iter().map(Monitor::new).collect()
   }
 
+  /// The monitor the window is mostly on. The result is cached until the
+  /// window moves, the display configuration changes, or the next message is
+  /// taken from the window, so it is at most one frame stale.
   pub fn current_monitor(&self) -> Monitor {
+    if let Some(monitor) = self.0.data.lock().unwrap().monitor {
+      return monitor;
+    }
+
     let hmonitor =
       unsafe { MonitorFromWindow(self.0.hwnd, Gdi::MONITOR_DEFAULTTONEAREST) };
-    Monitor::new(hmonitor)
+    let monitor = Monitor::cached(hmonitor);
+    self.0.data.lock().unwrap().monitor = Some(monitor);
+    monitor
   }
 
   pub fn primary_monitor(&self) -> Monitor {
@@ -448,34 +1157,204 @@ impl Window {
     Monitor::new(hmonitor)
   }
 
-  pub fn key(&self, keycode: Key) -> KeyState {
-    self.0.data.lock().unwrap().input.key(keycode)
+  /// The monitor containing `position` in screen coordinates, or the nearest
+  /// one if it is off every monitor.
+  pub fn monitor_from_point(&self, position: PhysicalPosition) -> Monitor {
+    let point = POINT {
+      x: position.x,
+      y: position.y,
+    };
+    let hmonitor = unsafe { MonitorFromPoint(point, Gdi::MONITOR_DEFAULTTONEAREST) };
+    Monitor::new(hmonitor)
   }
 
-  pub fn mouse(&self, button: MouseButton) -> ButtonState {
-    self.0.data.lock().unwrap().input.mouse(button)
+  /// The monitor `rect` in screen coordinates mostly falls on, or the nearest
+  /// one if it is off every monitor.
+  pub fn monitor_from_rect(&self, rect: PhysicalRect) -> Monitor {
+    let rect = RECT::from(rect);
+    let hmonitor = unsafe { MonitorFromRect(&rect, Gdi::MONITOR_DEFAULTTONEAREST) };
+    Monitor::new(hmonitor)
+  }
+
+  /// A snapshot of the window's state for bug reports, cheap enough to take
+  /// from any thread. Format it with `{}` to paste it into an issue. The last
+  /// messages taken are only included with the "diagnostics" feature.
+  pub fn diagnostics(&self) -> Diagnostics {
+    let hwnd = self.0.hwnd;
+    let (title, stage, flow, style, scale_factor, cursor) = {
+      let data = self.0.data.lock().unwrap();
+      (
+        data.title.to_string_lossy().into_owned(),
+        data.stage,
+        data.flow,
+        data.style.clone(),
+        data.scale_factor,
+        data.cursor.clone(),
+      )
+    };
+    #[cfg(feature = "diagnostics")]
+    let recent_messages = self
+      .0
+      .data
+      .lock()
+      .unwrap()
+      .message_history
+      .iter()
+      .cloned()
+      .collect();
+
+    let current = unsafe { MonitorFromWindow(hwnd, Gdi::MONITOR_DEFAULTTONEAREST) };
+    let monitors: Vec<Monitor> = self
+      .available_monitors()
+      .iter()
+      .map(|monitor| Monitor::cached(monitor.hmonitor()))
+      .collect();
+    let current_monitor = monitors
+      .iter()
+      .position(|monitor| monitor.hmonitor() == current);
+
+    Diagnostics {
+      title,
+      stage,
+      flow,
+      os_style: unsafe { GetWindowLongW(hwnd, WindowsAndMessaging::GWL_STYLE) } as u32,
+      expected_style: get_window_style(&style).0,
+      os_ex_style: unsafe { GetWindowLongW(hwnd, WindowsAndMessaging::GWL_EXSTYLE) }
+        as u32,
+      expected_ex_style: get_window_ex_style(&style).0,
+      style,
+      dpi: hwnd_dpi(hwnd),
+      dpi_awareness: hwnd_dpi_awareness(hwnd),
+      scale_factor,
+      outer_position: self.outer_position(),
+      outer_size: self.outer_size(),
+      inner_position: self.inner_position(),
+      inner_size: self.inner_size(),
+      cursor,
+      monitors,
+      current_monitor,
+      #[cfg(feature = "diagnostics")]
+      recent_messages,
+    }
+  }
+
+  /// Logs [`Window::diagnostics`] at the info level.
+  pub fn print_diagnostics(&self) {
+    tracing::info!("{}", self.diagnostics());
+  }
+
+  /// A present mode that fits the current state of the window, so swapchain
+  /// setup doesn't have to hardcode one. This is advice only; check that the
+  /// surface supports it and fall back to [`PresentMode::Fifo`] otherwise.
+  ///
+  /// Borderless fullscreen windows can be flipped straight to the screen,
+  /// where [`PresentMode::Mailbox`] gives the lowest latency without tearing.
+  /// Windowed frames go through the compositor, which already limits them to
+  /// the refresh rate, so [`PresentMode::Fifo`] saves the wasted frames unless
+  /// the monitor refreshes fast enough (120 Hz or more) for latency to matter.
+  pub fn recommended_present_mode(&self) -> PresentMode {
+    const HIGH_REFRESH_RATE: u32 = 120;
+
+    if self.fullscreen().is_some() {
+      return PresentMode::Mailbox;
+    }
+
+    match self.current_monitor().refresh_rate() {
+      Some(hz) if hz >= HIGH_REFRESH_RATE => PresentMode::Mailbox,
+      _ => PresentMode::Fifo,
+    }
+  }
+
+  /// Composition timing of the desktop window manager, useful for detecting
+  /// dropped frames. Returns [`None`] if the compositor couldn't be queried.
+  ///
+  /// The values come from the compositor, not the GPU present queue.
+  pub fn present_stats(&self) -> Option<PresentStats> {
+    let mut info = DWM_TIMING_INFO {
+      cbSize: std::mem::size_of::<DWM_TIMING_INFO>() as u32,
+      ..Default::default()
+    };
+    // the window handle must be null on Windows 8.1 and later
+    if let Err(_error) =
+      unsafe { DwmGetCompositionTimingInfo(HWND::default(), &mut info) }
+    {
+      tracing::error!("{_error}");
+      return None;
+    }
+
+    let (numerator, denominator) =
+      (info.rateRefresh.uiNumerator, info.rateRefresh.uiDenominator);
+    let refresh_rate = if denominator == 0 {
+      0.0
+    } else {
+      numerator as f64 / denominator as f64
+    };
+    let refresh_period = if numerator == 0 {
+      Duration::ZERO
+    } else {
+      Duration::from_secs_f64(denominator as f64 / numerator as f64)
+    };
+
+    Some(PresentStats {
+      refresh_count: info.cRefresh,
+      refresh_rate,
+      refresh_period,
+      frame_count: info.cFrame,
+      frames_dropped: info.cFramesDropped,
+      frames_missed: info.cFramesMissed,
+    })
+  }
+
+  /// The state of the key as of the last message taken from the window, so it
+  /// always agrees with the [`Message::Key`]s seen so far.
+  pub fn key(&self, keycode: Key) -> KeyState {
+    self.0.data.lock().unwrap().input.delivered.key(keycode)
+  }
+
+  /// The state of the key as the window thread last saw it, which may be ahead
+  /// of the messages taken so far.
+  pub fn key_realtime(&self, keycode: Key) -> KeyState {
+    self.0.data.lock().unwrap().input.realtime.key(keycode)
+  }
+
+  /// The state of the button as of the last message taken from the window, so
+  /// it always agrees with the [`Message::MouseButton`]s seen so far.
+  pub fn mouse(&self, button: MouseButton) -> ButtonState {
+    self.0.data.lock().unwrap().input.delivered.mouse(button)
+  }
+
+  /// The state of the button as the window thread last saw it, which may be
+  /// ahead of the messages taken so far.
+  pub fn mouse_realtime(&self, button: MouseButton) -> ButtonState {
+    self.0.data.lock().unwrap().input.realtime.mouse(button)
   }
 
   pub fn shift(&self) -> ButtonState {
-    self.0.data.lock().unwrap().input.shift()
+    self.0.data.lock().unwrap().input.delivered.shift()
   }
 
   pub fn ctrl(&self) -> ButtonState {
-    self.0.data.lock().unwrap().input.ctrl()
+    self.0.data.lock().unwrap().input.delivered.ctrl()
   }
 
   pub fn alt(&self) -> ButtonState {
-    self.0.data.lock().unwrap().input.alt()
+    self.0.data.lock().unwrap().input.delivered.alt()
   }
 
   pub fn win(&self) -> ButtonState {
-    self.0.data.lock().unwrap().input.win()
+    self.0.data.lock().unwrap().input.delivered.win()
   }
 
   pub fn is_closing(&self) -> bool {
     self.0.is_closing()
   }
 
+  /// Whether a [`Message::CloseRequested`] is waiting on
+  /// [`Window::confirm_close`] or [`Window::cancel_close`].
+  pub fn is_close_pending(&self) -> bool {
+    self.0.data.lock().unwrap().close_pending
+  }
+
   pub fn is_minimized(&self) -> bool {
     self.0.data.lock().unwrap().style.minimized
   }
@@ -484,6 +1363,47 @@ impl Window {
     self.0.data.lock().unwrap().style.maximized
   }
 
+  fn force_set_minimized(&self, minimized: bool) {
+    self.0.data.lock().unwrap().style.minimized = minimized;
+    Command::SetMinimized(minimized).post(self.0.hwnd);
+  }
+
+  /// Minimize the window, or restore it from being minimized. A window that
+  /// was maximized before being minimized is restored to maximized.
+  pub fn set_minimized(&self, minimized: bool) {
+    if minimized == self.0.data.lock().unwrap().style.minimized {
+      return;
+    }
+    self.force_set_minimized(minimized)
+  }
+
+  fn force_set_maximized(&self, maximized: bool) {
+    {
+      let mut data = self.0.data.lock().unwrap();
+      data.style.maximized = maximized;
+      // both maximizing and restoring bring a minimized window back
+      data.style.minimized = false;
+    }
+    Command::SetMaximized(maximized).post(self.0.hwnd);
+  }
+
+  /// Maximize the window, or restore it from being maximized.
+  pub fn set_maximized(&self, maximized: bool) {
+    if maximized == self.0.data.lock().unwrap().style.maximized {
+      return;
+    }
+    self.force_set_maximized(maximized)
+  }
+
+  /// The margins last applied with [`Window::set_dwm_margins`], if any.
+  pub fn dwm_margins(&self) -> Option<Margins> {
+    self.0.data.lock().unwrap().dwm_margins
+  }
+
+  pub fn is_continuous_redraw(&self) -> bool {
+    self.0.data.lock().unwrap().continuous_redraw
+  }
+
   // SETTERS
 
   fn force_set_cursor_icon(&self, cursor_icon: CursorIcon) {
@@ -499,6 +1419,18 @@ impl Window {
     self.force_set_cursor_icon(cursor_icon)
   }
 
+  /// Like [`Window::set_cursor_icon`], but returns
+  /// [`WindowError::CommandQueueFull`] instead of dropping the change when the
+  /// window thread is flooded. The icon is only cached once the window thread
+  /// applies it, so nothing goes out of sync either way.
+  pub fn try_set_cursor_icon(&self, cursor_icon: CursorIcon) -> Result<(), WindowError> {
+    let selected_icon = self.0.data.lock().unwrap().cursor.selected_icon;
+    if selected_icon == cursor_icon {
+      return Ok(());
+    }
+    Command::SetCursorIcon(cursor_icon).try_post(self.0.hwnd)
+  }
+
   fn force_set_outer_position(&self, position: Position) {
     // self.state.write_lock().position = position;
     Command::SetPosition(position).post(self.0.hwnd);
@@ -530,27 +1462,8 @@ impl Window {
     let scale_factor = self.0.data.lock().unwrap().scale_factor;
     let physical_size = size.as_physical(scale_factor);
     let style = self.0.data.lock().unwrap().style.clone();
-    let mut window_rect = RECT {
-      top: 0,
-      left: 0,
-      right: physical_size.width as i32,
-      bottom: physical_size.height as i32,
-    };
-    unsafe {
-      AdjustWindowRectExForDpi(
-        &mut window_rect,
-        get_window_style(&style),
-        false,
-        get_window_ex_style(&style),
-        hwnd_dpi(self.0.hwnd),
-      )
-    }
-    .unwrap();
-
-    let adjusted_size = PhysicalSize {
-      width: (window_rect.right - window_rect.left) as u32,
-      height: (window_rect.bottom - window_rect.top) as u32,
-    };
+    let adjusted_size =
+      outer_size_for_inner(physical_size, &style, hwnd_dpi(self.0.hwnd));
 
     Command::SetSize(adjusted_size.into()).post(self.0.hwnd);
   }
@@ -564,6 +1477,53 @@ impl Window {
     self.force_set_inner_size(size)
   }
 
+  pub fn min_inner_size(&self) -> Option<Size> {
+    self.0.data.lock().unwrap().min_inner_size
+  }
+
+  pub fn max_inner_size(&self) -> Option<Size> {
+    self.0.data.lock().unwrap().max_inner_size
+  }
+
+  /// Keep the user from resizing the window smaller than `size`, or remove the
+  /// limit with [`None`]. The window grows right away if it is smaller.
+  /// Logical sizes keep covering the same content when the scale factor
+  /// changes.
+  pub fn set_min_inner_size(&self, size: Option<impl Into<Size>>) {
+    self.0.data.lock().unwrap().min_inner_size = size.map(Into::into);
+    self.fit_size_limits();
+  }
+
+  /// Keep the user from resizing the window larger than `size`, or remove the
+  /// limit with [`None`]. The window shrinks right away if it is larger.
+  /// Logical sizes keep covering the same content when the scale factor
+  /// changes.
+  pub fn set_max_inner_size(&self, size: Option<impl Into<Size>>) {
+    self.0.data.lock().unwrap().max_inner_size = size.map(Into::into);
+    self.fit_size_limits();
+  }
+
+  /// Resizes the window into the size limits, if it is outside of them.
+  fn fit_size_limits(&self) {
+    let (min_inner_size, max_inner_size, scale_factor) = {
+      let data = self.0.data.lock().unwrap();
+      (data.min_inner_size, data.max_inner_size, data.scale_factor)
+    };
+    let size = self.inner_size();
+    let mut fitted = size;
+    if let Some(max) = max_inner_size.map(|size| size.as_physical(scale_factor)) {
+      fitted.width = fitted.width.min(max.width);
+      fitted.height = fitted.height.min(max.height);
+    }
+    if let Some(min) = min_inner_size.map(|size| size.as_physical(scale_factor)) {
+      fitted.width = fitted.width.max(min.width);
+      fitted.height = fitted.height.max(min.height);
+    }
+    if fitted != size {
+      self.force_set_inner_size(fitted.into());
+    }
+  }
+
   fn force_set_visibility(&self, visibility: Visibility) {
     self.0.data.lock().unwrap().style.visibility = visibility;
     Command::SetVisibility(visibility).post(self.0.hwnd);
@@ -588,37 +1548,93 @@ impl Window {
     self.force_set_decorations(visibility)
   }
 
-  fn force_set_theme(&self, theme: Theme) {
-    let theme = match theme {
-      Theme::Auto => {
-        if is_system_dark_mode_enabled() {
-          Theme::Dark
-        } else {
-          Theme::Light
-        }
-      }
-      Theme::Dark => {
-        if is_dark_mode_supported() {
-          Theme::Dark
-        } else {
-          Theme::Light
-        }
-      }
-      Theme::Light => Theme::Light,
-    };
+  pub fn enabled_buttons(&self) -> WindowButtons {
+    self.0.data.lock().unwrap().style.enabled_buttons
+  }
+
+  fn force_set_enabled_buttons(&self, buttons: WindowButtons) {
+    self.0.data.lock().unwrap().style.enabled_buttons = buttons;
+    Command::SetEnabledButtons(buttons).post(self.0.hwnd);
+  }
+
+  /// Enable or disable the buttons of the title bar. A disabled close button
+  /// still lets [`Message::CloseRequested`] through from Alt+F4 or the
+  /// taskbar, but it is no longer handled by `close_on_x`, so the app decides
+  /// whether to close. Alt+F4 can be blocked with
+  /// [`WindowBuilder::with_alt_f4`].
+  pub fn set_enabled_buttons(&self, buttons: WindowButtons) {
+    if buttons == self.0.data.lock().unwrap().style.enabled_buttons {
+      return;
+    }
+    self.force_set_enabled_buttons(buttons)
+  }
+
+  pub fn transparency_color_key(&self) -> Option<Color> {
+    self.0.data.lock().unwrap().style.transparency_color_key
+  }
+
+  fn force_set_transparency_color_key(&self, color_key: Option<Color>) {
+    self.0.data.lock().unwrap().style.transparency_color_key = color_key;
+    Command::SetTransparencyColorKey(color_key).post(self.0.hwnd);
+  }
+
+  /// Make every pixel of `color_key` fully transparent and click-through, for
+  /// irregularly shaped overlays. This makes the window layered
+  /// (`WS_EX_LAYERED`) with `LWA_COLORKEY`, which can't be combined with
+  /// per-pixel alpha on the same window. `None` removes the color key.
+  pub fn set_transparency_color_key(&self, color_key: Option<Color>) {
+    if color_key == self.0.data.lock().unwrap().style.transparency_color_key {
+      return;
+    }
+    self.force_set_transparency_color_key(color_key)
+  }
+
+  /// Whether the window was created without a GDI redirection surface. See
+  /// [`WindowSettings::with_no_redirection_bitmap`].
+  pub fn has_no_redirection_bitmap(&self) -> bool {
+    self.0.data.lock().unwrap().style.no_redirection_bitmap
+  }
+
+  pub fn is_resizable(&self) -> bool {
+    self.0.data.lock().unwrap().style.resizeable
+  }
+
+  fn force_set_resizable(&self, resizable: bool) {
+    self.0.data.lock().unwrap().style.resizeable = resizable;
+    Command::SetResizable(resizable).post(self.0.hwnd);
+  }
+
+  /// Whether the user can resize the window by dragging its border. A window
+  /// that isn't resizable also loses its minimize and maximize buttons.
+  pub fn set_resizable(&self, resizable: bool) {
+    if resizable == self.0.data.lock().unwrap().style.resizeable {
+      return;
+    }
+    self.force_set_resizable(resizable)
+  }
 
+  pub fn is_focusable(&self) -> bool {
+    self.0.data.lock().unwrap().style.focusable
+  }
+
+  fn force_set_focusable(&self, focusable: bool) {
+    self.0.data.lock().unwrap().style.focusable = focusable;
+    Command::SetFocusable(focusable).post(self.0.hwnd);
+  }
+
+  /// Whether the window can be activated. Non-focusable windows still receive
+  /// mouse input, but never take focus or the foreground from other windows,
+  /// which is what overlays and on-screen keyboards need.
+  pub fn set_focusable(&self, focusable: bool) {
+    if focusable == self.0.data.lock().unwrap().style.focusable {
+      return;
+    }
+    self.force_set_focusable(focusable)
+  }
+
+  fn force_set_theme(&self, theme: Theme) {
     self.0.data.lock().unwrap().theme = theme;
-    let dark_mode = BOOL::from(theme == Theme::Dark);
-    if let Err(_error) = unsafe {
-      DwmSetWindowAttribute(
-        self.0.hwnd,
-        Dwm::DWMWA_USE_IMMERSIVE_DARK_MODE,
-        std::ptr::addr_of!(dark_mode) as *const std::ffi::c_void,
-        std::mem::size_of::<BOOL>() as u32,
-      )
-    } {
-      tracing::error!("{_error}");
-    };
+    self.0.update_effective_theme();
   }
 
   pub fn set_theme(&self, theme: Theme) {
@@ -628,9 +1644,104 @@ impl Window {
     self.force_set_theme(theme)
   }
 
+  /// Widen the area along the window edges which resizes the window, for
+  /// touch or imprecise pointers. Applies to decorated windows too, reaching
+  /// into the caption and client area. Sizes below the system resize frame
+  /// have no effect. The size follows scale factor changes.
+  ///
+  /// This is the same thickness as [`Window::set_resize_border`], so the last
+  /// call to either wins.
+  pub fn set_resize_grip_size(&self, size: u32) {
+    self.set_resize_border(Some(size));
+  }
+
+  /// The thickness of the resize grips in physical pixels, which is the system
+  /// resize frame unless [`Window::set_resize_grip_size`] made it larger.
+  pub fn resize_grip_size(&self) -> u32 {
+    let dpi = unsafe { GetDpiForWindow(self.0.hwnd) };
+    let system = unsafe {
+      GetSystemMetricsForDpi(WindowsAndMessaging::SM_CXSIZEFRAME, dpi)
+        + GetSystemMetricsForDpi(WindowsAndMessaging::SM_CXPADDEDBORDER, dpi)
+    } as u32;
+    self
+      .resize_border()
+      .map_or(system, |border| border.max(system))
+  }
+
+  /// Set the AppUserModelID of this window, which decides which taskbar
+  /// button it is grouped under, its jump list, and how it is pinned. `None`
+  /// removes it, grouping the window with the rest of the process again.
+  pub fn set_app_user_model_id(&self, id: Option<&str>) {
+    Command::SetAppUserModelId(id.map(str::to_owned)).post(self.0.hwnd);
+  }
+
+  /// The AppUserModelID of this window, read back from its property store.
+  pub fn app_user_model_id(&self) -> Option<String> {
+    window_app_user_model_id(self.0.hwnd)
+  }
+
+  /// Set the AppUserModelID of the whole process, which windows without their
+  /// own ID are grouped under. Must be called before any window is shown.
+  pub fn set_process_app_user_model_id(id: &str) -> Result<(), WindowError> {
+    unsafe { SetCurrentProcessExplicitAppUserModelID(&HSTRING::from(id)) }?;
+    Ok(())
+  }
+
+  /// Give the window invisible resize grips `thickness` physical pixels wide
+  /// along its edges, which is mostly useful with hidden decorations or
+  /// [`WindowBuilder::with_borderless_shadow`], whose frame isn't hit tested.
+  /// The thickness follows scale factor changes. Has no effect while the
+  /// window isn't resizeable, is maximized, or is fullscreen. On decorated
+  /// windows it works like [`Window::set_resize_grip_size`].
+  pub fn set_resize_border(&self, thickness: Option<u32>) {
+    let mut data = self.0.data.lock().unwrap();
+    data.resize_border = thickness.map(|thickness| thickness as f64 / data.scale_factor);
+  }
+
+  /// Best-effort hint that the window belongs to a game which wants to be
+  /// prioritized by the OS. Windows has no public opt-in for Game Mode, so
+  /// this uses what is available:
+  ///
+  /// * Windows 10 1709+: opts the process out of execution speed power
+  ///   throttling (`SetProcessInformation` with `ProcessPowerThrottling`).
+  /// * Older versions: no-op.
+  ///
+  /// Game Mode itself and fullscreen optimizations are still decided by the
+  /// OS, and are most likely to apply to a focused
+  /// [`Fullscreen::Borderless`] window. The hint is process-wide.
+  pub fn set_game_mode_hint(&self, enabled: bool) {
+    {
+      let mut data = self.0.data.lock().unwrap();
+      if data.game_mode_hint == enabled {
+        return;
+      }
+      data.game_mode_hint = enabled;
+    }
+    set_execution_speed_throttling(!enabled);
+  }
+
+  /// Whether witer sets the dark mode title bar attribute to match
+  /// [`Window::effective_theme`]. Disabling this leaves the caption as it currently is.
+  pub fn set_manage_titlebar_theme(&self, manage: bool) {
+    {
+      let mut data = self.0.data.lock().unwrap();
+      if data.manage_titlebar_theme == manage {
+        return;
+      }
+      data.manage_titlebar_theme = manage;
+    }
+    if manage {
+      self.force_set_theme(self.requested_theme());
+    }
+  }
+
   fn force_set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
     self.0.data.lock().unwrap().style.fullscreen = fullscreen;
-    Command::SetFullscreen(fullscreen).post(self.0.hwnd);
+    Command::SetFullscreen {
+      fullscreen,
+      apply_cursor: false,
+    }
+    .post(self.0.hwnd);
   }
 
   pub fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
@@ -640,24 +1751,95 @@ impl Window {
     self.force_set_fullscreen(fullscreen)
   }
 
-  fn force_set_title(&self, title: impl AsRef<str>) {
-    self.0.data.lock().unwrap().title = title.as_ref().into();
-    let title = HSTRING::from(format!(
-      "{}{}",
-      title.as_ref(),
-      self.0.data.lock().unwrap().subtitle
-    ));
-    Command::SetWindowText(title).post(self.0.hwnd);
+  /// Like [`Window::set_fullscreen`], but also changes the cursor mode and
+  /// visibility in the same step, after the window has its new bounds. Use
+  /// [`CursorBehavior::ConfineAndHide`] when entering fullscreen and
+  /// [`CursorBehavior::RestorePrevious`] when leaving it.
+  pub fn set_fullscreen_with(
+    &self,
+    fullscreen: Option<Fullscreen>,
+    cursor_behavior: CursorBehavior,
+  ) {
+    let apply_cursor = {
+      let mut data = self.0.data.lock().unwrap();
+      data.style.fullscreen = fullscreen;
+      match cursor_behavior {
+        CursorBehavior::Keep => false,
+        CursorBehavior::ConfineAndHide => {
+          // toggling again shouldn't overwrite the state to restore
+          if data.cursor_snapshot.is_none() {
+            data.cursor_snapshot = Some((data.cursor.mode, data.cursor.visibility));
+          }
+          data.cursor.mode = CursorMode::Confined;
+          data.cursor.visibility = Visibility::Hidden;
+          true
+        }
+        CursorBehavior::RestorePrevious => match data.cursor_snapshot.take() {
+          Some((mode, visibility)) => {
+            data.cursor.mode = mode;
+            data.cursor.visibility = visibility;
+            true
+          }
+          None => false,
+        },
+      }
+    };
+
+    Command::SetFullscreen {
+      fullscreen,
+      apply_cursor,
+    }
+    .post(self.0.hwnd);
+  }
+
+  /// Sets the window text from the title, separator, and subtitle.
+  fn window_text_command(&self) -> Command {
+    let data = self.0.data.lock().unwrap();
+    Command::SetWindowText(compose_window_text(
+      &data.title,
+      &data.title_separator,
+      &data.subtitle,
+    ))
+  }
+
+  fn update_window_text(&self) {
+    self.window_text_command().post(self.0.hwnd);
+  }
+
+  fn force_set_title(&self, title: impl AsRef<OsStr>) {
+    self.0.data.lock().unwrap().title = title.as_ref().to_os_string();
+    self.update_window_text();
   }
 
   /// Set the title of the window
   pub fn set_title(&self, title: impl AsRef<str>) {
+    self.set_title_os(title.as_ref())
+  }
+
+  /// Like [`Window::set_title`], but also takes titles that aren't valid
+  /// Unicode, e.g. file names, without replacing anything.
+  pub fn set_title_os(&self, title: impl AsRef<OsStr>) {
     if title.as_ref() == self.0.data.lock().unwrap().title {
       return;
     }
     self.force_set_title(title)
   }
 
+  /// Like [`Window::set_title`], but returns
+  /// [`WindowError::CommandQueueFull`] instead of dropping the change when the
+  /// window thread is flooded, in which case the title stays as it was.
+  pub fn try_set_title(&self, title: impl AsRef<str>) -> Result<(), WindowError> {
+    let title = OsString::from(title.as_ref());
+    let previous = {
+      let mut data = self.0.data.lock().unwrap();
+      if title == data.title {
+        return Ok(());
+      }
+      std::mem::replace(&mut data.title, title)
+    };
+    self.try_update_window_text(|data| data.title = previous)
+  }
+
   fn force_set_cursor_mode(&self, cursor_mode: CursorMode) {
     self.0.data.lock().unwrap().cursor.mode = cursor_mode;
     Command::SetCursorMode(cursor_mode).post(self.0.hwnd);
@@ -682,29 +1864,337 @@ impl Window {
     self.force_set_cursor_visibility(cursor_visibility)
   }
 
-  fn force_set_subtitle(&self, subtitle: impl AsRef<str>) {
-    self.0.data.lock().unwrap().subtitle = subtitle.as_ref().into();
-    let title = HSTRING::from(format!(
-      "{}{}",
-      self.0.data.lock().unwrap().title,
-      subtitle.as_ref()
-    ));
-    Command::SetWindowText(title).post(self.0.hwnd);
+  fn force_set_subtitle(&self, subtitle: impl AsRef<OsStr>) {
+    self.0.data.lock().unwrap().subtitle = subtitle.as_ref().to_os_string();
+    self.update_window_text();
   }
 
-  /// Set text to appear after the title of the window
+  /// Set text to appear after the title of the window, separated by the
+  /// [title separator](Window::set_title_separator). Text too long to show in
+  /// full is shortened with an ellipsis.
   pub fn set_subtitle(&self, subtitle: impl AsRef<str>) {
+    self.set_subtitle_os(subtitle.as_ref())
+  }
+
+  /// Like [`Window::set_subtitle`], but also takes text that isn't valid
+  /// Unicode without replacing anything.
+  pub fn set_subtitle_os(&self, subtitle: impl AsRef<OsStr>) {
     if subtitle.as_ref() == self.0.data.lock().unwrap().subtitle {
       return;
     }
     self.force_set_subtitle(subtitle)
   }
 
+  /// Like [`Window::set_subtitle`], but returns
+  /// [`WindowError::CommandQueueFull`] instead of dropping the change when the
+  /// window thread is flooded, in which case the subtitle stays as it was.
+  pub fn try_set_subtitle(&self, subtitle: impl AsRef<str>) -> Result<(), WindowError> {
+    let subtitle = OsString::from(subtitle.as_ref());
+    let previous = {
+      let mut data = self.0.data.lock().unwrap();
+      if subtitle == data.subtitle {
+        return Ok(());
+      }
+      std::mem::replace(&mut data.subtitle, subtitle)
+    };
+    self.try_update_window_text(|data| data.subtitle = previous)
+  }
+
+  /// Posts the composed window text, undoing the cached change with `revert`
+  /// if it couldn't be posted.
+  fn try_update_window_text(
+    &self,
+    revert: impl FnOnce(&mut Data),
+  ) -> Result<(), WindowError> {
+    let result = self.window_text_command().try_post(self.0.hwnd);
+    if result.is_err() {
+      revert(&mut self.0.data.lock().unwrap());
+    }
+    result
+  }
+
+  fn force_set_title_separator(&self, separator: impl AsRef<str>) {
+    self.0.data.lock().unwrap().title_separator = separator.as_ref().into();
+    self.update_window_text();
+  }
+
+  /// Set the text put between the title and subtitle, `" — "` by default. Only
+  /// used when both are non-empty.
+  pub fn set_title_separator(&self, separator: impl AsRef<str>) {
+    if separator.as_ref() == self.0.data.lock().unwrap().title_separator {
+      return;
+    }
+    self.force_set_title_separator(separator)
+  }
+
+  fn force_set_z_order_after(
+    window: &Window,
+    insert_after: &Window,
+  ) -> Result<(), WindowError> {
+    if window.is_closing() || insert_after.is_closing() {
+      return Err(WindowError::AlreadyClosed);
+    }
+    Command::SetZOrderAfter(insert_after.0.hwnd).post(window.0.hwnd);
+    Ok(())
+  }
+
+  /// Post a message in the `WM_APP` range to the window, which arrives as
+  /// [`Message::App`] with the same `code`. Plugins and other processes can
+  /// post to the same range through the window handle. `code` must be at most
+  /// `APP_MESSAGE_LAST - WM_APP` (`0x3FFF`). See
+  /// [`APP_MESSAGE_LAST`](crate::APP_MESSAGE_LAST) for the ranges witer
+  /// reserves.
+  pub fn post_app_message(
+    &self,
+    code: u16,
+    wparam: usize,
+    lparam: isize,
+  ) -> Result<(), WindowError> {
+    let Some(msg) = app_message_id(code) else {
+      return Err(WindowError::Error(format!(
+        "app message code {code:#x} is out of range"
+      )));
+    };
+    unsafe { PostMessageW(self.0.hwnd, msg, WPARAM(wparam), LPARAM(lparam)) }?;
+    Ok(())
+  }
+
+  /// Place this window directly above `other` in the z-order. Fails with
+  /// [`WindowError::AlreadyClosed`] if either window is closing.
+  pub fn set_z_order_above(&self, other: &Window) -> Result<(), WindowError> {
+    Self::force_set_z_order_after(other, self)
+  }
+
+  /// Place this window directly below `other` in the z-order. Fails with
+  /// [`WindowError::AlreadyClosed`] if either window is closing.
+  pub fn set_z_order_below(&self, other: &Window) -> Result<(), WindowError> {
+    Self::force_set_z_order_after(self, other)
+  }
+
+  fn force_set_dwm_margins(&self, margins: Option<Margins>) {
+    self.0.data.lock().unwrap().dwm_margins = margins;
+    Command::SetDwmMargins(margins).post(self.0.hwnd);
+  }
+
+  /// Extend the window frame (and its shadow) into the client area by the
+  /// given margins using `DwmExtendFrameIntoClientArea`. The margins are
+  /// re-applied whenever desktop composition changes.
+  pub fn set_dwm_margins(&self, margins: Margins) {
+    if Some(margins) == self.0.data.lock().unwrap().dwm_margins {
+      return;
+    }
+    self.force_set_dwm_margins(Some(margins))
+  }
+
+  /// Stop extending the window frame into the client area.
+  pub fn reset_dwm_margins(&self) {
+    if self.0.data.lock().unwrap().dwm_margins.is_none() {
+      return;
+    }
+    self.force_set_dwm_margins(None)
+  }
+
+  fn force_set_pre_iterator_filter(&self, filter: Option<MessageFilter>) {
+    self.0.data.lock().unwrap().message_filter = filter;
+  }
+
+  /// Pass every message through `filter` on the iterating thread before the
+  /// loop sees it, to remap, collapse or drop messages in one place. Returning
+  /// [`None`] drops the message and the iterator moves on to the next one.
+  ///
+  /// The filter runs after witer's own handling, so dropped messages still
+  /// update the input state, and dropping [`Message::CloseRequested`] doesn't
+  /// stop [`WindowBuilder::with_close_on_x`](crate::WindowBuilder::with_close_on_x)
+  /// from closing the window. [`Message::Loop`] messages, including the exit,
+  /// bypass the filter.
+  pub fn set_pre_iterator_filter(&self, filter: MessageFilter) {
+    self.force_set_pre_iterator_filter(Some(filter))
+  }
+
+  /// Stop filtering messages set with [`Window::set_pre_iterator_filter`].
+  pub fn reset_pre_iterator_filter(&self) {
+    self.force_set_pre_iterator_filter(None)
+  }
+
+  /// Call `callback` for every message of `kinds`, right before the iterator
+  /// yields it. This is sugar over the loop: callbacks only run while the
+  /// window is being iterated, on the iterating thread, after the
+  /// [pre-iterator filter](Window::set_pre_iterator_filter).
+  ///
+  /// - Callbacks run in the order they were registered. One returning
+  ///   [`Control::Handled`] skips the rest and hides the message from the
+  ///   iterator, except for [`Message::Loop`] messages.
+  /// - Callbacks may use the window, including registering and removing
+  ///   callbacks, which takes effect from the next message. Taking messages
+  ///   from the window inside a callback panics.
+  /// - A callback that panics is removed and the panic is logged.
+  /// - A window handle captured by a callback counts like any other, so it
+  ///   keeps the window open until [`Window::close`] or
+  ///   [`Window::unsubscribe`]. Callbacks are dropped once the loop ends.
+  pub fn on(
+    &self,
+    kinds: MessageKindSet,
+    callback: impl FnMut(&Message) -> Control + Send + 'static,
+  ) -> SubscriptionId {
+    self
+      .0
+      .data
+      .lock()
+      .unwrap()
+      .subscriptions
+      .subscribe(kinds, callback)
+  }
+
+  /// Remove a callback registered with [`Window::on`].
+  pub fn unsubscribe(&self, id: SubscriptionId) {
+    self.0.data.lock().unwrap().subscriptions.unsubscribe(id)
+  }
+
+  fn force_set_window_region(&self, region: Option<Region>) {
+    self.0.data.lock().unwrap().window_region = region.clone();
+    Command::SetWindowRegion(region).post(self.0.hwnd);
+  }
+
+  /// Clip the window to `region`, e.g. for a non-rectangular splash screen.
+  /// Clicks outside the region fall through to whatever is below, and
+  /// painting and capture are clipped to it as well. [`None`] restores the
+  /// default rectangular region. See [`Region`].
+  pub fn set_window_region(&self, region: Option<Region>) {
+    if region == self.0.data.lock().unwrap().window_region {
+      return;
+    }
+    self.force_set_window_region(region)
+  }
+
+  pub fn window_region(&self) -> Option<Region> {
+    self.0.data.lock().unwrap().window_region.clone()
+  }
+
+  fn force_set_window_icon(&self, icon: Option<Icon>) {
+    self.0.data.lock().unwrap().window_icon = icon.clone();
+    Command::SetWindowIcon(icon).post(self.0.hwnd);
+  }
+
+  /// Set the icon shown in the title bar and on the taskbar button. [`None`]
+  /// restores the default application icon.
+  pub fn set_window_icon(&self, icon: Option<Icon>) {
+    if icon == self.0.data.lock().unwrap().window_icon {
+      return;
+    }
+    self.force_set_window_icon(icon)
+  }
+
+  /// Use the RGBA pixels as the window icon. See [`Icon::from_rgba`] and
+  /// [`Window::set_window_icon`].
+  pub fn set_icon(
+    &self,
+    width: u32,
+    height: u32,
+    rgba: &[u8],
+  ) -> Result<(), WindowError> {
+    let icon = Icon::from_rgba(rgba.to_vec(), width, height)?;
+    self.set_window_icon(Some(icon));
+    Ok(())
+  }
+
+  pub fn window_icon(&self) -> Option<Icon> {
+    self.0.data.lock().unwrap().window_icon.clone()
+  }
+
   fn force_request_redraw(&self) {
     self.0.data.lock().unwrap().requested_redraw = true;
     Command::Redraw.post(self.0.hwnd);
   }
 
+  /// Show the window the next time [`Window::notify_presented`] is called,
+  /// so it appears with a rendered frame instead of flashing white. The window
+  /// is hidden until then.
+  pub fn reveal_after_first_present(&self) {
+    self.0.data.lock().unwrap().reveal_on_present = true;
+    self.set_visibility(Visibility::Hidden);
+  }
+
+  /// Tell the window a frame has been presented to it. Shows the window if
+  /// it is waiting to be revealed, otherwise does nothing, so it can be called
+  /// after every present.
+  pub fn notify_presented(&self) {
+    let reveal = std::mem::take(&mut self.0.data.lock().unwrap().reveal_on_present);
+    if reveal {
+      self.set_visibility(Visibility::Shown);
+    }
+  }
+
+  /// Move the popup so its `anchor` corner is at `position` in screen
+  /// coordinates, then show it. The popup flips to the opposite side where it
+  /// would leave the work area of the monitor, like a context menu near the
+  /// edge of the screen, and is pushed back onto it if it doesn't fit either
+  /// way. See [`WindowBuilder::with_popup`](crate::WindowBuilder::with_popup).
+  pub fn show_popup_at(&self, position: PhysicalPosition, anchor: PopupAnchor) {
+    let size = self.outer_size();
+    let work_area = self.monitor_from_point(position).work_area();
+    let (width, height) = (size.width as i32, size.height as i32);
+
+    // start of the popup along one axis, flipped if it leaves the work area
+    // on that side but fits on the other
+    let place = |point: i32, extent: i32, backwards: bool, min: i32, max: i32| {
+      let fits_forwards = point + extent <= max;
+      let fits_backwards = point - extent >= min;
+      let start = match backwards {
+        false if !fits_forwards && fits_backwards => point - extent,
+        true if !fits_backwards && fits_forwards => point,
+        false => point,
+        true => point - extent,
+      };
+      start.clamp(min, (max - extent).max(min))
+    };
+
+    let (right, bottom) = match anchor {
+      PopupAnchor::TopLeft => (false, false),
+      PopupAnchor::TopRight => (true, false),
+      PopupAnchor::BottomLeft => (false, true),
+      PopupAnchor::BottomRight => (true, true),
+    };
+    let x = place(position.x, width, right, work_area.left(), work_area.right());
+    let y = place(position.y, height, bottom, work_area.top(), work_area.bottom());
+
+    self.set_outer_position(PhysicalPosition::new(x, y).into());
+    self.set_visibility(Visibility::Shown);
+  }
+
+  /// Flash the title bar and taskbar button once, e.g. as a subtle sign that
+  /// a long task has finished. Unlike a continuous request for attention, the
+  /// flash doesn't repeat, though the taskbar button stays highlighted until
+  /// the window is activated. Does nothing if the window is already active.
+  pub fn flash_once(&self) {
+    self.flash(FlashMode::Once);
+  }
+
+  /// Flash the title bar and taskbar button as `mode` says. See [`FlashMode`].
+  /// Does nothing if the window is already active, except for stopping.
+  pub fn flash(&self, mode: FlashMode) {
+    Command::Flash(mode).post(self.0.hwnd);
+  }
+
+  /// Flash the window until it is activated, e.g. when a long task needs
+  /// input. [`None`] stops flashing early. The flashing stops on its own once
+  /// the window is activated, so it never keeps going after the user looked at
+  /// it. Does nothing if the window is already active.
+  ///
+  /// A shorthand for [`Window::flash`] with
+  /// [`FlashMode::UntilFocused`], [`FlashMode::TaskbarUntilFocused`] or
+  /// [`FlashMode::Stop`].
+  pub fn request_user_attention(&self, attention: Option<UserAttention>) {
+    self.flash(attention.map_or(FlashMode::Stop, FlashMode::from));
+  }
+
+  /// Bring the window to the foreground and give it keyboard focus, restoring
+  /// it first if it is minimized. Windows only lets this through while the app
+  /// is in the foreground or was the last to receive input, otherwise the
+  /// taskbar button flashes instead.
+  pub fn focus_window(&self) {
+    Command::Focus.post(self.0.hwnd);
+  }
+
   /// Request a new Draw event
   pub fn request_redraw(&self) {
     if self.0.data.lock().unwrap().requested_redraw {
@@ -713,12 +2203,59 @@ impl Window {
     self.force_request_redraw()
   }
 
-  /// Request the window be closed
+  /// Automatically request a new Draw event after every [`Message::Paint`],
+  /// rendering continuously instead of on demand. Pass `false` to return to
+  /// on-demand rendering with [`Window::request_redraw`].
+  pub fn set_continuous_redraw(&self, continuous: bool) {
+    {
+      let mut data = self.0.data.lock().unwrap();
+      if data.continuous_redraw == continuous {
+        return;
+      }
+      data.continuous_redraw = continuous;
+    }
+    if continuous {
+      self.request_redraw();
+    }
+  }
+
+  /// Close the window in answer to a pending [`Message::CloseRequested`], e.g.
+  /// once the user chose to discard their changes. The final
+  /// [`LoopMessage::Exit`] reports [`ExitReason::UserClosed`]. Does nothing if
+  /// no close request is pending. See [`Window::is_close_pending`].
+  ///
+  /// Requests are only left pending when `close_on_x` doesn't handle them,
+  /// i.e. with [`WindowBuilder::with_close_on_x`] disabled or the close
+  /// button disabled.
+  pub fn confirm_close(&self) {
+    let pending = std::mem::take(&mut self.0.data.lock().unwrap().close_pending);
+    if pending {
+      self.close_with(ExitReason::UserClosed);
+    }
+  }
+
+  /// Keep the window open in answer to a pending
+  /// [`Message::CloseRequested`]. The user can request another close later.
+  pub fn cancel_close(&self) {
+    self.0.data.lock().unwrap().close_pending = false;
+  }
+
+  /// Request the window be closed. The iterator yields [`LoopMessage::Exit`]
+  /// next and then ends. See [`Window`] for the full shutdown order.
   pub fn close(&self) {
+    self.close_with(ExitReason::AppRequested);
+  }
+
+  /// Request the window be closed, reporting `reason` in the final
+  /// [`LoopMessage::Exit`] message.
+  pub fn close_with(&self, reason: ExitReason) {
     if self.is_closing() {
       return; // already closing
     }
-    self.0.data.lock().unwrap().stage = Stage::Closing;
+    let mut data = self.0.data.lock().unwrap();
+    data.close_pending = false;
+    data.exit_reason = Some(reason);
+    data.stage = Stage::Closing;
   }
 
   #[cfg(all(feature = "rwh_06", not(feature = "rwh_05")))]
@@ -780,6 +2317,7 @@ impl Window {
           "[`{}`]: preparing to immutably iterate over messages",
           self.title()
         );
+        self.queue_initial_state_messages();
         self.0.data.lock().unwrap().stage = Stage::Looping;
       }
       Stage::ExitLoop => {
@@ -804,6 +2342,7 @@ impl Window {
           "[`{}`]: preparing to mutably iterate over messages",
           self.title()
         );
+        self.queue_initial_state_messages();
         self.0.data.lock().unwrap().stage = Stage::Looping;
       }
       Stage::ExitLoop => {
@@ -862,3 +2401,83 @@ impl<'a> IntoIterator for &'a mut Window {
     self.iter_mut()
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Barrier;
+
+  use super::*;
+
+  fn hidden_window() -> Window {
+    Window::builder()
+      .with_title("witer test")
+      .with_visibility(Visibility::Hidden)
+      .build()
+      .unwrap()
+  }
+
+  #[test]
+  fn closing_from_message_handling_yields_exit_last() {
+    let window = hidden_window();
+    let internal = Arc::downgrade(&window.0);
+
+    let mut after_close = Vec::new();
+    for message in &window {
+      if window.is_closing() {
+        after_close.push(message);
+      } else {
+        window.close();
+      }
+    }
+
+    assert_eq!(after_close, vec![Message::Loop(LoopMessage::Exit(
+      ExitReason::AppRequested
+    ))]);
+    // the iterator has ended, but the window lives until the last handle drops
+    assert!(window.is_closing());
+    assert!(internal.upgrade().is_some());
+    drop(window);
+    assert!(internal.upgrade().is_none());
+  }
+
+  #[test]
+  fn handles_captured_by_callbacks_are_released_with_the_loop() {
+    let window = hidden_window();
+    let internal = Arc::downgrade(&window.0);
+    let captured = window.clone();
+    window.on(MessageKindSet::ALL, move |_| {
+      let _ = captured.is_closing();
+      Control::Continue
+    });
+
+    window.close();
+    for _ in &window {}
+    drop(window);
+    assert!(internal.upgrade().is_none());
+  }
+
+  #[test]
+  fn last_handles_dropped_together_release_the_window() {
+    for _ in 0..8 {
+      let window = hidden_window();
+      let internal = Arc::downgrade(&window.0);
+      let barrier = Arc::new(Barrier::new(2));
+
+      let threads: Vec<_> = [window.clone(), window]
+        .into_iter()
+        .map(|handle| {
+          let barrier = barrier.clone();
+          std::thread::spawn(move || {
+            barrier.wait();
+            drop(handle);
+          })
+        })
+        .collect();
+      for thread in threads {
+        thread.join().unwrap();
+      }
+
+      assert!(internal.upgrade().is_none());
+    }
+  }
+}
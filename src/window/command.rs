@@ -1,49 +1,196 @@
+use std::time::Duration;
+
 use cursor_icon::CursorIcon;
 use windows::{
   core::HSTRING,
   Win32::{
-    Foundation::{HWND, LPARAM, WPARAM},
+    Foundation::{
+      ERROR_INVALID_WINDOW_HANDLE,
+      ERROR_NOT_ENOUGH_QUOTA,
+      HWND,
+      LPARAM,
+      WPARAM,
+    },
     UI::WindowsAndMessaging::{self, PostMessageW, SendMessageW},
   },
 };
 
-use super::data::{CursorMode, Fullscreen, Position, Size, Visibility};
+use super::{
+  data::{
+    Color,
+    CursorMode,
+    FlashMode,
+    Fullscreen,
+    Margins,
+    PhysicalRect,
+    Position,
+    Size,
+    Visibility,
+    WindowButtons,
+  },
+  icon::Icon,
+  region::Region,
+};
+use crate::error::WindowError;
 
 #[repr(u32)]
 #[derive(Debug, Clone, PartialEq)]
 pub enum Command {
   Exit,
   Destroy,
+  /// Does nothing besides waking the message pump.
+  Wake,
   Redraw,
   SetVisibility(Visibility),
+  SetMinimized(bool),
+  Focus,
+  Flash(FlashMode),
+  SetMaximized(bool),
   SetDecorations(Visibility),
+  SetResizable(bool),
+  SetEnabledButtons(WindowButtons),
+  SetFocusable(bool),
+  SetTransparencyColorKey(Option<Color>),
   SetWindowText(HSTRING),
   SetSize(Size),
   SetPosition(Position),
-  SetFullscreen(Option<Fullscreen>),
+  /// Set the outer bounds the window restores to, in screen coordinates.
+  SetNormalRect(PhysicalRect),
+  /// Change the fullscreen state, then reapply the cached cursor mode and
+  /// visibility if `apply_cursor` is set, so the cursor is never confined to
+  /// the old bounds.
+  SetFullscreen {
+    fullscreen: Option<Fullscreen>,
+    apply_cursor: bool,
+  },
   SetCursorIcon(CursorIcon),
   SetCursorMode(CursorMode),
   SetCursorVisibility(Visibility),
+  SetDwmMargins(Option<Margins>),
+  SetWindowRegion(Option<Region>),
+  /// Place the window directly below the given window in the z-order.
+  SetZOrderAfter(HWND),
+  SetAppUserModelId(Option<String>),
+  SetWindowIcon(Option<Icon>),
 }
 
 impl Command {
   pub const MESSAGE_ID: u32 = WindowsAndMessaging::WM_USER + 69;
+  /// How long posting [`Command::Exit`] and [`Command::Destroy`] keeps
+  /// retrying while the queue is full, doubling the wait each time.
+  const RETRY_BACKOFF: [Duration; 8] = [
+    Duration::from_millis(1),
+    Duration::from_millis(2),
+    Duration::from_millis(4),
+    Duration::from_millis(8),
+    Duration::from_millis(16),
+    Duration::from_millis(32),
+    Duration::from_millis(64),
+    Duration::from_millis(128),
+  ];
 
+  /// Posts the command, logging if it is dropped. Commands that end the window
+  /// are retried while the queue is full, so the window always goes away.
   pub fn post(self, hwnd: HWND) {
-    let command = Box::leak(Box::new(self));
-    let addr = command as *mut Command as usize;
-    unsafe {
-      if let Err(e) = PostMessageW(hwnd, Self::MESSAGE_ID, WPARAM(addr), LPARAM(0)) {
-        tracing::error!("{e}");
+    let critical = matches!(self, Command::Exit | Command::Destroy);
+    let mut command = self;
+    let mut backoff = Self::RETRY_BACKOFF.iter();
+    loop {
+      match command.post_or_return(hwnd) {
+        Ok(()) => return,
+        Err((returned, WindowError::CommandQueueFull)) if critical => {
+          let Some(wait) = backoff.next() else {
+            tracing::error!("gave up posting {returned:?}, the queue stayed full");
+            return;
+          };
+          tracing::warn!("command queue is full, retrying {returned:?} in {wait:?}");
+          std::thread::sleep(*wait);
+          command = returned;
+        }
+        Err((returned, error)) => {
+          tracing::error!("dropped {returned:?}: {error}");
+          return;
+        }
+      }
+    }
+  }
+
+  /// Posts the command, or returns why it couldn't be. The command is never
+  /// retried.
+  pub fn try_post(self, hwnd: HWND) -> Result<(), WindowError> {
+    self.post_or_return(hwnd).map_err(|(_, error)| error)
+  }
+
+  /// Hands the command back if it wasn't posted, which also keeps it from
+  /// leaking.
+  fn post_or_return(self, hwnd: HWND) -> Result<(), (Command, WindowError)> {
+    let wparam = self.into_wparam();
+    match unsafe { PostMessageW(hwnd, Self::MESSAGE_ID, wparam, LPARAM(0)) } {
+      Ok(()) => Ok(()),
+      Err(error) => {
+        // never reached the window, so it's still ours
+        let command = unsafe { Self::from_wparam(wparam) };
+        let error = if error.code() == ERROR_NOT_ENOUGH_QUOTA.to_hresult() {
+          WindowError::CommandQueueFull
+        } else if error.code() == ERROR_INVALID_WINDOW_HANDLE.to_hresult() {
+          WindowError::AlreadyClosed
+        } else {
+          WindowError::Win32Error(error)
+        };
+        Err((command, error))
       }
     }
   }
 
   pub(crate) fn send(self, hwnd: HWND) {
-    let command = Box::leak(Box::new(self));
-    let addr = command as *mut Command as usize;
     unsafe {
-      SendMessageW(hwnd, Self::MESSAGE_ID, WPARAM(addr), LPARAM(0));
+      SendMessageW(hwnd, Self::MESSAGE_ID, self.into_wparam(), LPARAM(0));
+    }
+  }
+
+  /// Moves the command into the `WPARAM` of [`Command::MESSAGE_ID`]. It leaks
+  /// unless taken back with [`Command::from_wparam`].
+  pub(crate) fn into_wparam(self) -> WPARAM {
+    WPARAM(Box::into_raw(Box::new(self)) as usize)
+  }
+
+  /// Takes back the command moved into `wparam`.
+  ///
+  /// # Safety
+  ///
+  /// `wparam` must come from [`Command::into_wparam`], and each command can
+  /// only be taken back once.
+  pub(crate) unsafe fn from_wparam(wparam: WPARAM) -> Command {
+    *unsafe { Box::from_raw(wparam.0 as *mut Command) }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::window::data::LogicalSize;
+
+  fn round_trip(command: Command) {
+    let wparam = command.clone().into_wparam();
+    assert_eq!(unsafe { Command::from_wparam(wparam) }, command);
+  }
+
+  #[test]
+  fn flash_round_trips() {
+    for mode in [
+      FlashMode::Once,
+      FlashMode::UntilFocused,
+      FlashMode::TaskbarUntilFocused,
+      FlashMode::Stop,
+    ] {
+      round_trip(Command::Flash(mode));
     }
   }
+
+  #[test]
+  fn commands_with_payloads_round_trip() {
+    round_trip(Command::Exit);
+    round_trip(Command::SetSize(Size::Logical(LogicalSize::new(800.0, 600.0))));
+    round_trip(Command::SetAppUserModelId(Some("witer.test".to_owned())));
+  }
 }
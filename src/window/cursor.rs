@@ -10,3 +10,28 @@ pub struct Cursor {
   pub last_position: PhysicalPosition,
   pub selected_icon: CursorIcon,
 }
+
+/// A snapshot of the cursor as the window last saw it. See
+/// [`Window::cursor_state`](crate::Window::cursor_state).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CursorState {
+  /// Whether the cursor is over the client area.
+  pub inside: bool,
+  /// The last position inside the client area, or where the cursor left it.
+  pub position: PhysicalPosition,
+  pub mode: CursorMode,
+  pub visibility: Visibility,
+  pub icon: CursorIcon,
+}
+
+impl From<&Cursor> for CursorState {
+  fn from(cursor: &Cursor) -> Self {
+    Self {
+      inside: cursor.inside_window,
+      position: cursor.last_position,
+      mode: cursor.mode,
+      visibility: cursor.visibility,
+      icon: cursor.selected_icon,
+    }
+  }
+}
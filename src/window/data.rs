@@ -1,13 +1,22 @@
 use std::{
+  collections::VecDeque,
+  ffi::OsString,
   ops::{Div, Mul},
-  sync::{Arc, Condvar, Mutex, MutexGuard},
+  sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc,
+    Condvar,
+    Mutex,
+    MutexGuard,
+  },
   thread::JoinHandle,
+  time::Duration,
 };
 
 use windows::{
   core::PCWSTR,
   Win32::{
-    Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, RECT, WPARAM},
+    Foundation::{COLORREF, HINSTANCE, HWND, LPARAM, LRESULT, POINT, RECT, WPARAM},
     Graphics::Gdi::{
       self,
       GetMonitorInfoW,
@@ -16,27 +25,41 @@ use windows::{
       RedrawWindow,
       MONITORINFO,
     },
+    System::DataExchange::COPYDATASTRUCT,
     UI::{
       self,
       Controls,
       Input::{
-        KeyboardAndMouse::{self, TrackMouseEvent, TRACKMOUSEEVENT},
+        Ime,
+        KeyboardAndMouse::{self, ReleaseCapture, SetCapture, SetFocus},
+        Touch::HTOUCHINPUT,
         HRAWINPUT,
         RID_DEVICE_INFO_TYPE,
       },
+      Shell::HDROP,
       WindowsAndMessaging::{
         self,
         DefWindowProcW,
+        DestroyIcon,
         GetClientRect,
+        GetPropW,
+        GetWindowLongW,
         GetWindowRect,
-        LoadCursorW,
-        PostMessageW,
-        SetCursor,
+        IsIconic,
+        IsWindow,
+        IsZoomed,
+        SendMessageW,
+        SetForegroundWindow,
+        SetLayeredWindowAttributes,
         SetWindowLongW,
         SetWindowPos,
         SetWindowTextW,
         ShowWindow,
         UnregisterClassW,
+        FLASHWINFO_FLAGS,
+        HICON,
+        MINMAXINFO,
+        NCCALCSIZE_PARAMS,
         WINDOWPOS,
       },
     },
@@ -46,63 +69,198 @@ use windows::{
 use super::{
   command::Command,
   cursor::Cursor,
+  focus_gate::FocusGate,
   frame::Style,
-  input::mouse::mouse_button_states,
-  message::{get_cursor_move_kind, CursorMoveKind, Focus},
+  icon::Icon,
+  input::{
+    app_command::AppCommand,
+    mouse::{mouse_button_transitions, mouse_wheel_deltas},
+    InputBuffers,
+  },
+  message::{
+    get_cursor_move_kind,
+    CursorMoveKind,
+    ExitReason,
+    Focus,
+    MessageFilter,
+    SurfaceInvalidationReason,
+    APP_MESSAGE_LAST,
+  },
+  persistence::{StatePersistence, WindowPlacement},
+  region::Region,
   stage::Stage,
+  subscription::Subscriptions,
 };
 use crate::{
   error::WindowError,
+  single_instance::{read_instance_args, INSTANCE_PROPERTY},
   utilities::{
+    attach_input_to_owner,
+    detach_input,
     dpi_to_scale_factor,
+    extend_frame_into_client_area,
+    flash_window,
     get_window_ex_style,
     get_window_style,
     hi_word,
+    hit_test_cursor_icon,
+    hwnd_dpi,
+    ime_preedit,
+    ime_result_text,
     is_flag_set,
+    is_printable_char,
     lo_word,
+    maximized_bounds,
+    normal_rect,
+    outer_size_for_inner,
+    read_dropped_files,
+    read_pen_pointer,
     read_raw_input,
-    set_cursor_clip,
+    read_touch_input,
+    resize_border_hit_test,
+    resolve_theme,
+    restore_display_mode,
+    set_borderless_fullscreen_pos,
+    set_close_button_enabled,
+    set_cursor_mode,
     set_cursor_visibility,
+    set_dark_titlebar,
+    set_display_mode,
+    set_normal_rect,
+    set_os_cursor_icon,
+    set_window_app_user_model_id,
+    set_window_region,
     signed_hi_word,
     signed_lo_word,
-    to_windows_cursor,
+    sizing_frame,
+    system_accent_colors,
+    track_mouse_leave,
+    Monitor,
   },
-  window::Input,
   Key,
+  KeyState,
   Message,
-  MouseButton,
   RawInputMessage,
   RawKeyState,
 };
 
+/// How a message is answered once witer has handled it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Response {
+  /// Pass the message on to `DefWindowProcW` and return its result.
+  Default,
+  /// Return this result without calling `DefWindowProcW`.
+  Handled(LRESULT),
+}
+
 #[derive(Clone)]
 pub struct SyncData {
   pub message: Arc<Mutex<Option<Message>>>,
   pub new_message: Arc<(Mutex<bool>, Condvar)>,
   pub next_frame: Arc<(Mutex<bool>, Condvar)>,
+  /// Messages waiting to be taken when the window lives on the calling thread,
+  /// or when the window thread is allowed to run ahead of the main thread.
+  pub queue: Arc<Mutex<VecDeque<Message>>>,
+  pub current_thread: bool,
+  /// How many messages the window thread may queue before it waits on the main
+  /// thread. `0` keeps the two threads in lockstep.
+  pub message_buffer: usize,
+  /// How long the window thread waits on the main thread before it considers
+  /// it stalled and stops waiting.
+  pub stall_timeout: Option<Duration>,
+  pub stall_policy: StallPolicy,
+  /// Set while the main thread is stalled, until it starts a new frame.
+  pub stalled: Arc<AtomicBool>,
+  /// Follows the keyboard focus for other threads, without the data lock.
+  pub focus_gate: FocusGate,
 }
 
 impl SyncData {
+  /// How many messages are kept at most while the main thread is stalled.
+  const STALLED_QUEUE_LIMIT: usize = 1024;
+
   pub fn send_to_main(&self, message: Message, state: &Internal) {
-    let should_wait = self.message.lock().unwrap().is_some();
-    if should_wait {
-      self.wait_on_frame(|| {
-        matches!(
-          state.data.lock().unwrap().stage,
-          Stage::Setup | Stage::ExitLoop | Stage::Destroyed
-        )
-      });
+    if self.current_thread {
+      // the main thread is the one pumping, so waiting here would deadlock
+      self.queue.lock().unwrap().push_back(message);
+      return;
     }
 
-    self.message.lock().unwrap().replace(message);
-    self.signal_new_message();
+    if self.stalled.load(Ordering::Acquire) {
+      self.send_while_stalled(message);
+      return;
+    }
 
-    self.wait_on_frame(|| {
+    let interrupt = || {
       matches!(
         state.data.lock().unwrap().stage,
         Stage::Setup | Stage::ExitLoop | Stage::Destroyed
       )
-    });
+    };
+
+    if self.message_buffer > 0 {
+      // only wait once the buffer is full, so a stalled main thread doesn't
+      // stall the window thread until it falls `message_buffer` messages behind
+      while self.queue.lock().unwrap().len() >= self.message_buffer && !interrupt() {
+        if !self.wait_on_frame(interrupt) {
+          self.send_while_stalled(message);
+          return;
+        }
+      }
+
+      self.queue.lock().unwrap().push_back(message);
+      self.signal_new_message();
+      return;
+    }
+
+    let should_wait = self.message.lock().unwrap().is_some();
+    if should_wait && !self.wait_on_frame(interrupt) {
+      self.send_while_stalled(message);
+      return;
+    }
+
+    {
+      // messages kept while the main thread was stalled have to go first
+      let mut queue = self.queue.lock().unwrap();
+      if queue.is_empty() {
+        self.message.lock().unwrap().replace(message);
+      } else {
+        queue.push_back(message);
+      }
+    }
+    self.signal_new_message();
+
+    self.wait_on_frame(interrupt);
+  }
+
+  /// Queues the message without waiting on the main thread, or drops it
+  /// depending on the [`StallPolicy`].
+  fn send_while_stalled(&self, message: Message) {
+    let keep = match self.stall_policy {
+      StallPolicy::DropLowPriority => !message.is_low_priority(),
+      StallPolicy::DropAll => false,
+    };
+    if !keep {
+      return;
+    }
+
+    {
+      let mut queue = self.queue.lock().unwrap();
+      if queue.len() >= Self::STALLED_QUEUE_LIMIT {
+        return;
+      }
+      queue.push_back(message);
+    }
+    self.signal_new_message();
+  }
+
+  pub fn take_queued(&self) -> Option<Message> {
+    let message = self.message.lock().unwrap().take();
+    message.or_else(|| self.queue.lock().unwrap().pop_front())
+  }
+
+  pub fn has_queued(&self) -> bool {
+    self.message.lock().unwrap().is_some() || !self.queue.lock().unwrap().is_empty()
   }
 
   pub fn signal_new_message(&self) {
@@ -112,15 +270,47 @@ impl SyncData {
     cvar.notify_one();
   }
 
-  pub fn wait_on_frame(&self, interrupt: impl Fn() -> bool) {
+  /// Returns `false` if the main thread didn't start a new frame within the
+  /// stall timeout, in which case it is marked as stalled.
+  pub fn wait_on_frame(&self, interrupt: impl Fn() -> bool) -> bool {
     let (lock, cvar) = self.next_frame.as_ref();
-    let mut next = cvar
-      .wait_while(lock.lock().unwrap(), |next| !*next && !interrupt())
-      .unwrap();
+    let (mut next, timed_out) = match self.stall_timeout {
+      Some(timeout) => {
+        let (next, result) = cvar
+          .wait_timeout_while(lock.lock().unwrap(), timeout, |next| {
+            !*next && !interrupt()
+          })
+          .unwrap();
+        (next, result.timed_out())
+      }
+      None => (
+        cvar
+          .wait_while(lock.lock().unwrap(), |next| !*next && !interrupt())
+          .unwrap(),
+        false,
+      ),
+    };
     *next = false;
+
+    if timed_out && !self.stalled.swap(true, Ordering::AcqRel) {
+      tracing::warn!(
+        "main thread hasn't taken a window message in {:?}, {} until it resumes",
+        self.stall_timeout.unwrap_or_default(),
+        match self.stall_policy {
+          StallPolicy::DropLowPriority => "dropping low priority messages",
+          StallPolicy::DropAll => "dropping all messages",
+        }
+      );
+    }
+
+    !timed_out
   }
 
   pub fn signal_next_frame(&self) {
+    if self.stalled.swap(false, Ordering::AcqRel) {
+      tracing::info!("main thread resumed taking window messages");
+    }
+
     let (lock, cvar) = self.next_frame.as_ref();
     let mut next = lock.lock().unwrap();
     *next = true;
@@ -131,16 +321,20 @@ impl SyncData {
 pub struct Internal {
   pub hinstance: HINSTANCE,
   pub hwnd: HWND,
+  pub class_name: String,
   pub class_atom: u16,
   pub sync: SyncData,
   pub thread: Mutex<Option<JoinHandle<Result<(), WindowError>>>>,
+  /// The number of [`Window`](crate::Window) handles, which doesn't include
+  /// the reference held by the window procedure.
+  pub handles: AtomicUsize,
   pub data: Mutex<Data>,
 }
 
 /// Window is destroyed on drop.
 impl Drop for Internal {
   fn drop(&mut self) {
-    let title = self.data_lock().title.clone();
+    let title = self.data_lock().title.to_string_lossy().into_owned();
 
     if self.data_lock().stage == Stage::Destroyed {
       return;
@@ -150,8 +344,16 @@ impl Drop for Internal {
 
     tracing::trace!("[`{}`]: destroying window", title);
 
-    Command::Destroy.post(self.hwnd);
-    self.join_thread();
+    if self.sync.current_thread {
+      Command::Destroy.send(self.hwnd);
+    } else {
+      Command::Destroy.post(self.hwnd);
+      self.join_thread();
+    }
+
+    if let Some(hicon) = self.data_lock().window_hicon.take() {
+      let _ = unsafe { DestroyIcon(hicon) };
+    }
 
     tracing::trace!("[`{}`]: unregistering window class", title);
     unsafe { UnregisterClassW(PCWSTR(self.class_atom as *const u16), self.hinstance) }
@@ -161,23 +363,89 @@ impl Drop for Internal {
   }
 }
 
+#[derive(Debug)]
 pub struct Data {
-  pub title: String,
-  pub subtitle: String,
+  pub title: OsString,
+  pub subtitle: OsString,
+  pub title_separator: OsString,
   pub theme: Theme,
+  pub effective_theme: EffectiveTheme,
+  pub accent_colors: Option<AccentColors>,
+  pub manage_titlebar_theme: bool,
   pub flow: Flow,
   pub close_on_x: bool,
+  pub alt_f4: bool,
+  pub dpi_scaling: bool,
+  pub minimized_poll_interval: Duration,
+  pub raw_text: bool,
+  pub initial_state_messages: bool,
 
   pub stage: Stage,
+  pub exit_reason: Option<ExitReason>,
+  /// Set when a [`Message::CloseRequested`] wasn't handled by `close_on_x`,
+  /// until the app confirms or cancels it.
+  pub close_pending: bool,
+  /// Set once the window procedure dropped its reference to the window.
+  pub procedure_released: bool,
   pub style: Style,
-  pub input: Input,
+  pub input: InputBuffers,
+  /// The last messages taken from the window, oldest first.
+  #[cfg(feature = "diagnostics")]
+  pub message_history: VecDeque<Message>,
   pub cursor: Cursor,
+  /// Cursor mode and visibility from before
+  /// [`CursorBehavior::ConfineAndHide`].
+  pub cursor_snapshot: Option<(CursorMode, Visibility)>,
 
   pub last_windowed_position: Position,
   pub last_windowed_size: Size,
   pub scale_factor: f64,
 
+  /// Synthetic messages taken before any real ones once the loop starts.
+  pub initial_messages: VecDeque<Message>,
+
   pub requested_redraw: bool,
+  /// Whether [`LoopMessage::Idle`](crate::LoopMessage::Idle) was sent since the
+  /// last message.
+  pub idle: bool,
+  pub continuous_redraw: bool,
+  pub game_mode_hint: bool,
+  /// Show the window on the next [`Window::notify_presented`](crate::Window::notify_presented).
+  pub reveal_on_present: bool,
+  /// Set while the window flashes for attention, until it is activated.
+  pub flashing: bool,
+  /// The monitor whose display mode was changed for exclusive fullscreen.
+  pub exclusive_display: Option<[u16; 32]>,
+  pub click_activation: ClickActivation,
+  pub auto_dismiss: bool,
+  /// Whether the popup holds the mouse capture to notice clicks outside of
+  /// it. Cleared before releasing it, so losing it otherwise means dismissal.
+  pub dismiss_capture: bool,
+  /// The owner's thread the input was attached to while capturing, so the
+  /// capture also sees clicks on the owner.
+  pub dismiss_attached_thread: Option<u32>,
+  /// The button down message eaten by the last activating click, whose button
+  /// up is dropped too.
+  pub eaten_click: Option<u32>,
+  /// Logical thickness of the resize border and grips, so it follows the
+  /// scale factor.
+  pub resize_border: Option<f64>,
+  /// Kept as given, so logical sizes follow the scale factor.
+  pub min_inner_size: Option<Size>,
+  pub max_inner_size: Option<Size>,
+
+  pub dwm_margins: Option<Margins>,
+  pub message_filter: Option<MessageFilter>,
+  pub(crate) subscriptions: Subscriptions,
+  pub window_region: Option<Region>,
+  pub window_icon: Option<Icon>,
+  /// The handle made from [`Data::window_icon`], destroyed once replaced.
+  pub window_hicon: Option<HICON>,
+  pub(crate) state_persistence: Option<StatePersistence>,
+
+  /// Cleared every loop iteration and whenever the window moves or the
+  /// display configuration changes.
+  pub monitor: Option<Monitor>,
 }
 
 impl Internal {
@@ -192,12 +460,85 @@ impl Internal {
   pub(crate) fn join_thread(&self) {
     let thread = self.thread.lock().unwrap().take();
     if let Some(thread) = thread {
-      tracing::trace!("[`{}`]: joining window thread", self.data.lock().unwrap().title);
+      let title = self
+        .data
+        .lock()
+        .unwrap()
+        .title
+        .to_string_lossy()
+        .into_owned();
+      tracing::trace!("[`{}`]: joining window thread", title);
       let _ = thread.join();
-      tracing::trace!("[`{}`]: joined window thread", self.data.lock().unwrap().title);
+      tracing::trace!("[`{}`]: joined window thread", title);
     }
   }
 
+  /// Hands the placement to the storage given to
+  /// [`WindowBuilder::with_state_persistence`](crate::WindowBuilder::with_state_persistence).
+  fn save_placement(&self) {
+    let (persistence, maximized, windowed_rect) = {
+      let data = self.data.lock().unwrap();
+      let Some(persistence) = data.state_persistence.clone() else {
+        return;
+      };
+      // the normal rect of a fullscreen window is the monitor
+      let windowed_rect = data.style.fullscreen.is_some().then(|| {
+        PhysicalRect::new(
+          data.last_windowed_position.as_physical(data.scale_factor),
+          data.last_windowed_size.as_physical(data.scale_factor),
+        )
+      });
+      (persistence, data.style.maximized, windowed_rect)
+    };
+    let Some(normal_rect) = windowed_rect.or_else(|| normal_rect(self.hwnd)) else {
+      return;
+    };
+    persistence.save(WindowPlacement {
+      normal_rect,
+      maximized,
+    });
+  }
+
+  /// The single path every flash and attention request takes.
+  fn flash(&self, hwnd: HWND, mode: FlashMode) {
+    // checked on the window thread, so the window can't be activated in
+    // between and be left flashing
+    let flash = {
+      let mut data = self.data.lock().unwrap();
+      data.flashing = mode != FlashMode::Stop && !data.style.active;
+      data.flashing || mode == FlashMode::Stop
+    };
+    if flash {
+      let (flags, count) = mode.flash_window_args();
+      flash_window(hwnd, flags, count);
+    }
+  }
+
+  /// Makes the window procedure drop its reference, so dropping the last
+  /// [`Window`](crate::Window) destroys the window. Blocks until the window
+  /// thread has let go, which keeps the window from ever being destroyed on
+  /// its own thread. Only the first call does anything.
+  pub(crate) fn release_procedure(&self) {
+    let subscriptions = {
+      let mut data = self.data.lock().unwrap();
+      if std::mem::replace(&mut data.procedure_released, true) {
+        return;
+      }
+      // stops the window thread from waiting on frames that won't come
+      if data.stage != Stage::Destroyed {
+        data.stage = Stage::ExitLoop;
+      }
+      std::mem::take(&mut data.subscriptions)
+    };
+    self.sync.signal_next_frame();
+    self.sync.focus_gate.close();
+    self.save_placement();
+    Command::Exit.send(self.hwnd);
+    // callbacks can't run anymore, and any handles they captured would keep
+    // the window alive. Dropped without the lock, as those handles take it.
+    drop(subscriptions);
+  }
+
   pub(crate) fn is_closing(&self) -> bool {
     matches!(
       self.data.lock().unwrap().stage,
@@ -208,7 +549,159 @@ impl Internal {
   // pub(crate) fn exit_loop(&self) {
   // }
 
+  /// The frame extension which keeps the shadow of a borderless window, used
+  /// while no margins were set with
+  /// [`Window::set_dwm_margins`](crate::Window::set_dwm_margins).
+  fn shadow_margins(&self) -> Option<Margins> {
+    let style = &self.data.lock().unwrap().style;
+    (style.borderless_shadow && !style.popup).then_some(Margins::SHADOW)
+  }
+
+  /// Reapplies both styles, for changes that affect the frame.
+  fn update_style(&self, hwnd: HWND) {
+    let style = self.data.lock().unwrap().style.clone();
+    unsafe {
+      SetWindowLongW(
+        hwnd,
+        WindowsAndMessaging::GWL_STYLE,
+        get_window_style(&style).0 as i32,
+      )
+    };
+    self.update_ex_style(hwnd);
+  }
+
+  /// Applies the extended style derived from the current [`Style`].
+  fn update_ex_style(&self, hwnd: HWND) {
+    let style = self.data.lock().unwrap().style.clone();
+    unsafe {
+      SetWindowLongW(
+        hwnd,
+        WindowsAndMessaging::GWL_EXSTYLE,
+        get_window_ex_style(&style).0 as i32,
+      );
+      if let Err(e) = SetWindowPos(
+        hwnd,
+        None,
+        0,
+        0,
+        0,
+        0,
+        WindowsAndMessaging::SWP_NOZORDER
+          | WindowsAndMessaging::SWP_NOMOVE
+          | WindowsAndMessaging::SWP_NOSIZE
+          | WindowsAndMessaging::SWP_NOACTIVATE
+          | WindowsAndMessaging::SWP_FRAMECHANGED,
+      ) {
+        tracing::error!("{e}");
+      }
+    }
+  }
+
+  /// Takes or releases the mouse capture used to notice clicks outside of an
+  /// auto-dismissing popup.
+  fn set_dismiss_capture(&self, hwnd: HWND, capture: bool) {
+    if capture {
+      let attached_thread = attach_input_to_owner(hwnd);
+      {
+        let mut data = self.data.lock().unwrap();
+        data.dismiss_capture = true;
+        data.dismiss_attached_thread = attached_thread;
+      }
+      unsafe { SetCapture(hwnd) };
+    } else {
+      if std::mem::take(&mut self.data.lock().unwrap().dismiss_capture) {
+        if let Err(error) = unsafe { ReleaseCapture() } {
+          tracing::error!("{error}");
+        }
+      }
+      self.detach_dismiss_input();
+    }
+  }
+
+  fn detach_dismiss_input(&self) {
+    let attached_thread = self.data.lock().unwrap().dismiss_attached_thread.take();
+    if let Some(thread) = attached_thread {
+      detach_input(thread);
+    }
+  }
+
+  /// Whether `msg` is a button press outside of the client area while the
+  /// popup holds the mouse capture for dismissal.
+  fn is_dismissing_click(&self, hwnd: HWND, msg: u32, lparam: LPARAM) -> bool {
+    let is_button_down = matches!(
+      msg,
+      WindowsAndMessaging::WM_LBUTTONDOWN
+        | WindowsAndMessaging::WM_RBUTTONDOWN
+        | WindowsAndMessaging::WM_MBUTTONDOWN
+        | WindowsAndMessaging::WM_XBUTTONDOWN
+    );
+    if !is_button_down || !self.data.lock().unwrap().dismiss_capture {
+      return false;
+    }
+
+    let mut client_rect = RECT::default();
+    let _ = unsafe { GetClientRect(hwnd, &mut client_rect) };
+    let x = signed_lo_word(lparam.0 as i32) as i32;
+    let y = signed_hi_word(lparam.0 as i32) as i32;
+    x < client_rect.left
+      || y < client_rect.top
+      || x >= client_rect.right
+      || y >= client_rect.bottom
+  }
+
+  fn enter_exclusive_mode(&self, hwnd: HWND, mode: VideoMode) {
+    let monitor =
+      Monitor::new(unsafe { MonitorFromWindow(hwnd, Gdi::MONITOR_DEFAULTTONEAREST) });
+    let Some(device) = monitor.device_name() else {
+      tracing::error!("failed to query the monitor for exclusive fullscreen");
+      return;
+    };
+    match set_display_mode(&device, mode) {
+      Ok(()) => self.data.lock().unwrap().exclusive_display = Some(device),
+      Err(error) => tracing::error!("{error}"),
+    }
+  }
+
+  /// Restores the display mode changed for exclusive fullscreen, if any.
+  pub(crate) fn leave_exclusive_mode(&self) {
+    let device = self.data.lock().unwrap().exclusive_display.take();
+    if let Some(device) = device {
+      restore_display_mode(&device);
+    }
+  }
+
+  /// Queries the accent colors again, returning them if they changed.
+  fn update_accent_colors(&self) -> Option<AccentColors> {
+    let accent_colors = system_accent_colors()?;
+    let mut data = self.data.lock().unwrap();
+    (data.accent_colors.replace(accent_colors) != Some(accent_colors))
+      .then_some(accent_colors)
+  }
+
+  /// Resolves the requested theme and applies it to the title bar. Returns
+  /// `true` if the effective theme changed.
+  pub(crate) fn update_effective_theme(&self) -> bool {
+    let (effective_theme, changed, manage_titlebar_theme) = {
+      let mut data = self.data.lock().unwrap();
+      let effective_theme = resolve_theme(data.theme);
+      let changed = data.effective_theme != effective_theme;
+      data.effective_theme = effective_theme;
+      (effective_theme, changed, data.manage_titlebar_theme)
+    };
+
+    if manage_titlebar_theme {
+      set_dark_titlebar(self.hwnd, effective_theme == EffectiveTheme::Dark);
+    }
+
+    changed
+  }
+
   pub(crate) fn update_last_windowed_pos_size(&self, hwnd: HWND) {
+    // minimized windows report a placeholder rect far off screen
+    if unsafe { IsIconic(hwnd) }.as_bool() {
+      return;
+    }
+
     let mut window_rect = RECT::default();
     let _ = unsafe { GetWindowRect(hwnd, &mut window_rect) };
     let size = PhysicalSize {
@@ -223,6 +716,98 @@ impl Internal {
     self.data.lock().unwrap().last_windowed_position = position.into();
   }
 
+  fn on_dpi_changed(
+    &self,
+    hwnd: HWND,
+    wparam: WPARAM,
+    lparam: LPARAM,
+    messages: &mut Vec<Message>,
+  ) {
+    let dpi = lo_word(wparam.0 as u32) as u32;
+    let suggested_rect = unsafe { *(lparam.0 as *const RECT) };
+    // the window may be going away while it moves between monitors
+    if let Err(error) = unsafe {
+      SetWindowPos(
+        hwnd,
+        None,
+        suggested_rect.left,
+        suggested_rect.top,
+        suggested_rect.right - suggested_rect.left,
+        suggested_rect.bottom - suggested_rect.top,
+        WindowsAndMessaging::SWP_NOZORDER | WindowsAndMessaging::SWP_NOACTIVATE,
+      )
+    } {
+      tracing::error!("{error}");
+    }
+    let scale_factor = dpi_to_scale_factor(dpi);
+    let region = {
+      let mut data = self.data.lock().unwrap();
+      data.scale_factor = scale_factor;
+      data.monitor = None;
+      data.window_region.clone()
+    };
+    if let Some(region) = region.filter(Region::is_logical) {
+      set_window_region(hwnd, Some(&region), scale_factor);
+    }
+    messages.push(Message::ScaleFactorChanged(scale_factor));
+  }
+
+  fn on_raw_input(&self, lparam: LPARAM, messages: &mut Vec<Message>) {
+    let Some(data) = read_raw_input(HRAWINPUT(lparam.0)) else {
+      return;
+    };
+
+    match RID_DEVICE_INFO_TYPE(data.header.dwType) {
+      UI::Input::RIM_TYPEMOUSE => {
+        let mouse_data = unsafe { data.data.mouse };
+        let button_flags = unsafe { mouse_data.Anonymous.Anonymous.usButtonFlags };
+
+        if mouse_data.usFlags == UI::Input::MOUSE_MOVE_RELATIVE {
+          let x = mouse_data.lLastX as f32;
+          let y = mouse_data.lLastY as f32;
+
+          if x != 0.0 || y != 0.0 {
+            messages.push(Message::RawInput(RawInputMessage::MouseMove {
+              delta_x: x,
+              delta_y: y,
+            }));
+          }
+        }
+
+        for (button, state) in mouse_button_transitions(button_flags) {
+          messages.push(Message::RawInput(RawInputMessage::MouseButton { button, state }))
+        }
+
+        let button_data = unsafe { mouse_data.Anonymous.Anonymous.usButtonData };
+        for (delta_x, delta_y) in mouse_wheel_deltas(button_flags, button_data) {
+          messages
+            .push(Message::RawInput(RawInputMessage::MouseWheel { delta_x, delta_y }))
+        }
+      }
+      UI::Input::RIM_TYPEKEYBOARD => {
+        let keyboard_data = unsafe { data.data.keyboard };
+
+        let Some(key) = Key::from_raw(keyboard_data) else {
+          return;
+        };
+
+        let pressed = matches!(
+          keyboard_data.Message,
+          WindowsAndMessaging::WM_KEYDOWN | WindowsAndMessaging::WM_SYSKEYDOWN
+        );
+        let released = matches!(
+          keyboard_data.Message,
+          WindowsAndMessaging::WM_KEYUP | WindowsAndMessaging::WM_SYSKEYUP
+        );
+
+        if let Some(state) = RawKeyState::from_bools(pressed, released) {
+          messages.push(Message::RawInput(RawInputMessage::Keyboard { key, state }));
+        }
+      }
+      _ => (),
+    };
+  }
+
   pub(crate) fn on_message(
     &self,
     hwnd: HWND,
@@ -233,22 +818,103 @@ impl Internal {
     let mut messages = Vec::with_capacity(0);
     messages.reserve_exact(1);
 
-    let result = match msg {
+    let response = match msg {
       Command::MESSAGE_ID => {
-        let command = unsafe { Box::from_raw(wparam.0 as *mut Command) };
+        let command = unsafe { Command::from_wparam(wparam) };
         // tracing::debug!("{command:?}");
-        match *command {
+        match command {
           Command::Exit => (),
           Command::Destroy => (),
+          Command::Wake => (),
           Command::Redraw => unsafe {
             RedrawWindow(hwnd, None, None, Gdi::RDW_INTERNALPAINT);
           },
-          Command::SetVisibility(visibility) => unsafe {
-            ShowWindow(hwnd, match visibility {
-              Visibility::Hidden => WindowsAndMessaging::SW_HIDE,
-              Visibility::Shown => WindowsAndMessaging::SW_SHOW,
+          Command::SetVisibility(visibility) => {
+            let (focusable, auto_dismiss) = {
+              let data = self.data.lock().unwrap();
+              (data.style.focusable, data.style.popup && data.auto_dismiss)
+            };
+            // released before hiding, so losing it isn't taken for a dismissal
+            if auto_dismiss && visibility == Visibility::Hidden {
+              self.set_dismiss_capture(hwnd, false);
+            }
+            unsafe {
+              ShowWindow(hwnd, match visibility {
+                Visibility::Hidden => WindowsAndMessaging::SW_HIDE,
+                Visibility::Shown if focusable => WindowsAndMessaging::SW_SHOW,
+                Visibility::Shown => WindowsAndMessaging::SW_SHOWNOACTIVATE,
+              })
+            };
+            if auto_dismiss && visibility == Visibility::Shown {
+              self.set_dismiss_capture(hwnd, true);
+            }
+          }
+          Command::SetMinimized(minimized) => unsafe {
+            ShowWindow(hwnd, match minimized {
+              true => WindowsAndMessaging::SW_MINIMIZE,
+              false => WindowsAndMessaging::SW_RESTORE,
+            });
+          },
+          Command::SetMaximized(maximized) => unsafe {
+            ShowWindow(hwnd, match maximized {
+              true => WindowsAndMessaging::SW_MAXIMIZE,
+              false => WindowsAndMessaging::SW_RESTORE,
             });
           },
+          Command::Focus => unsafe {
+            if IsIconic(hwnd).as_bool() {
+              ShowWindow(hwnd, WindowsAndMessaging::SW_RESTORE);
+            }
+            SetForegroundWindow(hwnd);
+            let _ = SetFocus(hwnd);
+          },
+          Command::Flash(mode) => self.flash(hwnd, mode),
+          Command::SetFocusable(_) => self.update_ex_style(hwnd),
+          Command::SetResizable(_) => self.update_style(hwnd),
+          Command::SetTransparencyColorKey(color_key) => {
+            // the layered style has to be in place before its attributes are set
+            self.update_ex_style(hwnd);
+            if let Some(color) = color_key {
+              let color_ref =
+                COLORREF(color.r as u32 | (color.g as u32) << 8 | (color.b as u32) << 16);
+              if let Err(e) = unsafe {
+                SetLayeredWindowAttributes(
+                  hwnd,
+                  color_ref,
+                  0,
+                  WindowsAndMessaging::LWA_COLORKEY,
+                )
+              } {
+                tracing::error!("{e}");
+              }
+            }
+          }
+          Command::SetEnabledButtons(buttons) => {
+            let style = self.data.lock().unwrap().style.clone();
+            unsafe {
+              SetWindowLongW(
+                hwnd,
+                WindowsAndMessaging::GWL_STYLE,
+                get_window_style(&style).0 as i32,
+              );
+              if let Err(e) = SetWindowPos(
+                hwnd,
+                None,
+                0,
+                0,
+                0,
+                0,
+                WindowsAndMessaging::SWP_NOZORDER
+                  | WindowsAndMessaging::SWP_NOMOVE
+                  | WindowsAndMessaging::SWP_NOSIZE
+                  | WindowsAndMessaging::SWP_NOACTIVATE
+                  | WindowsAndMessaging::SWP_FRAMECHANGED,
+              ) {
+                tracing::error!("{e}");
+              }
+            }
+            set_close_button_enabled(hwnd, buttons.close);
+          }
           Command::SetDecorations(decorations) => {
             let style = self.data.lock().unwrap().style.clone();
             match decorations {
@@ -285,7 +951,7 @@ impl Internal {
                 };
               }
             }
-            unsafe {
+            if let Err(e) = unsafe {
               SetWindowPos(
                 hwnd,
                 None,
@@ -299,15 +965,18 @@ impl Internal {
                   | WindowsAndMessaging::SWP_NOACTIVATE
                   | WindowsAndMessaging::SWP_FRAMECHANGED,
               )
-              .expect("Failed to set window size");
+            } {
+              tracing::error!("{e}");
+            }
+          }
+          Command::SetWindowText(text) => {
+            if let Err(e) = unsafe { SetWindowTextW(hwnd, &text) } {
+              tracing::error!("{e}");
             }
           }
-          Command::SetWindowText(text) => unsafe {
-            SetWindowTextW(hwnd, &text).unwrap();
-          },
           Command::SetSize(size) => {
             let physical_size = size.as_physical(self.data.lock().unwrap().scale_factor);
-            unsafe {
+            if let Err(e) = unsafe {
               SetWindowPos(
                 hwnd,
                 None,
@@ -320,14 +989,21 @@ impl Internal {
                   | WindowsAndMessaging::SWP_NOREPOSITION
                   | WindowsAndMessaging::SWP_NOACTIVATE,
               )
-              .expect("Failed to set window size");
+            } {
+              tracing::error!("{e}");
             }
             unsafe { InvalidateRgn(hwnd, None, false) };
           }
+          Command::SetNormalRect(rect) => {
+            set_normal_rect(hwnd, rect);
+            let mut data = self.data.lock().unwrap();
+            data.last_windowed_position = rect.position.into();
+            data.last_windowed_size = rect.size.into();
+          }
           Command::SetPosition(position) => {
             let physical_position =
               position.as_physical(self.data.lock().unwrap().scale_factor);
-            unsafe {
+            if let Err(e) = unsafe {
               SetWindowPos(
                 hwnd,
                 None,
@@ -340,11 +1016,15 @@ impl Internal {
                   | WindowsAndMessaging::SWP_NOREPOSITION
                   | WindowsAndMessaging::SWP_NOACTIVATE,
               )
-              .expect("Failed to set window position");
+            } {
+              tracing::error!("{e}");
             }
             unsafe { InvalidateRgn(hwnd, None, false) };
           }
-          Command::SetFullscreen(fullscreen) => {
+          Command::SetFullscreen {
+            fullscreen,
+            apply_cursor,
+          } => {
             // update style
             let style = self.data.lock().unwrap().style.clone();
             unsafe {
@@ -361,9 +1041,15 @@ impl Internal {
                 get_window_ex_style(&style).0 as i32,
               )
             };
+            // the display mode changes first, so the window covers the monitor
+            // at its new resolution
+            self.leave_exclusive_mode();
+            if let Some(Fullscreen::Exclusive(mode)) = fullscreen {
+              self.enter_exclusive_mode(hwnd, mode);
+            }
             // update size
             match fullscreen {
-              Some(Fullscreen::Borderless) => {
+              Some(Fullscreen::Borderless | Fullscreen::Exclusive(_)) => {
                 let monitor =
                   unsafe { MonitorFromWindow(hwnd, Gdi::MONITOR_DEFAULTTONEAREST) };
                 let mut info = MONITORINFO {
@@ -371,20 +1057,14 @@ impl Internal {
                   ..Default::default()
                 };
                 if unsafe { GetMonitorInfoW(monitor, &mut info) }.as_bool() {
-                  unsafe {
-                    SetWindowPos(
-                      hwnd,
-                      None,
-                      info.rcMonitor.left,
-                      info.rcMonitor.top,
-                      info.rcMonitor.right - info.rcMonitor.left,
-                      info.rcMonitor.bottom - info.rcMonitor.top,
-                      WindowsAndMessaging::SWP_ASYNCWINDOWPOS
-                        | WindowsAndMessaging::SWP_NOZORDER
-                        | WindowsAndMessaging::SWP_FRAMECHANGED,
-                    )
-                    .expect("Failed to set window to fullscreen");
-                  }
+                  debug_assert!(!is_flag_set(
+                    unsafe { GetWindowLongW(hwnd, WindowsAndMessaging::GWL_STYLE) }
+                      as u32,
+                    (WindowsAndMessaging::WS_CAPTION
+                      | WindowsAndMessaging::WS_THICKFRAME)
+                      .0
+                  ));
+                  set_borderless_fullscreen_pos(hwnd, info.rcMonitor);
                   unsafe { InvalidateRgn(hwnd, None, false) };
                 }
               }
@@ -402,7 +1082,7 @@ impl Internal {
                   .unwrap()
                   .last_windowed_position
                   .as_physical(scale_factor);
-                unsafe {
+                if let Err(e) = unsafe {
                   SetWindowPos(
                     hwnd,
                     None,
@@ -410,35 +1090,88 @@ impl Internal {
                     position.y,
                     size.width as i32,
                     size.height as i32,
-                    WindowsAndMessaging::SWP_ASYNCWINDOWPOS
-                      | WindowsAndMessaging::SWP_NOZORDER
+                    WindowsAndMessaging::SWP_NOZORDER
                       | WindowsAndMessaging::SWP_FRAMECHANGED,
                   )
-                  .expect("Failed to set window to windowed");
-                };
+                } {
+                  tracing::error!("{e}");
+                }
                 unsafe { InvalidateRgn(hwnd, None, false) };
               }
             }
+
+            if apply_cursor {
+              let cursor = self.data.lock().unwrap().cursor.clone();
+              set_cursor_mode(hwnd, cursor.mode);
+              set_cursor_visibility(cursor.visibility);
+            }
           }
           Command::SetCursorIcon(icon) => {
             self.data.lock().unwrap().cursor.selected_icon = icon;
-            let cursor_icon = to_windows_cursor(icon);
-            let hcursor =
-              unsafe { LoadCursorW(HINSTANCE::default(), cursor_icon) }.unwrap();
-            unsafe { SetCursor(hcursor) };
+            set_os_cursor_icon(icon);
           }
-          Command::SetCursorMode(mode) => {
-            match mode {
-              CursorMode::Normal => {
-                set_cursor_clip(None);
-              }
-              CursorMode::Confined => {
-                let mut client_rect = RECT::default();
-                unsafe { GetClientRect(hwnd, &mut client_rect) }.unwrap();
-
-                set_cursor_clip(Some(&client_rect));
+          Command::SetCursorMode(mode) => set_cursor_mode(hwnd, mode),
+          Command::SetDwmMargins(margins) => {
+            let margins = margins.or_else(|| self.shadow_margins());
+            extend_frame_into_client_area(hwnd, margins.unwrap_or_default());
+          }
+          Command::SetWindowRegion(region) => {
+            let scale_factor = self.data.lock().unwrap().scale_factor;
+            set_window_region(hwnd, region.as_ref(), scale_factor);
+          }
+          Command::SetWindowIcon(icon) => {
+            let hicon = icon.and_then(|icon| match icon.create() {
+              Ok(hicon) => Some(hicon),
+              Err(error) => {
+                tracing::error!("{error}");
+                None
               }
-            };
+            });
+            // a null icon falls back to the class icon, which is the default
+            let lparam = LPARAM(hicon.map_or(0, |hicon| hicon.0));
+            for size in [
+              WindowsAndMessaging::ICON_SMALL,
+              WindowsAndMessaging::ICON_BIG,
+            ] {
+              unsafe {
+                SendMessageW(
+                  hwnd,
+                  WindowsAndMessaging::WM_SETICON,
+                  WPARAM(size as usize),
+                  lparam,
+                )
+              };
+            }
+            let previous =
+              std::mem::replace(&mut self.data.lock().unwrap().window_hicon, hicon);
+            if let Some(previous) = previous {
+              let _ = unsafe { DestroyIcon(previous) };
+            }
+          }
+          Command::SetAppUserModelId(id) => {
+            if let Err(error) = set_window_app_user_model_id(hwnd, id.as_deref()) {
+              tracing::error!("{error}");
+            }
+          }
+          Command::SetZOrderAfter(insert_after) => {
+            // the other window may have been destroyed since this was posted
+            if !unsafe { IsWindow(insert_after) }.as_bool() {
+              tracing::warn!("z-order target was destroyed before it could be applied");
+            } else if let Err(error) = unsafe {
+              SetWindowPos(
+                hwnd,
+                insert_after,
+                0,
+                0,
+                0,
+                0,
+                WindowsAndMessaging::SWP_NOMOVE
+                  | WindowsAndMessaging::SWP_NOSIZE
+                  | WindowsAndMessaging::SWP_NOACTIVATE,
+              )
+            } {
+              tracing::error!("{error}");
+            }
           }
           Command::SetCursorVisibility(visibility) => match visibility {
             Visibility::Shown => {
@@ -450,34 +1183,66 @@ impl Internal {
           },
         }
 
-        LRESULT(0)
+        Response::Handled(LRESULT(0))
       }
       WindowsAndMessaging::WM_SETCURSOR => {
-        let in_client_area =
-          lo_word(lparam.0 as u32) as u32 == WindowsAndMessaging::HTCLIENT;
-
-        if in_client_area {
+        // the low word holds the result of the last WM_NCHITTEST, which may be
+        // a resize code from the custom resize border over the client area
+        let hit_test = lo_word(lparam.0 as u32) as u32;
+        let in_client_area = hit_test == WindowsAndMessaging::HTCLIENT;
+
+        if let Some(icon) = hit_test_cursor_icon(hit_test) {
+          set_os_cursor_icon(icon);
+          Response::Handled(LRESULT(0))
+        } else if in_client_area {
           let icon = self.data.lock().unwrap().cursor.selected_icon;
-          let cursor_icon = to_windows_cursor(icon);
-          let hcursor =
-            unsafe { LoadCursorW(HINSTANCE::default(), cursor_icon) }.unwrap();
-          unsafe { SetCursor(hcursor) };
-          LRESULT(0)
+          set_os_cursor_icon(icon);
+          Response::Handled(LRESULT(0))
         } else {
-          unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+          Response::Default
         }
       }
       // WindowsAndMessaging::WM_SIZING | WindowsAndMessaging::WM_MOVING => {
       //   // ignore certain messages
       //   return unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) };
       // }
+      WindowsAndMessaging::WM_DWMCOMPOSITIONCHANGED => {
+        // the frame extension is lost whenever composition is toggled
+        let margins = self.data.lock().unwrap().dwm_margins;
+        if let Some(margins) = margins.or_else(|| self.shadow_margins()) {
+          extend_frame_into_client_area(hwnd, margins);
+        }
+        Response::Default
+      }
+      WindowsAndMessaging::WM_NCCALCSIZE if wparam.0 != 0 => {
+        let covers_frame = {
+          let style = &self.data.lock().unwrap().style;
+          style.borderless_shadow && !style.popup && style.fullscreen.is_none()
+        };
+        if covers_frame {
+          let params = unsafe { &mut *(lparam.0 as *mut NCCALCSIZE_PARAMS) };
+          // maximized windows hang over the edges of the monitor by the frame
+          if unsafe { IsZoomed(hwnd) }.as_bool() {
+            let (x, y) = sizing_frame(hwnd_dpi(hwnd));
+            let client = &mut params.rgrc[0];
+            client.left += x;
+            client.right -= x;
+            client.top += y;
+            client.bottom -= y;
+          }
+          // the client area is the whole window
+          Response::Handled(LRESULT(0))
+        } else {
+          Response::Default
+        }
+      }
       WindowsAndMessaging::WM_CLOSE => {
         messages.push(Message::CloseRequested);
-        LRESULT(0)
+        Response::Handled(LRESULT(0))
       }
       WindowsAndMessaging::WM_PAINT => {
         messages.push(Message::Paint);
-        unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        Response::Default
       }
       WindowsAndMessaging::WM_SIZE => {
         self.data.lock().unwrap().style.minimized =
@@ -489,16 +1254,106 @@ impl Internal {
         let height = hi_word(lparam.0 as u32) as u32;
 
         messages.push(Message::Resized(PhysicalSize::new(width, height)));
-        unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        Response::Default
       }
       WindowsAndMessaging::WM_MOVE => {
-        let x = lo_word(lparam.0 as u32) as i32;
-        let y = hi_word(lparam.0 as u32) as i32;
+        // negative on monitors left of or above the primary monitor
+        let x = signed_lo_word(lparam.0 as i32) as i32;
+        let y = signed_hi_word(lparam.0 as i32) as i32;
 
         messages.push(Message::Moved(PhysicalPosition::new(x, y)));
-        unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        Response::Default
+      }
+      WindowsAndMessaging::WM_NCHITTEST => {
+        let result = unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) };
+        let border = {
+          let data = self.data.lock().unwrap();
+          let style = &data.style;
+          data
+            .resize_border
+            .filter(|_| {
+              style.resizeable && style.fullscreen.is_none() && !style.maximized
+            })
+            .map(|border| (border * data.scale_factor).round() as i32)
+        };
+        let overridable = [
+          WindowsAndMessaging::HTCLIENT,
+          WindowsAndMessaging::HTCAPTION,
+        ]
+        .map(|code| code as isize)
+        .contains(&result.0);
+        Response::Handled(match border {
+          Some(border) if overridable => {
+            let mut window_rect = RECT::default();
+            let _ = unsafe { GetWindowRect(hwnd, &mut window_rect) };
+            let point = PhysicalPosition::new(
+              signed_lo_word(lparam.0 as i32) as i32,
+              signed_hi_word(lparam.0 as i32) as i32,
+            );
+            match resize_border_hit_test(window_rect.into(), point, border) {
+              WindowsAndMessaging::HTCLIENT => result,
+              code => LRESULT(code as isize),
+            }
+          }
+          _ => result,
+        })
+      }
+      WindowsAndMessaging::WM_SETTINGCHANGE => {
+        let follows_system = self.data.lock().unwrap().theme == Theme::Auto;
+        if follows_system && self.update_effective_theme() {
+          let effective_theme = self.data.lock().unwrap().effective_theme;
+          messages.push(Message::ThemeChanged(effective_theme));
+        }
+        if let Some(accent_colors) = self.update_accent_colors() {
+          messages.push(Message::AccentColorChanged(accent_colors));
+        }
+        Response::Default
+      }
+      WindowsAndMessaging::WM_DROPFILES => {
+        messages
+          .push(Message::FilesDropped(read_dropped_files(HDROP(wparam.0 as isize))));
+        Response::Handled(LRESULT(0))
+      }
+      WindowsAndMessaging::WM_COPYDATA => {
+        let message_id = unsafe { GetPropW(hwnd, INSTANCE_PROPERTY) }.0 as u32;
+        let data = unsafe { &*(lparam.0 as *const COPYDATASTRUCT) };
+        match read_instance_args(data, message_id) {
+          Some(args) => {
+            messages.push(Message::SecondInstanceLaunched(args));
+            Response::Handled(LRESULT(1))
+          }
+          None => Response::Default,
+        }
+      }
+      WindowsAndMessaging::WM_DISPLAYCHANGE => {
+        self.data.lock().unwrap().monitor = None;
+        messages
+          .push(Message::SurfaceInvalidated(SurfaceInvalidationReason::DisplayChanged));
+        Response::Default
+      }
+      WindowsAndMessaging::WM_ENDSESSION => {
+        if wparam.0 != 0 {
+          let mut data = self.data.lock().unwrap();
+          if !matches!(data.stage, Stage::Closing | Stage::ExitLoop | Stage::Destroyed) {
+            data.exit_reason = Some(ExitReason::SessionEnd);
+            data.stage = Stage::Closing;
+          }
+        }
+        Response::Handled(LRESULT(0))
+      }
+      WindowsAndMessaging::WM_WTSSESSION_CHANGE => {
+        match wparam.0 as u32 {
+          WindowsAndMessaging::WTS_SESSION_LOCK => messages
+            .push(Message::SurfaceInvalidated(SurfaceInvalidationReason::SessionLocked)),
+          WindowsAndMessaging::WTS_SESSION_UNLOCK => messages.push(
+            Message::SurfaceInvalidated(SurfaceInvalidationReason::SessionUnlocked),
+          ),
+          _ => (),
+        }
+        Response::Default
       }
       WindowsAndMessaging::WM_WINDOWPOSCHANGED => {
+        self.data.lock().unwrap().monitor = None;
         let window_pos = unsafe { &*(lparam.0 as *const WINDOWPOS) };
         // if (window_pos.flags & WindowsAndMessaging::SWP_NOMOVE) !=
         // WindowsAndMessaging::SWP_NOMOVE {
@@ -509,119 +1364,215 @@ impl Internal {
           outer_position: PhysicalPosition::new(window_pos.x, window_pos.y),
           outer_size: PhysicalSize::new(window_pos.cx as u32, window_pos.cy as u32),
         });
-        unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        Response::Default
+      }
+      WindowsAndMessaging::WM_CAPTURECHANGED => {
+        if std::mem::take(&mut self.data.lock().unwrap().dismiss_capture) {
+          self.detach_dismiss_input();
+          messages.push(Message::DismissRequested);
+        }
+        Response::Default
+      }
+      WindowsAndMessaging::WM_ACTIVATEAPP => {
+        let deactivated = wparam.0 == 0;
+        if deactivated && self.data.lock().unwrap().dismiss_capture {
+          messages.push(Message::DismissRequested);
+        }
+        Response::Default
       }
       WindowsAndMessaging::WM_NCACTIVATE => {
         let is_active = wparam.0 == true.into();
-        self.data.lock().unwrap().style.active = is_active;
+        let was_flashing = {
+          let mut data = self.data.lock().unwrap();
+          data.style.active = is_active;
+          is_active && std::mem::take(&mut data.flashing)
+        };
+        // the taskbar button stays highlighted after a counted flash, even once
+        // the window is active
+        if was_flashing {
+          flash_window(hwnd, WindowsAndMessaging::FLASHW_STOP, 0);
+        }
 
-        unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        Response::Default
+      }
+      WindowsAndMessaging::WM_GETMINMAXINFO => {
+        let (style, min_inner_size, max_inner_size) = {
+          let data = self.data.lock().unwrap();
+          (data.style.clone(), data.min_inner_size, data.max_inner_size)
+        };
+        // the default maximized bounds are based on the primary monitor, which
+        // is wrong for other monitors with a different size, DPI, or taskbar
+        if style.fullscreen.is_none() {
+          let info = unsafe { &mut *(lparam.0 as *mut MINMAXINFO) };
+          let monitor = Monitor::cached(unsafe {
+            MonitorFromWindow(hwnd, Gdi::MONITOR_DEFAULTTONEAREST)
+          });
+          // the default position is offset by the frame, if the window has one
+          let frame = PhysicalSize::new(
+            (-info.ptMaxPosition.x).max(0) as u32,
+            (-info.ptMaxPosition.y).max(0) as u32,
+          );
+          let bounds = maximized_bounds(monitor.rect(), monitor.work_area(), frame);
+          info.ptMaxPosition = POINT {
+            x: bounds.left(),
+            y: bounds.top(),
+          };
+          info.ptMaxSize = POINT {
+            x: bounds.size.width as i32,
+            y: bounds.size.height as i32,
+          };
+
+          // the window may already be on a monitor with a new DPI, before
+          // WM_DPICHANGED updates the scale factor
+          let dpi = hwnd_dpi(hwnd);
+          let track_size = |size: Size| {
+            let outer_size = outer_size_for_inner(
+              size.as_physical(dpi_to_scale_factor(dpi)),
+              &style,
+              dpi,
+            );
+            POINT {
+              x: outer_size.width as i32,
+              y: outer_size.height as i32,
+            }
+          };
+          if let Some(size) = min_inner_size {
+            info.ptMinTrackSize = track_size(size);
+          }
+          if let Some(size) = max_inner_size {
+            info.ptMaxTrackSize = track_size(size);
+          }
+        }
+        Response::Handled(LRESULT(0))
+      }
+      WindowsAndMessaging::WM_MOUSEACTIVATE => {
+        let mut data = self.data.lock().unwrap();
+        data.eaten_click = None;
+        let in_client_area =
+          lo_word(lparam.0 as u32) as u32 == WindowsAndMessaging::HTCLIENT;
+        // only eat clicks in the client area, so the frame can still be
+        // dragged with the activating click. The matching button up is
+        // swallowed as well, so the app never sees half a click.
+        match (data.click_activation, data.style.focusable) {
+          (ClickActivation::NoActivateAndEat, _) if in_client_area => {
+            data.eaten_click = Some(hi_word(lparam.0 as u32) as u32);
+            Response::Handled(LRESULT(WindowsAndMessaging::MA_NOACTIVATEANDEAT as isize))
+          }
+          (ClickActivation::ActivateAndEat, true) if in_client_area => {
+            data.eaten_click = Some(hi_word(lparam.0 as u32) as u32);
+            Response::Handled(LRESULT(WindowsAndMessaging::MA_ACTIVATEANDEAT as isize))
+          }
+          (ClickActivation::NoActivate | ClickActivation::NoActivateAndEat, _)
+          | (_, false) => {
+            Response::Handled(LRESULT(WindowsAndMessaging::MA_NOACTIVATE as isize))
+          }
+          _ => {
+            drop(data);
+            Response::Default
+          }
+        }
       }
       WindowsAndMessaging::WM_SETFOCUS => {
         messages.push(Message::Focus(Focus::Gained));
         self.data.lock().unwrap().style.focused = true;
-        unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        self.sync.focus_gate.set_focused(true);
+        Response::Default
       }
       WindowsAndMessaging::WM_KILLFOCUS => {
         messages.push(Message::Focus(Focus::Lost));
         self.data.lock().unwrap().style.focused = false;
-        unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        self.sync.focus_gate.set_focused(false);
+        Response::Default
       }
       WindowsAndMessaging::WM_COMMAND => {
         messages.push(Message::Command);
-        unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        Response::Default
       }
       WindowsAndMessaging::WM_SYSCOMMAND => {
         messages.push(Message::SystemCommand);
-        unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        Response::Default
       }
       WindowsAndMessaging::WM_DPICHANGED => {
-        let dpi = lo_word(wparam.0 as u32) as u32;
-        let suggested_rect = unsafe { *(lparam.0 as *const RECT) };
-        unsafe {
-          SetWindowPos(
-            hwnd,
-            None,
-            suggested_rect.left,
-            suggested_rect.top,
-            suggested_rect.right - suggested_rect.left,
-            suggested_rect.bottom - suggested_rect.top,
-            WindowsAndMessaging::SWP_NOZORDER | WindowsAndMessaging::SWP_NOACTIVATE,
-          )
+        if self.data.lock().unwrap().dpi_scaling {
+          self.on_dpi_changed(hwnd, wparam, lparam, &mut messages);
+          Response::Default
+        } else {
+          Response::Handled(LRESULT(0))
         }
-        .unwrap();
-        let scale_factor = dpi_to_scale_factor(dpi);
-        self.data.lock().unwrap().scale_factor = scale_factor;
-        messages.push(Message::ScaleFactorChanged(scale_factor));
-        unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
       }
       WindowsAndMessaging::WM_INPUT => {
-        let Some(data) = read_raw_input(HRAWINPUT(lparam.0)) else {
-          return unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) };
-        };
-
-        match RID_DEVICE_INFO_TYPE(data.header.dwType) {
-          UI::Input::RIM_TYPEMOUSE => {
-            let mouse_data = unsafe { data.data.mouse };
-            let button_flags = unsafe { mouse_data.Anonymous.Anonymous.usButtonFlags };
-
-            if mouse_data.usFlags == UI::Input::MOUSE_MOVE_RELATIVE {
-              let x = mouse_data.lLastX as f32;
-              let y = mouse_data.lLastY as f32;
-
-              if x != 0.0 || y != 0.0 {
-                messages.push(Message::RawInput(RawInputMessage::MouseMove {
-                  delta_x: x,
-                  delta_y: y,
-                }));
-              }
-            }
-
-            for (id, state) in mouse_button_states(button_flags).iter().enumerate() {
-              if let Some(state) = *state {
-                let button = MouseButton::from_state(id);
-                messages
-                  .push(Message::RawInput(RawInputMessage::MouseButton { button, state }))
-              }
-            }
+        self.on_raw_input(lparam, &mut messages);
+        Response::Default
+      }
+      WindowsAndMessaging::WM_TOUCH => {
+        let count = lo_word(wparam.0 as u32) as usize;
+        let points = read_touch_input(hwnd, HTOUCHINPUT(lparam.0), count);
+        if !points.is_empty() {
+          messages.push(Message::Touch(points));
+        }
+        Response::Handled(LRESULT(0))
+      }
+      WindowsAndMessaging::WM_POINTERDOWN
+      | WindowsAndMessaging::WM_POINTERUPDATE
+      | WindowsAndMessaging::WM_POINTERUP => {
+        let pointer_id = lo_word(wparam.0 as u32) as u32;
+        if let Some(point) = read_pen_pointer(hwnd, msg, pointer_id) {
+          messages.push(Message::Touch(vec![point]));
+        }
+        // left to the default procedure, which turns them into mouse messages,
+        // or into WM_TOUCH for fingers
+        Response::Default
+      }
+      WindowsAndMessaging::WM_IME_STARTCOMPOSITION => {
+        messages.push(Message::ImeStarted);
+        Response::Default
+      }
+      WindowsAndMessaging::WM_IME_COMPOSITION => {
+        let flags = lparam.0 as u32;
+        if is_flag_set(flags, Ime::GCS_RESULTSTR.0) {
+          if let Some(text) = ime_result_text(hwnd) {
+            messages.push(Message::ImeCommit(text));
           }
-          UI::Input::RIM_TYPEKEYBOARD => {
-            let keyboard_data = unsafe { data.data.keyboard };
-
-            let Some(key) = Key::from_raw(keyboard_data) else {
-              return unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) };
-            };
-
-            let pressed = matches!(
-              keyboard_data.Message,
-              WindowsAndMessaging::WM_KEYDOWN | WindowsAndMessaging::WM_SYSKEYDOWN
-            );
-            let released = matches!(
-              keyboard_data.Message,
-              WindowsAndMessaging::WM_KEYUP | WindowsAndMessaging::WM_SYSKEYUP
-            );
-
-            if let Some(state) = RawKeyState::from_bools(pressed, released) {
-              messages.push(Message::RawInput(RawInputMessage::Keyboard { key, state }));
-            }
+        }
+        if is_flag_set(flags, Ime::GCS_COMPSTR.0) {
+          if let Some((text, cursor_range)) = ime_preedit(hwnd) {
+            messages.push(Message::ImePreedit { text, cursor_range });
           }
-          _ => (),
-        };
-        unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        }
+        // the default IME window still draws the composition, but without the
+        // result, which it would otherwise send again as WM_IME_CHAR
+        let result_flags = Ime::GCS_RESULTSTR.0
+          | Ime::GCS_RESULTCLAUSE.0
+          | Ime::GCS_RESULTREADSTR.0
+          | Ime::GCS_RESULTREADCLAUSE.0;
+        let lparam = LPARAM((flags & !result_flags) as isize);
+        Response::Handled(unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) })
+      }
+      WindowsAndMessaging::WM_IME_ENDCOMPOSITION => {
+        messages.push(Message::ImePreedit {
+          text: String::new(),
+          cursor_range: None,
+        });
+        Response::Default
       }
       WindowsAndMessaging::WM_CHAR => {
-        let text = char::from_u32(wparam.0 as u32)
-          .unwrap_or_default()
-          .to_string();
-        messages.push(Message::Text(text));
-        unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        let chr = char::from_u32(wparam.0 as u32).unwrap_or_default();
+        if self.data.lock().unwrap().raw_text || is_printable_char(chr) {
+          messages.push(Message::Text(chr.to_string()));
+        }
+        Response::Default
       }
       WindowsAndMessaging::WM_KEYDOWN
       | WindowsAndMessaging::WM_SYSKEYDOWN
       | WindowsAndMessaging::WM_KEYUP
       | WindowsAndMessaging::WM_SYSKEYUP => {
-        let (changed, shift, ctrl, alt, win) =
-          self.data.lock().unwrap().input.update_modifiers_state();
+        let (changed, shift, ctrl, alt, win) = self
+          .data
+          .lock()
+          .unwrap()
+          .input
+          .realtime
+          .update_modifiers_state();
         if changed {
           messages.push(Message::ModifiersChanged {
             shift,
@@ -630,8 +1581,42 @@ impl Internal {
             win,
           });
         }
-        messages.push(Message::new_keyboard_message(lparam));
-        unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        let message = Message::new_keyboard_message(wparam, lparam);
+        if let Message::Key {
+          key: Key::PrintScreen,
+          state: KeyState::Released,
+          scan_code,
+          is_extended_key,
+        } = message
+        {
+          // PrintScreen only ever sends WM_KEYUP, so synthesize the press
+          if !self
+            .data
+            .lock()
+            .unwrap()
+            .input
+            .realtime
+            .key(Key::PrintScreen)
+            .is_pressed()
+          {
+            messages.push(Message::Key {
+              key: Key::PrintScreen,
+              state: KeyState::Pressed,
+              scan_code,
+              is_extended_key,
+            });
+          }
+        }
+        messages.push(message);
+
+        // the default handling of alt+f4 is what turns it into WM_CLOSE
+        let is_alt_f4 = msg == WindowsAndMessaging::WM_SYSKEYDOWN
+          && wparam.0 == KeyboardAndMouse::VK_F4.0 as usize;
+        if is_alt_f4 && !self.data.lock().unwrap().alt_f4 {
+          Response::Handled(LRESULT(0))
+        } else {
+          Response::Default
+        }
       }
       WindowsAndMessaging::WM_MOUSEMOVE => {
         let x = signed_lo_word(lparam.0 as i32) as i32;
@@ -649,16 +1634,7 @@ impl Internal {
           match kind {
             CursorMoveKind::Entered => {
               self.data.lock().unwrap().cursor.inside_window = true;
-
-              unsafe {
-                TrackMouseEvent(&mut TRACKMOUSEEVENT {
-                  cbSize: std::mem::size_of::<TRACKMOUSEEVENT>() as u32,
-                  dwFlags: KeyboardAndMouse::TME_LEAVE,
-                  hwndTrack: hwnd,
-                  dwHoverTime: Controls::HOVER_DEFAULT,
-                })
-              }
-              .unwrap();
+              track_mouse_leave(hwnd);
 
               true
             }
@@ -677,7 +1653,7 @@ impl Internal {
           messages.push(Message::CursorMove { position, kind });
           self.data.lock().unwrap().cursor.last_position = position;
         }
-        unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        Response::Default
       }
       Controls::WM_MOUSELEAVE => {
         self.data.lock().unwrap().cursor.inside_window = false;
@@ -685,7 +1661,7 @@ impl Internal {
           position: self.data.lock().unwrap().cursor.last_position,
           kind: CursorMoveKind::Left,
         });
-        unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        Response::Default
       }
       WindowsAndMessaging::WM_MOUSEWHEEL => {
         let delta = signed_hi_word(wparam.0 as i32) as f32
@@ -694,7 +1670,16 @@ impl Internal {
           delta_x: 0.0,
           delta_y: delta,
         });
-        unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        Response::Default
+      }
+      WindowsAndMessaging::WM_APPCOMMAND => {
+        let command = AppCommand::from_lparam(lparam.0);
+        messages.push(Message::AppCommand(command));
+        if command.is_handled_by_system() {
+          Response::Default
+        } else {
+          Response::Handled(LRESULT(1))
+        }
       }
       WindowsAndMessaging::WM_MOUSEHWHEEL => {
         let delta = signed_hi_word(wparam.0 as i32) as f32
@@ -703,17 +1688,42 @@ impl Internal {
           delta_x: delta,
           delta_y: 0.0,
         });
-        unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        Response::Default
       }
       msg
         if (WindowsAndMessaging::WM_MOUSEFIRST..=WindowsAndMessaging::WM_MOUSELAST)
           .contains(&msg) =>
       {
         // mouse move / wheels will match earlier
-        messages.push(Message::new_mouse_button_message(msg, wparam, lparam));
-        unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        if self.is_dismissing_click(hwnd, msg, lparam) {
+          messages.push(Message::DismissRequested);
+          Response::Handled(LRESULT(0))
+        } else {
+          let eaten = {
+            // button up messages directly follow their button down messages
+            let mut data = self.data.lock().unwrap();
+            let eaten = data.eaten_click == Some(msg - 1);
+            if eaten {
+              data.eaten_click = None;
+            }
+            eaten
+          };
+          if !eaten {
+            messages.push(Message::new_mouse_button_message(msg, wparam, lparam));
+          }
+          Response::Default
+        }
+      }
+      msg if (WindowsAndMessaging::WM_APP..=APP_MESSAGE_LAST).contains(&msg) => {
+        messages.push(Message::new_app_message(msg, wparam, lparam));
+        Response::Handled(LRESULT(0))
       }
-      _ => unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+      _ => Response::Default,
+    };
+
+    let result = match response {
+      Response::Default => unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+      Response::Handled(result) => result,
     };
 
     // pass message to main thread
@@ -726,10 +1736,7 @@ impl Internal {
             if focus == Focus::Gained {
               Command::SetCursorMode(cursor_mode).post(hwnd);
               Command::SetCursorVisibility(cursor_visibility).post(hwnd);
-              unsafe {
-                PostMessageW(hwnd, WindowsAndMessaging::WM_APP, WPARAM(0), LPARAM(0))
-              }
-              .unwrap();
+              Command::Wake.post(hwnd);
             }
           }
           &Message::Resized(_size) => {
@@ -761,6 +1768,7 @@ impl Internal {
               .lock()
               .unwrap()
               .input
+              .realtime
               .update_key_state(key, key_state);
           }
           &Message::MouseButton {
@@ -772,9 +1780,14 @@ impl Internal {
             .lock()
             .unwrap()
             .input
+            .realtime
             .update_mouse_button_state(button, button_state),
           Message::Paint => {
-            self.data.lock().unwrap().requested_redraw = false;
+            let mut data = self.data.lock().unwrap();
+            data.requested_redraw = data.continuous_redraw;
+            if data.continuous_redraw {
+              Command::Redraw.post(hwnd);
+            }
           }
           _ => (),
         }
@@ -954,6 +1967,16 @@ impl PhysicalPosition {
   pub fn is_zero(&self) -> bool {
     self.x == 0 && self.y == 0
   }
+
+  /// Component-wise minimum of both positions.
+  pub fn min(self, other: Self) -> Self {
+    Self::new(self.x.min(other.x), self.y.min(other.y))
+  }
+
+  /// Component-wise maximum of both positions.
+  pub fn max(self, other: Self) -> Self {
+    Self::new(self.x.max(other.x), self.y.max(other.y))
+  }
 }
 
 impl Div<f64> for PhysicalPosition {
@@ -1173,6 +2196,21 @@ impl PhysicalSize {
   pub fn is_all_zero(&self) -> bool {
     self.width == 0 && self.height == 0
   }
+
+  /// Component-wise minimum of both sizes.
+  pub fn min(self, other: Self) -> Self {
+    Self::new(self.width.min(other.width), self.height.min(other.height))
+  }
+
+  /// Component-wise maximum of both sizes.
+  pub fn max(self, other: Self) -> Self {
+    Self::new(self.width.max(other.width), self.height.max(other.height))
+  }
+
+  /// Component-wise clamp between `min` and `max`.
+  pub fn clamp(self, min: Self, max: Self) -> Self {
+    self.max(min).min(max)
+  }
 }
 
 impl Div<f64> for PhysicalSize {
@@ -1239,9 +2277,193 @@ impl From<[u32; 2]> for PhysicalSize {
   }
 }
 
+/// Axis-aligned rectangle in physical pixels. Like Win32's `RECT`, it is
+/// half-open: the left and top edges are inside, the right and bottom edges
+/// are not.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct PhysicalRect {
+  pub position: PhysicalPosition,
+  pub size: PhysicalSize,
+}
+
+impl PhysicalRect {
+  pub fn new(
+    position: impl Into<PhysicalPosition>,
+    size: impl Into<PhysicalSize>,
+  ) -> Self {
+    Self {
+      position: position.into(),
+      size: size.into(),
+    }
+  }
+
+  pub fn left(&self) -> i32 {
+    self.position.x
+  }
+
+  pub fn top(&self) -> i32 {
+    self.position.y
+  }
+
+  pub fn right(&self) -> i32 {
+    self.position.x + self.size.width as i32
+  }
+
+  pub fn bottom(&self) -> i32 {
+    self.position.y + self.size.height as i32
+  }
+
+  /// Returns `true` if the point lies within `[left, right)` and
+  /// `[top, bottom)`.
+  pub fn contains(&self, point: PhysicalPosition) -> bool {
+    (self.left()..self.right()).contains(&point.x)
+      && (self.top()..self.bottom()).contains(&point.y)
+  }
+
+  /// Moves the point to the nearest position inside the rectangle. Empty
+  /// rectangles clamp to their position.
+  pub fn clamp(&self, point: PhysicalPosition) -> PhysicalPosition {
+    PhysicalPosition::new(
+      point
+        .x
+        .clamp(self.left(), (self.right() - 1).max(self.left())),
+      point
+        .y
+        .clamp(self.top(), (self.bottom() - 1).max(self.top())),
+    )
+  }
+}
+
+impl From<RECT> for PhysicalRect {
+  fn from(rect: RECT) -> Self {
+    Self {
+      position: PhysicalPosition::new(rect.left, rect.top),
+      size: PhysicalSize::new(
+        (rect.right - rect.left).max(0) as u32,
+        (rect.bottom - rect.top).max(0) as u32,
+      ),
+    }
+  }
+}
+
+impl From<PhysicalRect> for RECT {
+  fn from(rect: PhysicalRect) -> Self {
+    Self {
+      left: rect.left(),
+      top: rect.top(),
+      right: rect.right(),
+      bottom: rect.bottom(),
+    }
+  }
+}
+
+/// Per-side extent of the frame extended into the client area by the desktop
+/// window manager, in physical pixels. Negative values on every side extend
+/// the frame over the whole window.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Margins {
+  pub left: i32,
+  pub right: i32,
+  pub top: i32,
+  pub bottom: i32,
+}
+
+impl Margins {
+  /// The smallest frame extension which still makes DWM draw the shadow.
+  pub(crate) const SHADOW: Self = Self::new(0, 0, 1, 0);
+  /// Margins which extend the frame over the whole window.
+  pub const SHEET_OF_GLASS: Self = Self::uniform(-1);
+
+  pub const fn new(left: i32, right: i32, top: i32, bottom: i32) -> Self {
+    Self {
+      left,
+      right,
+      top,
+      bottom,
+    }
+  }
+
+  pub const fn uniform(margin: i32) -> Self {
+    Self::new(margin, margin, margin, margin)
+  }
+}
+
+/// An opaque RGB color.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Color {
+  pub r: u8,
+  pub g: u8,
+  pub b: u8,
+}
+
+impl Color {
+  pub const fn new(r: u8, g: u8, b: u8) -> Self {
+    Self { r, g, b }
+  }
+}
+
+/// The system accent color and the shades Windows derives from it. See
+/// [`system_accent_colors`](crate::utilities::system_accent_colors).
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct AccentColors {
+  pub accent: Color,
+  pub light_1: Color,
+  pub light_2: Color,
+  pub light_3: Color,
+  pub dark_1: Color,
+  pub dark_2: Color,
+  pub dark_3: Color,
+}
+
+/// A display mode of a monitor. See
+/// [`Monitor::video_modes`](crate::Monitor::video_modes).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct VideoMode {
+  pub size: PhysicalSize,
+  /// Bits per pixel. `0` keeps the current depth when switching modes.
+  pub bit_depth: u16,
+  /// In hertz. `0` keeps the current refresh rate when switching modes.
+  pub refresh_rate: u32,
+}
+
+impl VideoMode {
+  pub fn new(size: impl Into<PhysicalSize>, bit_depth: u16, refresh_rate: u32) -> Self {
+    Self {
+      size: size.into(),
+      bit_depth,
+      refresh_rate,
+    }
+  }
+
+  pub fn size(&self) -> PhysicalSize {
+    self.size
+  }
+
+  pub fn bit_depth(&self) -> u16 {
+    self.bit_depth
+  }
+
+  /// The refresh rate in hertz. Display modes are only listed in whole hertz,
+  /// see [`Monitor::refresh_rate_millihertz`](crate::Monitor::refresh_rate_millihertz)
+  /// for the exact rate of the current mode.
+  pub fn refresh_rate(&self) -> u32 {
+    self.refresh_rate
+  }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Fullscreen {
-  // Exclusive, // todo
+  /// Switch the monitor the window is on to the given display mode and cover
+  /// it. Pick one of [`Monitor::video_modes`](crate::Monitor::video_modes) to
+  /// be sure it is supported. The previous mode is restored when leaving
+  /// fullscreen or closing the window. Falls back to covering the monitor as
+  /// is if the mode can't be set.
+  Exclusive(VideoMode),
+  /// Cover the monitor without changing its display mode. The window is then
+  /// as large as the monitor in physical pixels, so render at
+  /// [`Window::inner_size`](crate::Window::inner_size) and lay out UI at
+  /// [`Monitor::size_logical`](crate::Monitor::size_logical) to keep it the
+  /// same size it was in windowed mode.
   Borderless,
 }
 
@@ -1252,6 +2474,163 @@ pub enum CursorMode {
   Confined,
 }
 
+/// What [`Window::set_fullscreen_with`](crate::Window::set_fullscreen_with)
+/// does with the cursor.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CursorBehavior {
+  /// Leave the cursor mode and visibility alone.
+  #[default]
+  Keep,
+  /// Remember the cursor mode and visibility, then confine and hide the
+  /// cursor.
+  ConfineAndHide,
+  /// Bring back the cursor mode and visibility remembered by
+  /// [`CursorBehavior::ConfineAndHide`], if any.
+  RestorePrevious,
+}
+
+/// The DPI awareness a window is created with. Windows created with
+/// different awareness can live in the same process, so a plugin can have
+/// sharp, per-monitor aware windows even in a host that is only system aware.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DpiAwareness {
+  /// Follows the DPI of each monitor and scales the non-client area. Falls
+  /// back to [`DpiAwareness::PerMonitor`] before Windows 10 1703.
+  #[default]
+  PerMonitorV2,
+  /// Follows the DPI of each monitor, without scaling the non-client area.
+  PerMonitor,
+  /// Uses the DPI of the primary monitor, and is bitmap-stretched by the
+  /// system on monitors with a different DPI.
+  System,
+  /// Always at 96 DPI, bitmap-stretched by the system.
+  Unaware,
+  /// Whatever the creating thread already uses, usually the awareness of
+  /// the process. Never reported by
+  /// [`Window::dpi_awareness`](crate::Window::dpi_awareness).
+  Inherit,
+}
+
+/// What the window thread does with new messages once the main thread has
+/// stopped taking them for longer than the stall timeout. Either way, the
+/// window thread stops waiting on the main thread, so the window stays
+/// movable and closable, and returns to normal once the main thread resumes.
+#[derive(Default, Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum StallPolicy {
+  /// Drop frequent messages such as cursor moves and paints, and queue the
+  /// rest for when the main thread resumes.
+  #[default]
+  DropLowPriority,
+  /// Drop every message until the main thread resumes.
+  DropAll,
+}
+
+/// Which buttons of the title bar are enabled. All are enabled by default.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct WindowButtons {
+  pub close: bool,
+  pub minimize: bool,
+  pub maximize: bool,
+}
+
+impl WindowButtons {
+  pub const ALL: Self = Self {
+    close: true,
+    minimize: true,
+    maximize: true,
+  };
+  pub const NONE: Self = Self {
+    close: false,
+    minimize: false,
+    maximize: false,
+  };
+}
+
+impl Default for WindowButtons {
+  fn default() -> Self {
+    Self::ALL
+  }
+}
+
+/// What happens when the user clicks the window while it is inactive.
+#[derive(Default, Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ClickActivation {
+  /// Activate the window and deliver the click.
+  #[default]
+  Activate,
+  /// Activate the window but don't deliver the click when it lands in the
+  /// client area, so focusing the window can't trigger anything under the
+  /// cursor.
+  ActivateAndEat,
+  /// Deliver the click without activating the window.
+  NoActivate,
+  /// Neither activate the window nor deliver the click when it lands in the
+  /// client area, e.g. for utility panels that ignore stray clicks.
+  NoActivateAndEat,
+}
+
+/// How [`Window::flash`](crate::Window::flash) flashes the window.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum FlashMode {
+  /// Flash once, like [`Window::flash_once`](crate::Window::flash_once).
+  Once,
+  /// Flash the title bar and taskbar button until the window is activated,
+  /// like [`UserAttention::Critical`].
+  UntilFocused,
+  /// Flash only the taskbar button until the window is activated, like
+  /// [`UserAttention::Informational`].
+  TaskbarUntilFocused,
+  /// Stop flashing early.
+  Stop,
+}
+
+impl FlashMode {
+  /// The `dwFlags` and `uCount` of the `FLASHWINFO` for this mode.
+  fn flash_window_args(self) -> (FLASHWINFO_FLAGS, u32) {
+    match self {
+      FlashMode::Once => (WindowsAndMessaging::FLASHW_ALL, 1),
+      FlashMode::UntilFocused => (
+        WindowsAndMessaging::FLASHW_ALL | WindowsAndMessaging::FLASHW_TIMERNOFG,
+        u32::MAX,
+      ),
+      // counted rather than timed, WM_NCACTIVATE stops it on activation
+      FlashMode::TaskbarUntilFocused => (WindowsAndMessaging::FLASHW_TRAY, u32::MAX),
+      FlashMode::Stop => (WindowsAndMessaging::FLASHW_STOP, 0),
+    }
+  }
+}
+
+impl From<UserAttention> for FlashMode {
+  fn from(attention: UserAttention) -> Self {
+    match attention {
+      UserAttention::Critical => FlashMode::UntilFocused,
+      UserAttention::Informational => FlashMode::TaskbarUntilFocused,
+    }
+  }
+}
+
+/// How insistently [`Window::request_user_attention`](crate::Window::request_user_attention)
+/// asks for attention.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum UserAttention {
+  /// Flash the title bar and taskbar button until the window is activated.
+  Critical,
+  /// Flash only the taskbar button until the window is activated.
+  Informational,
+}
+
+/// Which corner of a popup is placed at the point given to
+/// [`Window::show_popup_at`](crate::Window::show_popup_at). The popup flips to
+/// the opposite corner when it wouldn't fit on the work area otherwise.
+#[derive(Default, Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum PopupAnchor {
+  #[default]
+  TopLeft,
+  TopRight,
+  BottomLeft,
+  BottomRight,
+}
+
 /// The wait behaviour of the window.
 #[derive(Default, Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum Flow {
@@ -1271,6 +2650,7 @@ pub enum Visibility {
   Hidden,
 }
 
+/// The theme requested for a window. [`Theme::Auto`] follows the system.
 #[derive(Default, Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum Theme {
   #[default]
@@ -1278,3 +2658,149 @@ pub enum Theme {
   Dark,
   Light,
 }
+
+/// The theme a window is actually shown in, after resolving [`Theme::Auto`]
+/// and falling back to light where dark mode isn't supported.
+#[derive(Default, Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum EffectiveTheme {
+  Dark,
+  #[default]
+  Light,
+}
+
+/// A present mode suggested by
+/// [`Window::recommended_present_mode`](crate::Window::recommended_present_mode).
+/// The variants match the present modes of wgpu and Vulkan, so mapping them
+/// is one line.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum PresentMode {
+  /// Wait for vertical blank. Supported everywhere.
+  Fifo,
+  /// Replace the queued frame with the newest one, without tearing.
+  Mailbox,
+  /// Present right away, which may tear.
+  Immediate,
+}
+
+/// Frame timing statistics reported by the desktop window manager.
+///
+/// These values come from the compositor, not the GPU present queue, so they
+/// describe what the compositor has shown rather than what has been submitted.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct PresentStats {
+  /// Number of monitor refreshes since the compositor started.
+  pub refresh_count: u64,
+  /// Refresh rate of the compositor in hertz.
+  pub refresh_rate: f64,
+  /// Time between two refreshes of the compositor.
+  pub refresh_period: Duration,
+  /// Number of frames composed since the compositor started.
+  pub frame_count: u64,
+  /// Number of frames that were dropped by the compositor.
+  pub frames_dropped: u64,
+  /// Number of frames that missed their refresh.
+  pub frames_missed: u64,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn rect() -> PhysicalRect {
+    PhysicalRect::new(PhysicalPosition::new(-10, 20), PhysicalSize::new(100, 50))
+  }
+
+  #[test]
+  fn rect_edges() {
+    let rect = rect();
+    assert_eq!(rect.left(), -10);
+    assert_eq!(rect.top(), 20);
+    assert_eq!(rect.right(), 90);
+    assert_eq!(rect.bottom(), 70);
+  }
+
+  #[test]
+  fn rect_contains_left_and_top_edges() {
+    let rect = rect();
+    assert!(rect.contains(PhysicalPosition::new(-10, 20)));
+    assert!(rect.contains(PhysicalPosition::new(-10, 69)));
+    assert!(rect.contains(PhysicalPosition::new(89, 20)));
+    assert!(rect.contains(PhysicalPosition::new(89, 69)));
+  }
+
+  #[test]
+  fn rect_excludes_right_and_bottom_edges() {
+    let rect = rect();
+    assert!(!rect.contains(PhysicalPosition::new(90, 20)));
+    assert!(!rect.contains(PhysicalPosition::new(-10, 70)));
+    assert!(!rect.contains(PhysicalPosition::new(90, 70)));
+    assert!(!rect.contains(PhysicalPosition::new(-11, 20)));
+    assert!(!rect.contains(PhysicalPosition::new(-10, 19)));
+  }
+
+  #[test]
+  fn empty_rect_contains_nothing() {
+    let rect = PhysicalRect::new(PhysicalPosition::new(5, 5), PhysicalSize::new(0, 0));
+    assert!(!rect.contains(PhysicalPosition::new(5, 5)));
+    assert_eq!(rect.clamp(PhysicalPosition::new(100, -100)), PhysicalPosition::new(5, 5));
+  }
+
+  #[test]
+  fn rect_clamps_inside_the_far_edges() {
+    let rect = rect();
+    assert_eq!(
+      rect.clamp(PhysicalPosition::new(1000, 1000)),
+      PhysicalPosition::new(89, 69)
+    );
+    assert_eq!(
+      rect.clamp(PhysicalPosition::new(-1000, -1000)),
+      PhysicalPosition::new(-10, 20)
+    );
+    assert_eq!(rect.clamp(PhysicalPosition::new(0, 30)), PhysicalPosition::new(0, 30));
+  }
+
+  #[test]
+  fn rect_from_win32_rect() {
+    let converted = PhysicalRect::from(RECT {
+      left: -10,
+      top: 20,
+      right: 90,
+      bottom: 70,
+    });
+    assert_eq!(converted, rect());
+
+    // inverted rects are empty rather than wrapping around
+    let inverted = PhysicalRect::from(RECT {
+      left: 10,
+      top: 10,
+      right: 0,
+      bottom: 0,
+    });
+    assert_eq!(inverted.size, PhysicalSize::new(0, 0));
+  }
+
+  #[test]
+  fn flash_modes_map_to_flash_window_args() {
+    use WindowsAndMessaging::{FLASHW_ALL, FLASHW_STOP, FLASHW_TIMERNOFG, FLASHW_TRAY};
+
+    assert_eq!(FlashMode::Once.flash_window_args(), (FLASHW_ALL, 1));
+    assert_eq!(
+      FlashMode::UntilFocused.flash_window_args(),
+      (FLASHW_ALL | FLASHW_TIMERNOFG, u32::MAX)
+    );
+    assert_eq!(
+      FlashMode::TaskbarUntilFocused.flash_window_args(),
+      (FLASHW_TRAY, u32::MAX)
+    );
+    assert_eq!(FlashMode::Stop.flash_window_args(), (FLASHW_STOP, 0));
+  }
+
+  #[test]
+  fn attention_maps_to_flash_modes() {
+    assert_eq!(FlashMode::from(UserAttention::Critical), FlashMode::UntilFocused);
+    assert_eq!(
+      FlashMode::from(UserAttention::Informational),
+      FlashMode::TaskbarUntilFocused
+    );
+  }
+}
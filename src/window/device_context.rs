@@ -0,0 +1,59 @@
+use std::marker::PhantomData;
+
+use windows::Win32::{
+  Foundation::HWND,
+  Graphics::Gdi::{GetDC, ReleaseDC, HDC},
+};
+
+use crate::Window;
+
+/// The device context of a window's client area, released on drop.
+///
+/// The window class is registered with `CS_OWNDC`, so every window has one
+/// private device context that lives as long as the window does, which is what
+/// OpenGL needs. Every guard of the same window refers to that same context, so
+/// anything selected into it (pens, fonts, pixel formats, …) stays selected for
+/// everyone, including later guards. Select the old objects back in when done
+/// with GDI.
+///
+/// GDI objects belong to the thread that created them, so use the context
+/// from one thread at a time.
+#[derive(Debug)]
+pub struct DeviceContext<'a> {
+  hwnd: HWND,
+  hdc: HDC,
+  _window: PhantomData<&'a Window>,
+}
+
+impl<'a> DeviceContext<'a> {
+  pub(crate) fn new(hwnd: HWND) -> Option<Self> {
+    let hdc = unsafe { GetDC(hwnd) };
+    if hdc.is_invalid() {
+      return None;
+    }
+
+    Some(Self {
+      hwnd,
+      hdc,
+      _window: PhantomData,
+    })
+  }
+
+  /// The raw `HDC`, valid until the guard is dropped.
+  pub fn raw(&self) -> isize {
+    self.hdc.0
+  }
+
+  #[cfg(feature = "interop")]
+  pub fn hdc(&self) -> HDC {
+    self.hdc
+  }
+}
+
+impl Drop for DeviceContext<'_> {
+  fn drop(&mut self) {
+    // a private device context isn't actually freed, but releasing it keeps
+    // the count balanced regardless of the class style
+    unsafe { ReleaseDC(self.hwnd, self.hdc) };
+  }
+}
@@ -0,0 +1,110 @@
+use std::fmt::{self, Display};
+
+use super::{cursor::Cursor, frame::Style, stage::Stage};
+#[cfg(feature = "diagnostics")]
+use crate::Message;
+use crate::{utilities::Monitor, DpiAwareness, Flow, PhysicalPosition, PhysicalSize};
+
+/// How many delivered messages are kept for [`Diagnostics::recent_messages`].
+#[cfg(feature = "diagnostics")]
+pub(crate) const MESSAGE_HISTORY_LEN: usize = 32;
+
+/// A snapshot of the state of a window, for bug reports. The [`Display`]
+/// output is meant to be pasted into an issue as is. See
+/// [`Window::diagnostics`](crate::Window::diagnostics).
+#[derive(Debug, Clone)]
+pub struct Diagnostics {
+  pub title: String,
+  pub stage: Stage,
+  pub flow: Flow,
+  /// The style witer thinks the window has.
+  pub style: Style,
+  /// `GWL_STYLE` as set on the window.
+  pub os_style: u32,
+  /// `GWL_STYLE` as derived from [`Diagnostics::style`].
+  pub expected_style: u32,
+  /// `GWL_EXSTYLE` as set on the window.
+  pub os_ex_style: u32,
+  /// `GWL_EXSTYLE` as derived from [`Diagnostics::style`].
+  pub expected_ex_style: u32,
+  pub dpi: u32,
+  pub dpi_awareness: DpiAwareness,
+  pub scale_factor: f64,
+  pub outer_position: PhysicalPosition,
+  pub outer_size: PhysicalSize,
+  pub inner_position: PhysicalPosition,
+  pub inner_size: PhysicalSize,
+  pub cursor: Cursor,
+  /// Every monitor, with the values captured when the snapshot was taken.
+  pub monitors: Vec<Monitor>,
+  /// Index into [`Diagnostics::monitors`] of the monitor the window is mostly
+  /// on.
+  pub current_monitor: Option<usize>,
+  /// The last messages taken from the window, oldest first.
+  #[cfg(feature = "diagnostics")]
+  pub recent_messages: Vec<Message>,
+}
+
+impl Diagnostics {
+  /// Bits of `GWL_STYLE` and `GWL_EXSTYLE` that differ from what the cached
+  /// style implies. Some bits, like `WS_CLIPSIBLINGS`, are added by the system
+  /// and show up here on every window.
+  pub fn style_mismatch(&self) -> (u32, u32) {
+    (self.os_style ^ self.expected_style, self.os_ex_style ^ self.expected_ex_style)
+  }
+}
+
+impl Display for Diagnostics {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let (style_mismatch, ex_style_mismatch) = self.style_mismatch();
+
+    writeln!(f, "witer {} diagnostics", env!("CARGO_PKG_VERSION"))?;
+    writeln!(f, "title: {:?}", self.title)?;
+    writeln!(f, "stage: {:?}, flow: {:?}", self.stage, self.flow)?;
+    writeln!(f, "style: {:?}", self.style)?;
+    writeln!(
+      f,
+      "GWL_STYLE: {:#010x} (expected {:#010x}, differs in {:#010x})",
+      self.os_style, self.expected_style, style_mismatch
+    )?;
+    writeln!(
+      f,
+      "GWL_EXSTYLE: {:#010x} (expected {:#010x}, differs in {:#010x})",
+      self.os_ex_style, self.expected_ex_style, ex_style_mismatch
+    )?;
+    writeln!(
+      f,
+      "dpi: {}, scale factor: {}, awareness: {:?}",
+      self.dpi, self.scale_factor, self.dpi_awareness
+    )?;
+    writeln!(f, "outer: {:?} {:?}", self.outer_position, self.outer_size)?;
+    writeln!(f, "inner: {:?} {:?}", self.inner_position, self.inner_size)?;
+    writeln!(f, "cursor: {:?}", self.cursor)?;
+
+    writeln!(f, "monitors:")?;
+    for (i, monitor) in self.monitors.iter().enumerate() {
+      writeln!(
+        f,
+        "  [{i}]{} rect: {:?}, work area: {:?}, scale factor: {}",
+        if self.current_monitor == Some(i) {
+          " (current)"
+        } else {
+          ""
+        },
+        monitor.rect(),
+        monitor.work_area(),
+        monitor.scale_factor()
+      )?;
+    }
+
+    #[cfg(feature = "diagnostics")]
+    {
+      writeln!(f, "recent messages:")?;
+      for message in &self.recent_messages {
+        writeln!(f, "  {message:?}")?;
+      }
+    }
+
+    Ok(())
+  }
+}
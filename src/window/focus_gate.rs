@@ -0,0 +1,83 @@
+use std::{
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+    Condvar,
+    Mutex,
+  },
+  time::Duration,
+};
+
+/// A handle to the keyboard focus of a window which other threads can check
+/// without touching the window, e.g. to drop controller input from a
+/// process-wide gamepad crate while the window is in the background. See
+/// [`Window::focus_gate`](crate::Window::focus_gate).
+///
+/// Cloning is cheap and every clone follows the same window.
+#[derive(Debug, Clone, Default)]
+pub struct FocusGate(Arc<Shared>);
+
+#[derive(Debug, Default)]
+struct Shared {
+  /// Mirrors [`GateState::focused`], so polling never takes the lock.
+  focused: AtomicBool,
+  state: Mutex<GateState>,
+  changed: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct GateState {
+  focused: bool,
+  closed: bool,
+}
+
+impl FocusGate {
+  /// Whether the window has keyboard focus. A single atomic load, so it can be
+  /// polled as often as needed.
+  pub fn is_focused(&self) -> bool {
+    self.0.focused.load(Ordering::Acquire)
+  }
+
+  /// Whether the window has closed. The gate stays unfocused from then on.
+  pub fn is_closed(&self) -> bool {
+    self.0.state.lock().unwrap().closed
+  }
+
+  /// Blocks until the focus of the window matches `focused`, the window closes,
+  /// or `timeout` passes. Returns whether the focus matches.
+  pub fn wait_until(&self, focused: bool, timeout: Option<Duration>) -> bool {
+    let state = self.0.state.lock().unwrap();
+    let waiting = |state: &mut GateState| state.focused != focused && !state.closed;
+    let state = match timeout {
+      Some(timeout) => {
+        self
+          .0
+          .changed
+          .wait_timeout_while(state, timeout, waiting)
+          .unwrap()
+          .0
+      }
+      None => self.0.changed.wait_while(state, waiting).unwrap(),
+    };
+    state.focused == focused
+  }
+
+  pub(crate) fn set_focused(&self, focused: bool) {
+    let mut state = self.0.state.lock().unwrap();
+    if state.closed || state.focused == focused {
+      return;
+    }
+    state.focused = focused;
+    self.0.focused.store(focused, Ordering::Release);
+    self.0.changed.notify_all();
+  }
+
+  /// Wakes every waiting thread for good.
+  pub(crate) fn close(&self) {
+    let mut state = self.0.state.lock().unwrap();
+    state.focused = false;
+    state.closed = true;
+    self.0.focused.store(false, Ordering::Release);
+    self.0.changed.notify_all();
+  }
+}
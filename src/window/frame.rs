@@ -1,11 +1,17 @@
-use crate::{Fullscreen, Visibility};
+use crate::{Color, Fullscreen, Visibility, WindowButtons};
 
 #[derive(Debug, Clone)]
 pub struct Style {
   pub visibility: Visibility,
   pub decorations: Visibility,
+  pub borderless_shadow: bool,
   pub fullscreen: Option<Fullscreen>,
   pub resizeable: bool,
+  pub enabled_buttons: WindowButtons,
+  pub focusable: bool,
+  pub popup: bool,
+  pub no_redirection_bitmap: bool,
+  pub transparency_color_key: Option<Color>,
   pub minimized: bool,
   pub maximized: bool,
   pub focused: bool,
@@ -0,0 +1,76 @@
+use windows::Win32::{
+  Foundation::HINSTANCE,
+  UI::WindowsAndMessaging::{CreateIcon, HICON},
+};
+
+use crate::error::WindowError;
+
+/// An image for the title bar and taskbar button. See
+/// [`Window::set_window_icon`](crate::Window::set_window_icon).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Icon {
+  rgba: Vec<u8>,
+  width: u32,
+  height: u32,
+}
+
+impl Icon {
+  /// Creates an icon from rows of unpremultiplied RGBA pixels, top row first.
+  /// Fails with [`WindowError::InvalidIcon`] if either side is zero or `rgba`
+  /// isn't `width * height * 4` bytes.
+  pub fn from_rgba(rgba: Vec<u8>, width: u32, height: u32) -> Result<Self, WindowError> {
+    if width == 0 || height == 0 || rgba.is_empty() {
+      return Err(WindowError::InvalidIcon(format!(
+        "{width}x{height} with {} bytes is empty",
+        rgba.len()
+      )));
+    }
+    let expected_len = width as usize * height as usize * 4;
+    if rgba.len() != expected_len {
+      return Err(WindowError::InvalidIcon(format!(
+        "{} bytes given, but {width}x{height} RGBA needs {expected_len}",
+        rgba.len()
+      )));
+    }
+
+    Ok(Self {
+      rgba,
+      width,
+      height,
+    })
+  }
+
+  pub fn width(&self) -> u32 {
+    self.width
+  }
+
+  pub fn height(&self) -> u32 {
+    self.height
+  }
+
+  /// Creates the icon handle, owned by the caller.
+  pub(crate) fn create(&self) -> Result<HICON, WindowError> {
+    // GDI wants BGRA
+    let mut bgra = self.rgba.clone();
+    for pixel in bgra.chunks_exact_mut(4) {
+      pixel.swap(0, 2);
+    }
+    // the alpha channel does the masking, but the AND mask has to exist. Its
+    // rows are padded to 16 bits.
+    let mask_stride = (self.width as usize).div_ceil(16) * 2;
+    let and_mask = vec![0u8; mask_stride * self.height as usize];
+
+    let hicon = unsafe {
+      CreateIcon(
+        HINSTANCE::default(),
+        self.width as i32,
+        self.height as i32,
+        1,
+        32,
+        and_mask.as_ptr(),
+        bgra.as_ptr(),
+      )
+    }?;
+    Ok(hicon)
+  }
+}
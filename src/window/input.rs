@@ -14,8 +14,10 @@ use self::state::KeyState;
 use crate::{
   utilities::is_flag_set,
   window::input::{key::Key, mouse::MouseButton, state::ButtonState},
+  Message,
 };
 
+pub mod app_command;
 pub mod key;
 pub mod mouse;
 pub mod state;
@@ -51,9 +53,7 @@ impl Input {
   }
 
   pub fn update_key_state(&mut self, keycode: Key, new_state: KeyState) {
-    if let Some(old_state) = self.keys.get_mut(&keycode) {
-      *old_state = new_state;
-    }
+    self.keys.insert(keycode, new_state);
   }
 
   pub fn update_mouse_button_state(
@@ -61,8 +61,29 @@ impl Input {
     button: MouseButton,
     new_state: ButtonState,
   ) {
-    if let Some(old_state) = self.mouse_buttons.get_mut(&button) {
-      *old_state = new_state;
+    self.mouse_buttons.insert(button, new_state);
+  }
+
+  /// Applies the input carried by a message, so the state only ever reflects
+  /// messages that have been delivered.
+  pub fn update_from_message(&mut self, message: &Message) {
+    match *message {
+      Message::Key { key, state, .. } => self.update_key_state(key, state),
+      Message::MouseButton { button, state, .. } => {
+        self.update_mouse_button_state(button, state)
+      }
+      Message::ModifiersChanged {
+        shift,
+        ctrl,
+        alt,
+        win,
+      } => {
+        self.shift = shift;
+        self.ctrl = ctrl;
+        self.alt = alt;
+        self.win = win;
+      }
+      _ => (),
     }
   }
 
@@ -159,3 +180,94 @@ impl Default for Input {
     Self::new()
   }
 }
+
+/// Input kept twice over: as the window thread has seen it, and as of the
+/// messages handed to the main thread. Polling reads the delivered copy, so it
+/// never runs ahead of the messages seen so far.
+#[derive(Debug, Default)]
+pub struct InputBuffers {
+  /// Input as the window thread has seen it, which may be ahead of the
+  /// messages the main thread has taken.
+  pub realtime: Input,
+  /// Input as of the last message handed to the main thread.
+  pub delivered: Input,
+}
+
+impl InputBuffers {
+  /// Catches the delivered copy up with a message being handed to the main
+  /// thread.
+  pub fn deliver(&mut self, message: &Message) {
+    self.delivered.update_from_message(message);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn key_message(state: KeyState) -> Message {
+    Message::Key {
+      key: Key::A,
+      state,
+      scan_code: 0x1E,
+      is_extended_key: false,
+    }
+  }
+
+  #[test]
+  fn key_press_hold_release() {
+    let mut input = Input::new();
+    assert!(input.key(Key::A).is_released());
+
+    input.update_from_message(&key_message(KeyState::Pressed));
+    assert_eq!(input.key(Key::A), KeyState::Pressed);
+    assert!(input.key(Key::A).is_down());
+
+    input.update_from_message(&key_message(KeyState::Held(1)));
+    assert_eq!(input.key(Key::A), KeyState::Held(1));
+    assert!(input.key(Key::A).is_down());
+
+    input.update_from_message(&key_message(KeyState::Released));
+    assert!(input.key(Key::A).is_released());
+    assert!(!input.key(Key::A).is_down());
+    assert!(input.key(Key::B).is_released());
+  }
+
+  #[test]
+  fn delivered_input_follows_delivered_messages() {
+    let mut input = InputBuffers::default();
+    let press = key_message(KeyState::Pressed);
+    let release = key_message(KeyState::Released);
+
+    // frame N - 1: the window thread sees the press, the main thread hasn't
+    // taken it yet
+    input.realtime.update_key_state(Key::A, KeyState::Pressed);
+    assert!(input.realtime.key(Key::A).is_pressed());
+    assert!(input.delivered.key(Key::A).is_released());
+
+    // frame N: the press is taken, and the release already queued behind it
+    input.deliver(&press);
+    input.realtime.update_key_state(Key::A, KeyState::Released);
+    assert!(input.delivered.key(Key::A).is_pressed());
+    assert!(input.realtime.key(Key::A).is_released());
+
+    // frame N + 1: the release is taken
+    input.deliver(&release);
+    assert!(input.delivered.key(Key::A).is_released());
+  }
+
+  #[test]
+  fn delivered_modifiers_follow_delivered_messages() {
+    let mut input = InputBuffers::default();
+    assert_eq!(input.delivered.shift(), ButtonState::Released);
+
+    input.deliver(&Message::ModifiersChanged {
+      shift: ButtonState::Pressed,
+      ctrl: ButtonState::Released,
+      alt: ButtonState::Released,
+      win: ButtonState::Released,
+    });
+    assert_eq!(input.delivered.shift(), ButtonState::Pressed);
+    assert_eq!(input.delivered.ctrl(), ButtonState::Released);
+  }
+}
@@ -0,0 +1,100 @@
+use windows::Win32::{System::SystemServices, UI::WindowsAndMessaging};
+
+use crate::utilities::hi_word;
+
+/// A command from a media key, a browser key, or a mouse or remote button,
+/// as sent by Windows in `WM_APPCOMMAND`. See
+/// [`Message::AppCommand`](crate::Message::AppCommand).
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum AppCommand {
+  BrowserBack,
+  BrowserForward,
+  BrowserRefresh,
+  BrowserStop,
+  BrowserSearch,
+  BrowserFavorites,
+  BrowserHome,
+  VolumeMute,
+  VolumeDown,
+  VolumeUp,
+  MicrophoneVolumeMute,
+  MicrophoneVolumeDown,
+  MicrophoneVolumeUp,
+  MediaPlayPause,
+  MediaPlay,
+  MediaPause,
+  MediaStop,
+  MediaRecord,
+  MediaNextTrack,
+  MediaPreviousTrack,
+  MediaFastForward,
+  MediaRewind,
+  MediaChannelUp,
+  MediaChannelDown,
+  LaunchMail,
+  LaunchMediaSelect,
+  LaunchApp1,
+  LaunchApp2,
+  /// Any other command, with its `APPCOMMAND_*` code.
+  Other(u16),
+}
+
+impl AppCommand {
+  /// Decodes the `lparam` of `WM_APPCOMMAND`, like `GET_APPCOMMAND_LPARAM`.
+  pub(crate) fn from_lparam(lparam: isize) -> Self {
+    let code = hi_word(lparam as u32) & !(WindowsAndMessaging::FAPPCOMMAND_MASK as u16);
+    Self::from_code(code)
+  }
+
+  fn from_code(code: u16) -> Self {
+    match SystemServices::APPCOMMAND_ID(code as u32) {
+      SystemServices::APPCOMMAND_BROWSER_BACKWARD => AppCommand::BrowserBack,
+      SystemServices::APPCOMMAND_BROWSER_FORWARD => AppCommand::BrowserForward,
+      SystemServices::APPCOMMAND_BROWSER_REFRESH => AppCommand::BrowserRefresh,
+      SystemServices::APPCOMMAND_BROWSER_STOP => AppCommand::BrowserStop,
+      SystemServices::APPCOMMAND_BROWSER_SEARCH => AppCommand::BrowserSearch,
+      SystemServices::APPCOMMAND_BROWSER_FAVORITES => AppCommand::BrowserFavorites,
+      SystemServices::APPCOMMAND_BROWSER_HOME => AppCommand::BrowserHome,
+      SystemServices::APPCOMMAND_VOLUME_MUTE => AppCommand::VolumeMute,
+      SystemServices::APPCOMMAND_VOLUME_DOWN => AppCommand::VolumeDown,
+      SystemServices::APPCOMMAND_VOLUME_UP => AppCommand::VolumeUp,
+      SystemServices::APPCOMMAND_MICROPHONE_VOLUME_MUTE => {
+        AppCommand::MicrophoneVolumeMute
+      }
+      SystemServices::APPCOMMAND_MICROPHONE_VOLUME_DOWN => {
+        AppCommand::MicrophoneVolumeDown
+      }
+      SystemServices::APPCOMMAND_MICROPHONE_VOLUME_UP => AppCommand::MicrophoneVolumeUp,
+      SystemServices::APPCOMMAND_MEDIA_PLAY_PAUSE => AppCommand::MediaPlayPause,
+      SystemServices::APPCOMMAND_MEDIA_PLAY => AppCommand::MediaPlay,
+      SystemServices::APPCOMMAND_MEDIA_PAUSE => AppCommand::MediaPause,
+      SystemServices::APPCOMMAND_MEDIA_STOP => AppCommand::MediaStop,
+      SystemServices::APPCOMMAND_MEDIA_RECORD => AppCommand::MediaRecord,
+      SystemServices::APPCOMMAND_MEDIA_NEXTTRACK => AppCommand::MediaNextTrack,
+      SystemServices::APPCOMMAND_MEDIA_PREVIOUSTRACK => AppCommand::MediaPreviousTrack,
+      SystemServices::APPCOMMAND_MEDIA_FAST_FORWARD => AppCommand::MediaFastForward,
+      SystemServices::APPCOMMAND_MEDIA_REWIND => AppCommand::MediaRewind,
+      SystemServices::APPCOMMAND_MEDIA_CHANNEL_UP => AppCommand::MediaChannelUp,
+      SystemServices::APPCOMMAND_MEDIA_CHANNEL_DOWN => AppCommand::MediaChannelDown,
+      SystemServices::APPCOMMAND_LAUNCH_MAIL => AppCommand::LaunchMail,
+      SystemServices::APPCOMMAND_LAUNCH_MEDIA_SELECT => AppCommand::LaunchMediaSelect,
+      SystemServices::APPCOMMAND_LAUNCH_APP1 => AppCommand::LaunchApp1,
+      SystemServices::APPCOMMAND_LAUNCH_APP2 => AppCommand::LaunchApp2,
+      _ => AppCommand::Other(code),
+    }
+  }
+
+  /// Whether the system acts on the command itself when the window leaves it
+  /// alone, i.e. the volume keys changing the system volume.
+  pub(crate) fn is_handled_by_system(&self) -> bool {
+    matches!(
+      self,
+      AppCommand::VolumeMute
+        | AppCommand::VolumeDown
+        | AppCommand::VolumeUp
+        | AppCommand::MicrophoneVolumeMute
+        | AppCommand::MicrophoneVolumeDown
+        | AppCommand::MicrophoneVolumeUp
+    )
+  }
+}
@@ -8,6 +8,10 @@ use windows::Win32::UI::{
 
 use crate::utilities::is_flag_set;
 
+/// A key on the keyboard, independent of the layout.
+///
+/// With the `interop` feature, converts to and from the windows-rs
+/// `VIRTUAL_KEY` through [`From`].
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum Key {
   Unknown = 0,
@@ -160,8 +164,163 @@ pub enum Key {
   Copy,
 }
 
-impl From<VIRTUAL_KEY> for Key {
-  fn from(value: VIRTUAL_KEY) -> Self {
+impl Key {
+  /// Every key, in declaration order. Includes [`Key::Unknown`].
+  pub const ALL: [Key; 146] = [
+    Key::Unknown,
+    Key::Tab,
+    Key::Enter,
+    Key::Space,
+    Key::Apostrophe,
+    Key::Comma,
+    Key::Minus,
+    Key::Period,
+    Key::ForwardSlash,
+    Key::_0,
+    Key::_1,
+    Key::_2,
+    Key::_3,
+    Key::_4,
+    Key::_5,
+    Key::_6,
+    Key::_7,
+    Key::_8,
+    Key::_9,
+    Key::Semicolon,
+    Key::Equals,
+    Key::A,
+    Key::B,
+    Key::C,
+    Key::D,
+    Key::E,
+    Key::F,
+    Key::G,
+    Key::H,
+    Key::I,
+    Key::J,
+    Key::K,
+    Key::L,
+    Key::M,
+    Key::N,
+    Key::O,
+    Key::P,
+    Key::Q,
+    Key::R,
+    Key::S,
+    Key::T,
+    Key::U,
+    Key::V,
+    Key::W,
+    Key::X,
+    Key::Y,
+    Key::Z,
+    Key::LeftBracket,
+    Key::BackSlash,
+    Key::RightBracket,
+    Key::Accent,
+    Key::Escape,
+    Key::NumEnter,
+    Key::Backspace,
+    Key::Insert,
+    Key::Delete,
+    Key::Up,
+    Key::Down,
+    Key::Left,
+    Key::Right,
+    Key::PageUp,
+    Key::PageDown,
+    Key::Home,
+    Key::End,
+    Key::CapsLock,
+    Key::ScrollLock,
+    Key::NumLock,
+    Key::PrintScreen,
+    Key::Pause,
+    Key::Num0,
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+    Key::F1,
+    Key::F2,
+    Key::F3,
+    Key::F4,
+    Key::F5,
+    Key::F6,
+    Key::F7,
+    Key::F8,
+    Key::F9,
+    Key::F10,
+    Key::F11,
+    Key::F12,
+    Key::F13,
+    Key::F14,
+    Key::F15,
+    Key::F16,
+    Key::F17,
+    Key::F18,
+    Key::F19,
+    Key::F20,
+    Key::F21,
+    Key::F22,
+    Key::F23,
+    Key::F24,
+    Key::NumPeriod,
+    Key::NumComma,
+    Key::NumPlus,
+    Key::NumMinus,
+    Key::NumDivide,
+    Key::NumMultiply,
+    Key::NumEquals,
+    Key::LeftShift,
+    Key::LeftControl,
+    Key::LeftAlt,
+    Key::LeftSuper,
+    Key::RightShift,
+    Key::RightControl,
+    Key::RightAlt,
+    Key::RightSuper,
+    Key::Menu,
+    Key::AbntC1,
+    Key::AbntC2,
+    Key::Ax,
+    Key::Convert,
+    Key::Kana,
+    Key::Kanji,
+    Key::Mail,
+    Key::MediaPlayPause,
+    Key::MediaStop,
+    Key::MediaSelect,
+    Key::MediaNextTrack,
+    Key::MediaPrevTrack,
+    Key::VolumeDown,
+    Key::VolumeUp,
+    Key::VolumeMute,
+    Key::NoConvert,
+    Key::OEM102,
+    Key::Sleep,
+    Key::NoName,
+    Key::WebBack,
+    Key::WebFavorites,
+    Key::WebForward,
+    Key::WebHome,
+    Key::WebRefresh,
+    Key::WebSearch,
+    Key::WebStop,
+    Key::Copy,
+  ];
+
+  /// Iterates over every key, e.g. to list them on a keybinding screen.
+  pub fn all() -> impl Iterator<Item = Key> {
+    Self::ALL.into_iter()
+  }
+
+  pub(crate) fn from_virtual_key(value: VIRTUAL_KEY) -> Self {
     match value {
       KeyboardAndMouse::VK_1 => Key::_1,
       KeyboardAndMouse::VK_2 => Key::_2,
@@ -308,11 +467,10 @@ impl From<VIRTUAL_KEY> for Key {
       _ => Key::Unknown,
     }
   }
-}
 
-impl From<Key> for VIRTUAL_KEY {
-  fn from(value: Key) -> Self {
-    match value {
+  #[allow(unused)]
+  pub(crate) fn to_virtual_key(self) -> VIRTUAL_KEY {
+    match self {
       Key::_1 => KeyboardAndMouse::VK_1,
       Key::_2 => KeyboardAndMouse::VK_2,
       Key::_3 => KeyboardAndMouse::VK_3,
@@ -463,6 +621,20 @@ impl From<Key> for VIRTUAL_KEY {
   }
 }
 
+#[cfg(feature = "interop")]
+impl From<VIRTUAL_KEY> for Key {
+  fn from(value: VIRTUAL_KEY) -> Self {
+    Self::from_virtual_key(value)
+  }
+}
+
+#[cfg(feature = "interop")]
+impl From<Key> for VIRTUAL_KEY {
+  fn from(value: Key) -> Self {
+    value.to_virtual_key()
+  }
+}
+
 impl Key {
   /*
    Stolen from winit, under the Apache-2.0 license. See winit's license for more details.
@@ -510,7 +682,14 @@ impl Key {
       // https://devblogs.microsoft.com/oldnewthing/20080211-00/?p=23503
       return None;
     }
-    let physical_key = if keyboard.VKey == KeyboardAndMouse::VK_NUMLOCK.0 {
+    let physical_key = if keyboard.VKey == KeyboardAndMouse::VK_PAUSE.0 {
+      // Pause is reported with the same 0x0045 scancode as NumLock (after the
+      // ignored 0xE11D prefix), so match the windowed path and use the virtual
+      // key.
+      Key::Pause
+    } else if keyboard.VKey == KeyboardAndMouse::VK_SNAPSHOT.0 {
+      Key::PrintScreen
+    } else if keyboard.VKey == KeyboardAndMouse::VK_NUMLOCK.0 {
       // Historically, the NumLock and the Pause key were one and the same physical
       // key. The user could trigger Pause by pressing Ctrl+NumLock.
       // Now these are often physically separate and the two keys can be
@@ -526,7 +705,7 @@ impl Key {
       // https://devblogs.microsoft.com/oldnewthing/20080211-00/?p=23503
       Key::NumLock
     } else {
-      Key::from(VIRTUAL_KEY(unsafe {
+      Key::from_virtual_key(VIRTUAL_KEY(unsafe {
         MapVirtualKeyW(scancode as u32, KeyboardAndMouse::MAPVK_VSC_TO_VK_EX) as u16
       }))
     };
@@ -568,3 +747,45 @@ impl Key {
     Some(physical_key)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashSet;
+
+  use super::*;
+
+  #[test]
+  fn all_lists_each_key_once() {
+    let unique: HashSet<Key> = Key::all().collect();
+    assert_eq!(unique.len(), Key::ALL.len());
+
+    // every key a virtual key can turn into must be listed
+    for code in 0..=u16::from(u8::MAX) {
+      let key = Key::from_virtual_key(VIRTUAL_KEY(code));
+      assert!(unique.contains(&key), "{key:?} is missing");
+    }
+  }
+
+  #[test]
+  fn all_is_in_declaration_order() {
+    assert_eq!(Key::ALL[0], Key::Unknown);
+    for pair in Key::ALL.windows(2) {
+      assert!((pair[0] as u32) < (pair[1] as u32), "{:?} before {:?}", pair[0], pair[1]);
+    }
+  }
+
+  #[test]
+  fn virtual_key_round_trip() {
+    // these share their virtual key with the main keyboard and are only told
+    // apart by the extended key flag or the scan code
+    let shared = [Key::NumEnter, Key::NumComma, Key::NumEquals];
+    let failed: Vec<_> = Key::all()
+      .filter(|key| !shared.contains(key))
+      .filter(|&key| Key::from_virtual_key(key.to_virtual_key()) != key)
+      .map(|key| (key, key.to_virtual_key()))
+      .collect();
+    assert!(failed.is_empty(), "{failed:?}");
+
+    assert_eq!(Key::from_virtual_key(Key::NumEnter.to_virtual_key()), Key::Enter);
+  }
+}
@@ -1,6 +1,7 @@
 use windows::Win32::UI::WindowsAndMessaging;
 
 use super::state::ButtonState;
+use crate::utilities::is_flag_set;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 #[repr(u16)]
@@ -9,49 +10,127 @@ pub enum MouseButton {
   Left = 1,
   Right = 2,
   Middle = 3,
+  /// `XBUTTON1`, or button 4 in raw input.
   Back = 4,
+  /// `XBUTTON2`, or button 5 in raw input.
   Forward = 5,
 }
 
-impl MouseButton {
-  pub(crate) fn from_state(id: usize) -> MouseButton {
-    match id {
-      0 => Self::Left,
-      1 => Self::Right,
-      2 => Self::Middle,
-      3 => Self::Back,
-      4 => Self::Forward,
-      _ => Self::Unknown,
-    }
-  }
+/// The down and up flags of each button in a raw mouse report.
+const RAW_BUTTON_FLAGS: [(MouseButton, u32, u32); 5] = [
+  (
+    MouseButton::Left,
+    WindowsAndMessaging::RI_MOUSE_BUTTON_1_DOWN,
+    WindowsAndMessaging::RI_MOUSE_BUTTON_1_UP,
+  ),
+  (
+    MouseButton::Right,
+    WindowsAndMessaging::RI_MOUSE_BUTTON_2_DOWN,
+    WindowsAndMessaging::RI_MOUSE_BUTTON_2_UP,
+  ),
+  (
+    MouseButton::Middle,
+    WindowsAndMessaging::RI_MOUSE_BUTTON_3_DOWN,
+    WindowsAndMessaging::RI_MOUSE_BUTTON_3_UP,
+  ),
+  (
+    MouseButton::Back,
+    WindowsAndMessaging::RI_MOUSE_BUTTON_4_DOWN,
+    WindowsAndMessaging::RI_MOUSE_BUTTON_4_UP,
+  ),
+  (
+    MouseButton::Forward,
+    WindowsAndMessaging::RI_MOUSE_BUTTON_5_DOWN,
+    WindowsAndMessaging::RI_MOUSE_BUTTON_5_UP,
+  ),
+];
+
+/// Every button transition in the `usButtonFlags` of a raw mouse report.
+pub(crate) fn mouse_button_transitions(
+  flags: u16,
+) -> impl Iterator<Item = (MouseButton, ButtonState)> {
+  RAW_BUTTON_FLAGS
+    .into_iter()
+    .flat_map(move |(button, down, up)| {
+      // a quick click can press and release in the same report, so both are
+      // kept, press first
+      [(down, ButtonState::Pressed), (up, ButtonState::Released)]
+        .into_iter()
+        .filter(move |(flag, _)| is_flag_set(flags as u32, *flag))
+        .map(move |(_, state)| (button, state))
+    })
 }
 
-pub(crate) fn mouse_button_states(flags: u16) -> [Option<ButtonState>; 5] {
+/// The scroll deltas, in notches, of a raw mouse report with the given
+/// `usButtonFlags` and `usButtonData`.
+pub(crate) fn mouse_wheel_deltas(
+  flags: u16,
+  data: u16,
+) -> impl Iterator<Item = (f32, f32)> {
+  let delta = data as i16 as f32 / WindowsAndMessaging::WHEEL_DELTA as f32;
+  // tilting the wheel sideways is reported as horizontal scrolling
   [
-    ButtonState::from_flag(
-      flags,
-      WindowsAndMessaging::RI_MOUSE_BUTTON_1_DOWN,
-      WindowsAndMessaging::RI_MOUSE_BUTTON_1_UP,
-    ),
-    ButtonState::from_flag(
-      flags,
-      WindowsAndMessaging::RI_MOUSE_BUTTON_2_DOWN,
-      WindowsAndMessaging::RI_MOUSE_BUTTON_2_UP,
-    ),
-    ButtonState::from_flag(
-      flags,
-      WindowsAndMessaging::RI_MOUSE_BUTTON_3_DOWN,
-      WindowsAndMessaging::RI_MOUSE_BUTTON_3_UP,
-    ),
-    ButtonState::from_flag(
-      flags,
-      WindowsAndMessaging::RI_MOUSE_BUTTON_4_DOWN,
-      WindowsAndMessaging::RI_MOUSE_BUTTON_4_UP,
-    ),
-    ButtonState::from_flag(
-      flags,
-      WindowsAndMessaging::RI_MOUSE_BUTTON_5_DOWN,
-      WindowsAndMessaging::RI_MOUSE_BUTTON_5_UP,
-    ),
+    (WindowsAndMessaging::RI_MOUSE_WHEEL, (0.0, delta)),
+    (WindowsAndMessaging::RI_MOUSE_HWHEEL, (delta, 0.0)),
   ]
+  .into_iter()
+  .filter(move |(flag, _)| is_flag_set(flags as u32, *flag))
+  .map(|(_, deltas)| deltas)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn button_flag_matrix() {
+    let transitions =
+      |flags: u32| mouse_button_transitions(flags as u16).collect::<Vec<_>>();
+    for (button, down, up) in RAW_BUTTON_FLAGS {
+      assert_eq!(transitions(down), [(button, ButtonState::Pressed)]);
+      assert_eq!(transitions(up), [(button, ButtonState::Released)]);
+      // a quick click presses and releases in one report
+      assert_eq!(transitions(down | up), [
+        (button, ButtonState::Pressed),
+        (button, ButtonState::Released)
+      ]);
+    }
+  }
+
+  #[test]
+  fn no_button_flags_no_transitions() {
+    assert_eq!(mouse_button_transitions(0).count(), 0);
+    let wheel =
+      WindowsAndMessaging::RI_MOUSE_WHEEL | WindowsAndMessaging::RI_MOUSE_HWHEEL;
+    assert_eq!(mouse_button_transitions(wheel as u16).count(), 0);
+  }
+
+  #[test]
+  fn transitions_follow_button_order() {
+    let flags = WindowsAndMessaging::RI_MOUSE_BUTTON_5_UP
+      | WindowsAndMessaging::RI_MOUSE_BUTTON_1_DOWN
+      | WindowsAndMessaging::RI_MOUSE_BUTTON_3_DOWN
+      | WindowsAndMessaging::RI_MOUSE_BUTTON_3_UP;
+    assert_eq!(mouse_button_transitions(flags as u16).collect::<Vec<_>>(), [
+      (MouseButton::Left, ButtonState::Pressed),
+      (MouseButton::Middle, ButtonState::Pressed),
+      (MouseButton::Middle, ButtonState::Released),
+      (MouseButton::Forward, ButtonState::Released),
+    ]);
+  }
+
+  #[test]
+  fn wheel_deltas() {
+    let wheel = WindowsAndMessaging::RI_MOUSE_WHEEL as u16;
+    let hwheel = WindowsAndMessaging::RI_MOUSE_HWHEEL as u16;
+    let notch = WindowsAndMessaging::WHEEL_DELTA as i16;
+    let deltas =
+      |flags, data: i16| mouse_wheel_deltas(flags, data as u16).collect::<Vec<_>>();
+
+    assert_eq!(deltas(wheel, notch), [(0.0, 1.0)]);
+    // scrolling back is negative, stored in the unsigned data
+    assert_eq!(deltas(wheel, -2 * notch), [(0.0, -2.0)]);
+    assert_eq!(deltas(hwheel, notch / 2), [(0.5, 0.0)]);
+    assert_eq!(deltas(0, notch), []);
+  }
 }
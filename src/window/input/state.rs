@@ -1,5 +1,3 @@
-use crate::utilities::is_flag_set;
-
 #[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum ButtonState {
   #[default]
@@ -11,16 +9,6 @@ impl ButtonState {
   pub fn is_pressed(self) -> bool {
     self == ButtonState::Pressed
   }
-
-  pub(crate) fn from_flag(flags: u16, down_flag: u32, up_flag: u32) -> Option<Self> {
-    if is_flag_set(flags as u32, down_flag) {
-      Some(ButtonState::Pressed)
-    } else if is_flag_set(flags as u32, up_flag) {
-      Some(ButtonState::Released)
-    } else {
-      None
-    }
-  }
 }
 
 #[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
@@ -36,6 +24,26 @@ impl KeyState {
     // this covers the first moments of the keypress as well
     self != KeyState::Released
   }
+
+  /// Returns `true` if the key is pressed or held.
+  pub fn is_down(self) -> bool {
+    matches!(self, KeyState::Pressed | KeyState::Held(_))
+  }
+
+  pub fn is_released(self) -> bool {
+    self == KeyState::Released
+  }
+}
+
+impl From<RawKeyState> for KeyState {
+  /// Raw input doesn't report repeats, so a raw press is always
+  /// [`KeyState::Pressed`].
+  fn from(value: RawKeyState) -> Self {
+    match value {
+      RawKeyState::Pressed => KeyState::Pressed,
+      RawKeyState::Released => KeyState::Released,
+    }
+  }
 }
 
 #[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
@@ -51,6 +59,10 @@ impl RawKeyState {
     self != RawKeyState::Released
   }
 
+  pub fn is_released(self) -> bool {
+    self == RawKeyState::Released
+  }
+
   pub(crate) fn from_bools(down_flag: bool, up_flag: bool) -> Option<Self> {
     if down_flag {
       Some(RawKeyState::Pressed)
@@ -61,3 +73,39 @@ impl RawKeyState {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn key_state_predicates() {
+    let cases = [
+      (KeyState::Released, false, true),
+      (KeyState::Pressed, true, false),
+      (KeyState::Held(1), true, false),
+      (KeyState::Held(0), true, false),
+    ];
+    for (state, down, released) in cases {
+      assert_eq!(state.is_down(), down, "{state:?}");
+      assert_eq!(state.is_pressed(), down, "{state:?}");
+      assert_eq!(state.is_released(), released, "{state:?}");
+    }
+  }
+
+  #[test]
+  fn raw_key_state_conversion() {
+    assert_eq!(KeyState::from(RawKeyState::Pressed), KeyState::Pressed);
+    assert_eq!(KeyState::from(RawKeyState::Released), KeyState::Released);
+    assert_eq!(KeyState::from(RawKeyState::default()), KeyState::default());
+  }
+
+  #[test]
+  fn raw_key_state_from_flags() {
+    assert_eq!(RawKeyState::from_bools(true, false), Some(RawKeyState::Pressed));
+    assert_eq!(RawKeyState::from_bools(false, true), Some(RawKeyState::Released));
+    // the down flag wins when both are set
+    assert_eq!(RawKeyState::from_bools(true, true), Some(RawKeyState::Pressed));
+    assert_eq!(RawKeyState::from_bools(false, false), None);
+  }
+}
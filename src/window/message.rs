@@ -1,3 +1,5 @@
+use std::{path::PathBuf, sync::Arc};
+
 use windows::Win32::{
   Foundation::{HINSTANCE, HWND, LPARAM, RECT, WPARAM},
   System::SystemServices::{
@@ -9,17 +11,18 @@ use windows::Win32::{
     MODIFIERKEYS_FLAGS,
   },
   UI::{
-    Input::KeyboardAndMouse::{MapVirtualKeyW, MAPVK_VSC_TO_VK_EX, VIRTUAL_KEY},
+    Input::KeyboardAndMouse::{self, MapVirtualKeyW, MAPVK_VSC_TO_VK_EX, VIRTUAL_KEY},
     WindowsAndMessaging::{self, GetClientRect},
   },
 };
 
 use super::{
   command::Command,
-  data::{PhysicalPosition, PhysicalSize},
-  input::{mouse::MouseButton, state::RawKeyState},
+  data::{AccentColors, EffectiveTheme, PhysicalPosition, PhysicalRect, PhysicalSize},
+  input::{app_command::AppCommand, mouse::MouseButton, state::RawKeyState},
 };
 use crate::{
+  error::WindowError,
   utilities::{hi_word, is_flag_set, lo_byte, lo_word, signed_hi_word, signed_lo_word},
   window::input::{
     key::Key,
@@ -40,13 +43,26 @@ pub enum Message {
   Loop(LoopMessage),
   /// Messages sent by devices registered for raw input.
   RawInput(RawInputMessage),
-  /// Message sent when window is created.
+  /// Message sent when window is created. The size, position, decorations and
+  /// fullscreen state given to the builder are all applied by then, so
+  /// [`Window::inner_size`](crate::Window::inner_size) can be used right away,
+  /// e.g. to configure a surface.
   Created { hwnd: HWND, hinstance: HINSTANCE },
-  /// Message sent when window X button is pressed.
+  /// Message sent when window X button is pressed. Closes the window unless
+  /// [`WindowBuilder::with_close_on_x`](crate::WindowBuilder::with_close_on_x)
+  /// is disabled, in which case answer it with
+  /// [`Window::confirm_close`](crate::Window::confirm_close) or
+  /// [`Window::cancel_close`](crate::Window::cancel_close).
   CloseRequested,
+  /// Message sent when a shown popup built with
+  /// [`WindowBuilder::with_auto_dismiss`](crate::WindowBuilder::with_auto_dismiss)
+  /// should go away, because the user clicked outside of it or switched to
+  /// another app. Nothing is done to the popup; hide or drop it in response.
+  DismissRequested,
   /// Message sent when Windows requests the window be repainted.
   Paint,
-  /// Message sent when a key is pressed, held, or released.
+  /// Message sent when a key is pressed, held, or released. If the key press
+  /// produces text, the [`Message::Text`] is always sent after this message.
   Key {
     key: Key,
     state: KeyState,
@@ -54,7 +70,25 @@ pub enum Message {
     is_extended_key: bool,
   },
   /// Message sent when a text character is typed containing that character.
+  /// Always sent after the [`Message::Key`] which produced it. Control
+  /// characters are filtered out unless
+  /// [`WindowBuilder::with_raw_text`](crate::WindowBuilder::with_raw_text) is
+  /// enabled.
   Text(String),
+  /// Message sent when the input method editor starts composing text, e.g.
+  /// for Chinese, Japanese, or Korean input.
+  ImeStarted,
+  /// Message sent when the text being composed changes. `cursor_range` is the
+  /// byte range of the part being converted, or the cursor, within `text`. An
+  /// empty `text` without a range ends the composition, whether it was
+  /// committed or cancelled.
+  ImePreedit {
+    text: String,
+    cursor_range: Option<(usize, usize)>,
+  },
+  /// Message sent when the composed text is committed. The text doesn't also
+  /// arrive as [`Message::Text`].
+  ImeCommit(String),
   ModifiersChanged {
     shift: ButtonState,
     ctrl: ButtonState,
@@ -68,8 +102,18 @@ pub enum Message {
     position: PhysicalPosition,
     is_double_click: bool,
   },
+  /// Message sent when fingers touch, move across, or leave a touchscreen, or
+  /// a pen does the same on a tablet. Windows also turns the primary contact
+  /// into mouse messages, so handle one or the other.
+  Touch(Vec<TouchPoint>),
   /// Message sent when the scroll wheel is actuated.
   MouseWheel { delta_x: f32, delta_y: f32 },
+  /// Message sent when a media, volume, or browser key is pressed, or a mouse
+  /// or remote button bound to such a command. Keyboards don't always send
+  /// these keys as [`Message::Key`], so this is the reliable way to react to
+  /// them. The volume commands are still left to the system, so the volume
+  /// changes as usual.
+  AppCommand(AppCommand),
   /// Message sent when the cursor is moved within the window bounds. Don't
   /// use this for mouse input in cases such as first-person cameras as it is
   /// locked to the bounds of the window.
@@ -94,6 +138,99 @@ pub enum Message {
   Focus(Focus),
   /// Message sent when the scale factor of the window has changed.
   ScaleFactorChanged(f64),
+  /// Message sent when something happened which usually invalidates
+  /// swapchains, so renderers can reconfigure before presenting fails. This is
+  /// advisory only: surface errors can still occur without it, and it doesn't
+  /// guarantee the surface was actually lost.
+  SurfaceInvalidated(SurfaceInvalidationReason),
+  /// Message sent when the effective theme of a window following the system
+  /// theme has changed.
+  ThemeChanged(EffectiveTheme),
+  /// Message sent when the system accent color or one of its shades has
+  /// changed.
+  AccentColorChanged(AccentColors),
+  /// Message sent when files are dragged from e.g. Explorer and dropped onto
+  /// the window.
+  FilesDropped(Vec<PathBuf>),
+  /// Message sent when the app was launched again and the new process handed
+  /// over its arguments with
+  /// [`SingleInstance::notify_existing`](crate::single_instance::SingleInstance::notify_existing).
+  /// Only sent to windows built with
+  /// [`WindowBuilder::with_single_instance`](crate::WindowBuilder::with_single_instance).
+  SecondInstanceLaunched(Vec<String>),
+  /// Message sent when the window receives a message in the `WM_APP` range,
+  /// e.g. from [`Window::post_app_message`](crate::Window::post_app_message).
+  /// `code` is the offset from `WM_APP`.
+  App {
+    code: u16,
+    wparam: usize,
+    lparam: isize,
+  },
+}
+
+/// A filter for [`Window::set_pre_iterator_filter`](crate::Window::set_pre_iterator_filter).
+pub type MessageFilter = fn(Message) -> Option<Message>;
+
+/// Passes a message through the pre-iterator filter, if there is one.
+/// [`Message::Loop`] messages bypass it. Returns [`None`] if the message was
+/// dropped.
+pub(crate) fn filter_message(
+  message: Message,
+  filter: Option<MessageFilter>,
+) -> Option<Message> {
+  match filter {
+    Some(filter) if !message.is_loop() => filter(message),
+    _ => Some(message),
+  }
+}
+
+/// The last message id in the `WM_APP` range.
+///
+/// Private message ranges are split like this:
+/// - `WM_USER..WM_APP` is reserved by witer. Its commands use `WM_USER + 69`.
+/// - `WM_APP..=APP_MESSAGE_LAST` is left entirely to applications and surfaces
+///   as [`Message::App`]. witer never posts into it itself.
+pub const APP_MESSAGE_LAST: u32 = 0xBFFF;
+
+/// The message id for the app message `code`, or [`None`] if it lies past
+/// [`APP_MESSAGE_LAST`].
+pub(crate) fn app_message_id(code: u16) -> Option<u32> {
+  let msg = WindowsAndMessaging::WM_APP + code as u32;
+  (msg <= APP_MESSAGE_LAST).then_some(msg)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TouchPhase {
+  Started,
+  Moved,
+  Ended,
+  /// The contact was lost without being lifted, e.g. because a palm was
+  /// detected or another window took over.
+  Cancelled,
+}
+
+/// A single finger or pen contact. See [`Message::Touch`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TouchPoint {
+  /// The same from [`TouchPhase::Started`] until the contact ends. Can be
+  /// reused by a later contact.
+  pub id: u32,
+  /// In client coordinates.
+  pub position: PhysicalPosition,
+  pub phase: TouchPhase,
+  /// Pen pressure from `0.0` to `1.0`. Touchscreens don't report it.
+  pub force: Option<f32>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SurfaceInvalidationReason {
+  /// The display configuration changed, such as the resolution, the monitor
+  /// layout, or the display driver restarting.
+  DisplayChanged,
+  /// The session was locked.
+  SessionLocked,
+  /// The session was unlocked.
+  SessionUnlocked,
 }
 
 /// Artificial window messages sent by the window loop.
@@ -103,8 +240,43 @@ pub enum LoopMessage {
   Command(Command),
   /// Sent when the message pump is polled, but there are no messages.
   Empty,
-  /// Sent when the message pump is exiting.
-  Exit,
+  /// Sent once the queue has drained and no redraw is pending, before the loop
+  /// waits or polls for more. Good for low priority background work. Not sent
+  /// again until another message arrives.
+  Idle,
+  /// Sent when the message pump is exiting, along with why it is exiting.
+  Exit(ExitReason),
+}
+
+/// Why the window loop exited. Sent with [`LoopMessage::Exit`].
+#[derive(Debug, Clone)]
+pub enum ExitReason {
+  /// The user closed the window with the X button while `close_on_x` was
+  /// enabled, or the app confirmed the request with
+  /// [`Window::confirm_close`](crate::Window::confirm_close).
+  UserClosed,
+  /// The application called [`Window::close`](crate::Window::close).
+  AppRequested,
+  /// The window was closed due to an error. Wrapped in an [`Arc`] so the
+  /// message can still be cloned.
+  Error(Arc<WindowError>),
+  /// The user session is ending (logoff, shutdown, or restart).
+  SessionEnd,
+}
+
+impl PartialEq for ExitReason {
+  fn eq(&self, other: &Self) -> bool {
+    match (self, other) {
+      (Self::Error(a), Self::Error(b)) => Arc::ptr_eq(a, b),
+      _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+    }
+  }
+}
+
+impl From<WindowError> for ExitReason {
+  fn from(value: WindowError) -> Self {
+    Self::Error(Arc::new(value))
+  }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -119,33 +291,44 @@ pub enum RawInputMessage {
   /// Raw mouse motion. Use this for mouse input in cases such as first-person
   /// cameras.
   MouseMove { delta_x: f32, delta_y: f32 },
+  /// Raw scroll wheel input, in notches. Tilting the wheel sideways scrolls
+  /// horizontally.
+  MouseWheel { delta_x: f32, delta_y: f32 },
 }
 
 impl Message {
-  pub(crate) fn new_keyboard_message(l_param: LPARAM) -> Message {
+  pub(crate) fn new_keyboard_message(w_param: WPARAM, l_param: LPARAM) -> Message {
     let flags = hi_word(unsafe { std::mem::transmute::<i32, u32>(l_param.0 as i32) });
 
     let is_extended_key = is_flag_set(flags, WindowsAndMessaging::KF_EXTENDED as u16);
 
     let mut scan_code = lo_byte(flags) as u16;
 
-    let key_code: Key = {
-      let extended_scan_code = u16::from_le_bytes([scan_code as u8, 0xE0]);
-      let extended_virtual_keycode = VIRTUAL_KEY(lo_word(unsafe {
-        MapVirtualKeyW(extended_scan_code as u32, MAPVK_VSC_TO_VK_EX)
-      }));
-
-      let virtual_keycode =
-        if extended_virtual_keycode != VIRTUAL_KEY(0) && is_extended_key {
-          scan_code = extended_scan_code;
-          extended_virtual_keycode
-        } else {
-          VIRTUAL_KEY(lo_word(unsafe {
-            MapVirtualKeyW(scan_code as u32, MAPVK_VSC_TO_VK_EX)
-          }))
-        };
-
-      virtual_keycode.into()
+    let key_code: Key = match VIRTUAL_KEY(w_param.0 as u16) {
+      // Pause shares its scan code with NumLock, and PrintScreen / ScrollLock
+      // can arrive with scan codes that map to other keys, so trust the
+      // virtual key for these instead.
+      KeyboardAndMouse::VK_PAUSE => Key::Pause,
+      KeyboardAndMouse::VK_SNAPSHOT => Key::PrintScreen,
+      KeyboardAndMouse::VK_SCROLL => Key::ScrollLock,
+      _ => {
+        let extended_scan_code = u16::from_le_bytes([scan_code as u8, 0xE0]);
+        let extended_virtual_keycode = VIRTUAL_KEY(lo_word(unsafe {
+          MapVirtualKeyW(extended_scan_code as u32, MAPVK_VSC_TO_VK_EX)
+        }));
+
+        let virtual_keycode =
+          if extended_virtual_keycode != VIRTUAL_KEY(0) && is_extended_key {
+            scan_code = extended_scan_code;
+            extended_virtual_keycode
+          } else {
+            VIRTUAL_KEY(lo_word(unsafe {
+              MapVirtualKeyW(scan_code as u32, MAPVK_VSC_TO_VK_EX)
+            }))
+          };
+
+        Key::from_virtual_key(virtual_keycode)
+      }
     };
 
     let state = {
@@ -168,6 +351,19 @@ impl Message {
     }
   }
 
+  /// Wraps a message in the `WM_APP` range, see [`app_message_id`].
+  pub(crate) fn new_app_message(
+    message: u32,
+    w_param: WPARAM,
+    l_param: LPARAM,
+  ) -> Message {
+    Message::App {
+      code: (message - WindowsAndMessaging::WM_APP) as u16,
+      wparam: w_param.0,
+      lparam: l_param.0,
+    }
+  }
+
   pub(crate) fn new_mouse_button_message(
     message: u32,
     w_param: WPARAM,
@@ -270,10 +466,91 @@ impl Message {
     matches!(self, Message::MouseButton { button: b, state: s, .. } if *b == button && *s == state)
   }
 
+  /// Messages that are sent often and only describe transient state, which
+  /// can be dropped while the main thread is stalled.
+  pub(crate) fn is_low_priority(&self) -> bool {
+    matches!(
+      self,
+      Message::Paint
+        | Message::CursorMove { .. }
+        | Message::RawInput(RawInputMessage::MouseMove { .. })
+        | Message::Command
+        | Message::SystemCommand
+    )
+  }
+
   /// Returns `true` if the message is [`LoopMessage::Empty`]
   pub fn is_empty(&self) -> bool {
     matches!(self, Message::Loop(LoopMessage::Empty))
   }
+
+  /// Returns `true` if the message is [`LoopMessage::Idle`]
+  pub fn is_idle(&self) -> bool {
+    matches!(self, Message::Loop(LoopMessage::Idle))
+  }
+
+  /// Returns `true` if the message comes from the keyboard, mouse, or a raw
+  /// input device
+  pub fn is_input(&self) -> bool {
+    matches!(
+      self,
+      Message::Key { .. }
+        | Message::Text(_)
+        | Message::ImeStarted
+        | Message::ImePreedit { .. }
+        | Message::ImeCommit(_)
+        | Message::ModifiersChanged { .. }
+        | Message::MouseButton { .. }
+        | Message::Touch(_)
+        | Message::MouseWheel { .. }
+        | Message::AppCommand(_)
+        | Message::CursorMove { .. }
+        | Message::RawInput(_)
+    )
+  }
+
+  /// Returns `true` if the message describes a change to the window itself,
+  /// such as its bounds, focus, or scale factor
+  pub fn is_window_event(&self) -> bool {
+    matches!(
+      self,
+      Message::Created { .. }
+        | Message::CloseRequested
+        | Message::DismissRequested
+        | Message::Resized(_)
+        | Message::Moved(_)
+        | Message::BoundsChanged { .. }
+        | Message::Focus(_)
+        | Message::ScaleFactorChanged(_)
+        | Message::SurfaceInvalidated(_)
+        | Message::ThemeChanged(_)
+        | Message::AccentColorChanged(_)
+        | Message::FilesDropped(_)
+        | Message::SecondInstanceLaunched(_)
+    )
+  }
+
+  /// Returns `true` if the message is a [`Message::Loop`]
+  pub fn is_loop(&self) -> bool {
+    matches!(self, Message::Loop(_))
+  }
+
+  /// Returns `true` if the message is [`Message::Paint`]
+  pub fn is_redraw(&self) -> bool {
+    matches!(self, Message::Paint)
+  }
+
+  /// Returns `true` for the messages that are sent constantly and usually
+  /// filtered out of logs: loop messages, paints, raw input, and cursor moves
+  pub fn is_noise(&self) -> bool {
+    matches!(
+      self,
+      Message::Loop(_)
+        | Message::Paint
+        | Message::RawInput(_)
+        | Message::CursorMove { .. }
+    )
+  }
 }
 
 /*
@@ -296,22 +573,255 @@ pub(crate) fn get_cursor_move_kind(
   x: i32,
   y: i32,
 ) -> CursorMoveKind {
-  let rect: RECT = {
+  let rect: PhysicalRect = {
     let mut rect = RECT::default();
     if unsafe { GetClientRect(hwnd, &mut rect) }.is_err() {
       return CursorMoveKind::Inside; // exit early if GetClientRect failed
     }
-    rect
+    rect.into()
   };
 
-  let x = (rect.left..rect.right).contains(&x);
-  let y = (rect.top..rect.bottom).contains(&y);
+  let inside = rect.contains(PhysicalPosition::new(x, y));
 
-  if !mouse_was_inside_window && x && y {
+  if !mouse_was_inside_window && inside {
     CursorMoveKind::Entered
-  } else if mouse_was_inside_window && !(x && y) {
+  } else if mouse_was_inside_window && !inside {
     CursorMoveKind::Left
   } else {
     CursorMoveKind::Inside
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// The key message for a `WM_KEYDOWN` or `WM_KEYUP` captured on a US
+  /// keyboard.
+  fn key_message(virtual_key: VIRTUAL_KEY, lparam: u32) -> Message {
+    Message::new_keyboard_message(WPARAM(virtual_key.0 as usize), LPARAM(lparam as isize))
+  }
+
+  fn key(key: Key, state: KeyState, scan_code: u16, is_extended_key: bool) -> Message {
+    Message::Key {
+      key,
+      state,
+      scan_code,
+      is_extended_key,
+    }
+  }
+
+  #[test]
+  fn print_screen_translation() {
+    // only the release is ever sent
+    assert_eq!(
+      key_message(KeyboardAndMouse::VK_SNAPSHOT, 0xC137_0001),
+      key(Key::PrintScreen, KeyState::Released, 0x37, true)
+    );
+  }
+
+  #[test]
+  fn pause_translation() {
+    // the scan code is NumLock's, after the dropped 0xE11D prefix
+    assert_eq!(
+      key_message(KeyboardAndMouse::VK_PAUSE, 0x0045_0001),
+      key(Key::Pause, KeyState::Pressed, 0x45, false)
+    );
+    assert_eq!(
+      key_message(KeyboardAndMouse::VK_PAUSE, 0xC045_0001),
+      key(Key::Pause, KeyState::Released, 0x45, false)
+    );
+  }
+
+  #[test]
+  fn scroll_lock_translation() {
+    assert_eq!(
+      key_message(KeyboardAndMouse::VK_SCROLL, 0x0046_0001),
+      key(Key::ScrollLock, KeyState::Pressed, 0x46, false)
+    );
+    assert_eq!(
+      key_message(KeyboardAndMouse::VK_SCROLL, 0x4046_0001),
+      key(Key::ScrollLock, KeyState::Held(1), 0x46, false)
+    );
+    assert_eq!(
+      key_message(KeyboardAndMouse::VK_SCROLL, 0xC046_0001),
+      key(Key::ScrollLock, KeyState::Released, 0x46, false)
+    );
+  }
+
+  #[test]
+  fn app_message_round_trip() {
+    for (code, wparam, lparam) in
+      [(0, 0, 0), (1, 42, -7), (0x3FFF, usize::MAX, isize::MIN)]
+    {
+      let msg = app_message_id(code).unwrap();
+      assert!((WindowsAndMessaging::WM_APP..=APP_MESSAGE_LAST).contains(&msg));
+      assert_eq!(
+        Message::new_app_message(msg, WPARAM(wparam), LPARAM(lparam)),
+        Message::App {
+          code,
+          wparam,
+          lparam
+        }
+      );
+    }
+  }
+
+  #[test]
+  fn app_message_codes_past_the_range_are_rejected() {
+    assert_eq!(app_message_id(0x4000), None);
+    assert_eq!(app_message_id(u16::MAX), None);
+  }
+
+  #[test]
+  fn commands_stay_out_of_the_app_range() {
+    let reserved = WindowsAndMessaging::WM_USER..WindowsAndMessaging::WM_APP;
+    assert!(reserved.contains(&Command::MESSAGE_ID));
+  }
+
+  /// Each message with the predicates it should match, in the order
+  /// `is_input`, `is_window_event`, `is_loop`, `is_redraw`, `is_noise`.
+  fn predicate_cases() -> Vec<(Message, [bool; 5])> {
+    vec![
+      (Message::Loop(LoopMessage::Empty), [false, false, true, false, true]),
+      (Message::Loop(LoopMessage::Idle), [false, false, true, false, true]),
+      (Message::Paint, [false, false, false, true, true]),
+      (
+        Message::CursorMove {
+          position: PhysicalPosition::new(1, 2),
+          kind: CursorMoveKind::Inside,
+        },
+        [true, false, false, false, true],
+      ),
+      (
+        Message::RawInput(RawInputMessage::MouseMove {
+          delta_x: 1.0,
+          delta_y: 0.0,
+        }),
+        [true, false, false, false, true],
+      ),
+      (
+        Message::Key {
+          key: Key::A,
+          state: KeyState::Pressed,
+          scan_code: 0x1E,
+          is_extended_key: false,
+        },
+        [true, false, false, false, false],
+      ),
+      (Message::Text("a".into()), [true, false, false, false, false]),
+      (
+        Message::MouseWheel {
+          delta_x: 0.0,
+          delta_y: 1.0,
+        },
+        [true, false, false, false, false],
+      ),
+      (Message::CloseRequested, [false, true, false, false, false]),
+      (Message::Resized(PhysicalSize::new(800, 600)), [
+        false, true, false, false, false,
+      ]),
+      (Message::Focus(Focus::Gained), [false, true, false, false, false]),
+      (Message::ScaleFactorChanged(1.5), [false, true, false, false, false]),
+      (Message::Command, [false, false, false, false, false]),
+      (Message::SystemCommand, [false, false, false, false, false]),
+      (
+        Message::App {
+          code: 0,
+          wparam: 0,
+          lparam: 0,
+        },
+        [false, false, false, false, false],
+      ),
+    ]
+  }
+
+  #[test]
+  fn message_predicates() {
+    for (message, expected) in predicate_cases() {
+      let actual = [
+        message.is_input(),
+        message.is_window_event(),
+        message.is_loop(),
+        message.is_redraw(),
+        message.is_noise(),
+      ];
+      assert_eq!(actual, expected, "{message:?}");
+    }
+  }
+
+  #[test]
+  fn input_and_window_events_are_disjoint() {
+    for (message, _) in predicate_cases() {
+      assert!(!(message.is_input() && message.is_window_event()), "{message:?}");
+    }
+  }
+
+  fn drop_cursor_moves(message: Message) -> Option<Message> {
+    match message {
+      Message::CursorMove { .. } => None,
+      Message::Focus(_) => Some(Message::Paint),
+      message => Some(message),
+    }
+  }
+
+  fn cursor_move(x: i32) -> Message {
+    Message::CursorMove {
+      position: PhysicalPosition::new(x, 0),
+      kind: CursorMoveKind::Inside,
+    }
+  }
+
+  #[test]
+  fn filter_drops_messages_before_the_loop() {
+    let messages = vec![
+      cursor_move(0),
+      Message::CloseRequested,
+      cursor_move(1),
+      cursor_move(2),
+      Message::Loop(LoopMessage::Empty),
+      cursor_move(3),
+    ];
+
+    let delivered: Vec<_> = messages
+      .into_iter()
+      .filter_map(|message| filter_message(message, Some(drop_cursor_moves)))
+      .collect();
+
+    assert_eq!(delivered.len(), 2);
+    assert!(!delivered
+      .iter()
+      .any(|message| matches!(message, Message::CursorMove { .. })));
+    assert!(matches!(delivered[0], Message::CloseRequested));
+    assert!(matches!(delivered[1], Message::Loop(LoopMessage::Empty)));
+  }
+
+  #[test]
+  fn filter_remaps_messages() {
+    assert!(matches!(
+      filter_message(Message::Focus(Focus::Lost), Some(drop_cursor_moves)),
+      Some(Message::Paint)
+    ));
+  }
+
+  #[test]
+  fn loop_messages_bypass_the_filter() {
+    fn drop_everything(_: Message) -> Option<Message> {
+      None
+    }
+
+    assert!(filter_message(Message::Paint, Some(drop_everything)).is_none());
+    assert!(matches!(
+      filter_message(Message::Loop(LoopMessage::Idle), Some(drop_everything)),
+      Some(Message::Loop(LoopMessage::Idle))
+    ));
+  }
+
+  #[test]
+  fn no_filter_keeps_every_message() {
+    assert!(matches!(
+      filter_message(cursor_move(0), None),
+      Some(Message::CursorMove { .. })
+    ));
+  }
+}
@@ -0,0 +1,207 @@
+use std::{fmt, str::FromStr, sync::Arc};
+
+use super::data::{PhysicalPosition, PhysicalRect, PhysicalSize};
+use crate::error::WindowError;
+
+/// Where a window sits on screen, as saved by
+/// [`WindowBuilder::with_state_persistence`](crate::WindowBuilder::with_state_persistence).
+///
+/// Converts to and from a short string with [`ToString`] and [`str::parse`],
+/// so it can be stored anywhere that takes text.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct WindowPlacement {
+  /// The outer bounds the window restores to, in screen coordinates. See
+  /// [`Window::normal_rect`](crate::Window::normal_rect).
+  pub normal_rect: PhysicalRect,
+  pub maximized: bool,
+}
+
+impl fmt::Display for WindowPlacement {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let PhysicalRect { position, size } = self.normal_rect;
+    write!(
+      f,
+      "{},{},{},{},{}",
+      position.x,
+      position.y,
+      size.width,
+      size.height,
+      if self.maximized {
+        "maximized"
+      } else {
+        "normal"
+      }
+    )
+  }
+}
+
+impl FromStr for WindowPlacement {
+  type Err = WindowError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let invalid = || WindowError::Error(format!("invalid window placement `{s}`"));
+
+    let mut fields = s.trim().split(',');
+    let mut next = || fields.next().map(str::trim).ok_or_else(invalid);
+    let x = next()?.parse().map_err(|_| invalid())?;
+    let y = next()?.parse().map_err(|_| invalid())?;
+    let width = next()?.parse().map_err(|_| invalid())?;
+    let height = next()?.parse().map_err(|_| invalid())?;
+    let maximized = match next()? {
+      "maximized" => true,
+      "normal" => false,
+      _ => return Err(invalid()),
+    };
+    if fields.next().is_some() {
+      return Err(invalid());
+    }
+
+    Ok(Self {
+      normal_rect: PhysicalRect::new(
+        PhysicalPosition::new(x, y),
+        PhysicalSize::new(width, height),
+      ),
+      maximized,
+    })
+  }
+}
+
+type Load = dyn Fn(&str) -> Option<String> + Send + Sync;
+type Save = dyn Fn(&str, &str) + Send + Sync;
+
+/// The key and storage callbacks given to
+/// [`WindowBuilder::with_state_persistence`](crate::WindowBuilder::with_state_persistence).
+#[derive(Clone)]
+pub(crate) struct StatePersistence {
+  key: String,
+  load: Arc<Load>,
+  save: Arc<Save>,
+}
+
+impl fmt::Debug for StatePersistence {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("StatePersistence")
+      .field("key", &self.key)
+      .finish_non_exhaustive()
+  }
+}
+
+impl StatePersistence {
+  pub fn new(
+    key: &str,
+    load: impl Fn(&str) -> Option<String> + Send + Sync + 'static,
+    save: impl Fn(&str, &str) + Send + Sync + 'static,
+  ) -> Self {
+    Self {
+      key: key.to_owned(),
+      load: Arc::new(load),
+      save: Arc::new(save),
+    }
+  }
+
+  /// The saved placement, if there is one and it still parses.
+  pub fn load(&self) -> Option<WindowPlacement> {
+    let saved = (self.load)(&self.key)?;
+    match saved.parse() {
+      Ok(placement) => Some(placement),
+      Err(error) => {
+        tracing::warn!("ignoring saved placement for `{}`: {error}", self.key);
+        None
+      }
+    }
+  }
+
+  pub fn save(&self, placement: WindowPlacement) {
+    (self.save)(&self.key, &placement.to_string())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn placement(
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    maximized: bool,
+  ) -> WindowPlacement {
+    WindowPlacement {
+      normal_rect: PhysicalRect::new(
+        PhysicalPosition::new(x, y),
+        PhysicalSize::new(width, height),
+      ),
+      maximized,
+    }
+  }
+
+  #[test]
+  fn round_trip() {
+    for placement in [
+      placement(100, 200, 800, 600, false),
+      placement(0, 0, 1, 1, true),
+      placement(i32::MAX, i32::MIN, u32::MAX, 0, false),
+    ] {
+      assert_eq!(placement.to_string().parse::<WindowPlacement>().unwrap(), placement);
+    }
+  }
+
+  #[test]
+  fn format() {
+    assert_eq!(placement(1, 2, 3, 4, false).to_string(), "1,2,3,4,normal");
+    assert_eq!(placement(1, 2, 3, 4, true).to_string(), "1,2,3,4,maximized");
+  }
+
+  #[test]
+  fn negative_coordinates() {
+    // monitors left of or above the primary one have negative coordinates
+    assert_eq!(
+      "-1920,-40,800,600,normal"
+        .parse::<WindowPlacement>()
+        .unwrap(),
+      placement(-1920, -40, 800, 600, false)
+    );
+    // sizes can't be negative
+    assert!("0,0,-800,600,normal".parse::<WindowPlacement>().is_err());
+  }
+
+  #[test]
+  fn surrounding_whitespace() {
+    assert_eq!(
+      "  10 , 20,\t300 ,400 , maximized\n"
+        .parse::<WindowPlacement>()
+        .unwrap(),
+      placement(10, 20, 300, 400, true)
+    );
+  }
+
+  #[test]
+  fn extra_fields() {
+    assert!("1,2,3,4,normal,5".parse::<WindowPlacement>().is_err());
+    assert!("1,2,3,4,normal,".parse::<WindowPlacement>().is_err());
+  }
+
+  #[test]
+  fn missing_fields() {
+    assert!("".parse::<WindowPlacement>().is_err());
+    assert!("1,2,3,4".parse::<WindowPlacement>().is_err());
+    assert!("1,2,,4,normal".parse::<WindowPlacement>().is_err());
+  }
+
+  #[test]
+  fn bad_state_word() {
+    assert!("1,2,3,4,minimized".parse::<WindowPlacement>().is_err());
+    assert!("1,2,3,4,Normal".parse::<WindowPlacement>().is_err());
+    assert!("1,2,3,4,true".parse::<WindowPlacement>().is_err());
+  }
+
+  #[test]
+  fn non_numeric_fields() {
+    assert!("a,2,3,4,normal".parse::<WindowPlacement>().is_err());
+    assert!("1.5,2,3,4,normal".parse::<WindowPlacement>().is_err());
+    assert!("1,2,3,99999999999,normal"
+      .parse::<WindowPlacement>()
+      .is_err());
+  }
+}
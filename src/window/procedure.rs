@@ -1,17 +1,29 @@
-use std::sync::{Arc, Mutex};
+use std::{
+  cell::Cell,
+  collections::VecDeque,
+  sync::{atomic::AtomicUsize, Arc, Mutex},
+};
 
 use cursor_icon::CursorIcon;
 // use crossbeam::channel::{Receiver, Sender};
 use windows::Win32::{
   Foundation::*,
+  System::RemoteDesktop::{
+    self,
+    WTSRegisterSessionNotification,
+    WTSUnRegisterSessionNotification,
+  },
   UI::{
     HiDpi::EnableNonClientDpiScaling,
+    Input::Touch::{RegisterTouchWindow, REGISTER_TOUCH_WINDOW_FLAGS},
     WindowsAndMessaging::{
       self,
       DefWindowProcW,
       DestroyWindow,
       GetWindowLongPtrW,
       PostQuitMessage,
+      RemovePropW,
+      SetPropW,
       SetWindowLongPtrW,
       CREATESTRUCTW,
     },
@@ -22,21 +34,28 @@ use windows::Win32::{
 use super::message::Message;
 use super::{
   command::Command,
-  data::{Data, Position, Size, SyncData, Visibility},
+  data::{Data, DpiAwareness, Position, Size, SyncData, Visibility, WindowButtons},
   frame::Style,
+  persistence::StatePersistence,
   settings::WindowSettings,
   Window,
 };
 use crate::{
-  prelude::Input,
+  single_instance::INSTANCE_PROPERTY,
   utilities::{
     dpi_to_scale_factor,
     hwnd_dpi,
+    hwnd_dpi_awareness,
+    is_cursor_over_client_area,
+    is_rect_on_screen,
     register_all_mice_and_keyboards_for_raw_input,
+    system_accent_colors,
+    track_mouse_leave,
   },
   window::{
     cursor::Cursor,
     data::{Internal, PhysicalPosition},
+    input::InputBuffers,
     stage::Stage,
   },
 };
@@ -46,6 +65,7 @@ pub struct CreateInfo {
   pub size: Size,
   pub position: Option<Position>,
   pub settings: WindowSettings,
+  pub class_name: String,
   pub class_atom: u16,
   pub window: Option<Window>,
   pub sync: SyncData,
@@ -56,6 +76,12 @@ pub struct UserData {
   state: Arc<Internal>,
 }
 
+thread_local! {
+  /// Whether the message pump of the current thread belongs to `witer`, in
+  /// which case destroying the window also ends the pump.
+  pub(crate) static OWNS_MESSAGE_PUMP: Cell<bool> = const { Cell::new(false) };
+}
+
 ////////////////////////
 /// WINDOW PROCEDURE ///
 ////////////////////////
@@ -72,53 +98,86 @@ pub extern "system" fn wnd_proc(
   match (user_data_ptr, msg) {
     (0, WindowsAndMessaging::WM_NCCREATE) => on_nccreate(hwnd, msg, wparam, lparam),
     (0, WindowsAndMessaging::WM_CREATE) => on_create(hwnd, msg, wparam, lparam),
-    (0, _) => unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
-    (state_ptr, message) => {
-      let result = match message {
-        Command::MESSAGE_ID => {
-          let command = unsafe { (wparam.0 as *mut Command).as_mut() }.unwrap();
-          match command {
-            Command::Exit => {
-              let user_data = unsafe { Box::from_raw(state_ptr as *mut UserData) };
-              drop(user_data);
-              LRESULT(0)
-            }
-            Command::Destroy => {
-              unsafe { DestroyWindow(hwnd) }.unwrap();
-              LRESULT(0)
-            }
-            _ => {
-              if let Some(user_data) = unsafe { (state_ptr as *mut UserData).as_mut() } {
-                user_data.state.on_message(hwnd, msg, wparam, lparam)
-              } else {
-                unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
-              }
-            }
+    (state_ptr, Command::MESSAGE_ID) => {
+      let command = unsafe { (wparam.0 as *mut Command).as_mut() }.unwrap();
+      match command {
+        Command::Exit => {
+          if state_ptr != 0 {
+            // clear the pointer first so no message can reach freed state
+            unsafe { SetWindowLongPtrW(hwnd, WindowsAndMessaging::GWLP_USERDATA, 0) };
+            let user_data = unsafe { Box::from_raw(state_ptr as *mut UserData) };
+            // WM_DESTROY won't reach the state anymore
+            user_data.state.leave_exclusive_mode();
+            drop(user_data);
           }
+          LRESULT(0)
         }
-        WindowsAndMessaging::WM_DESTROY => {
-          unsafe { PostQuitMessage(0) };
-          unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+        Command::Destroy => {
+          // owned windows are destroyed by the system along with their owner
+          if let Err(error) = unsafe { DestroyWindow(hwnd) } {
+            tracing::warn!("{error}");
+          }
+          LRESULT(0)
         }
         _ => {
           if let Some(user_data) = unsafe { (state_ptr as *mut UserData).as_mut() } {
             user_data.state.on_message(hwnd, msg, wparam, lparam)
           } else {
-            unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+            drop(unsafe { Command::from_wparam(wparam) });
+            LRESULT(0)
           }
         }
-      };
-      result
+      }
+    }
+    (state_ptr, WindowsAndMessaging::WM_DESTROY) => {
+      let _ = unsafe { WTSUnRegisterSessionNotification(hwnd) };
+      let _ = unsafe { RemovePropW(hwnd, INSTANCE_PROPERTY) };
+      if let Some(user_data) = unsafe { (state_ptr as *mut UserData).as_ref() } {
+        user_data.state.leave_exclusive_mode();
+      }
+      if OWNS_MESSAGE_PUMP.get() {
+        unsafe { PostQuitMessage(0) };
+      }
+      unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+    }
+    (0, _) => unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+    (state_ptr, _) => {
+      if let Some(user_data) = unsafe { (state_ptr as *mut UserData).as_mut() } {
+        user_data.state.on_message(hwnd, msg, wparam, lparam)
+      } else {
+        unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+      }
     }
   }
 }
 
 fn on_nccreate(hwnd: HWND, msg: u32, w_param: WPARAM, l_param: LPARAM) -> LRESULT {
-  if let Err(e) = unsafe { EnableNonClientDpiScaling(hwnd) } {
+  let create_struct = unsafe { (l_param.0 as *mut CREATESTRUCTW).as_ref().unwrap() };
+  let create_info = unsafe {
+    (create_struct.lpCreateParams as *const CreateInfo)
+      .as_ref()
+      .unwrap()
+  };
+
+  // only per-monitor aware windows can have their non-client area scaled
+  let per_monitor_aware = matches!(
+    hwnd_dpi_awareness(hwnd),
+    DpiAwareness::PerMonitor | DpiAwareness::PerMonitorV2
+  );
+  if create_info.settings.dpi_scaling && per_monitor_aware {
+    if let Err(e) = unsafe { EnableNonClientDpiScaling(hwnd) } {
+      tracing::error!("{e}");
+    }
+  }
+
+  if let Err(e) = register_all_mice_and_keyboards_for_raw_input(hwnd) {
     tracing::error!("{e}");
   }
 
-  register_all_mice_and_keyboards_for_raw_input(hwnd);
+  // fingers arrive as WM_TOUCH instead of gestures
+  if let Err(e) = unsafe { RegisterTouchWindow(hwnd, REGISTER_TOUCH_WINDOW_FLAGS(0)) } {
+    tracing::error!("{e}");
+  }
 
   unsafe { DefWindowProcW(hwnd, msg, w_param, l_param) }
 }
@@ -131,7 +190,11 @@ fn on_create(hwnd: HWND, msg: u32, w_param: WPARAM, l_param: LPARAM) -> LRESULT
       .unwrap()
   };
 
-  let scale_factor = dpi_to_scale_factor(hwnd_dpi(hwnd));
+  let scale_factor = if create_info.settings.dpi_scaling {
+    dpi_to_scale_factor(hwnd_dpi(hwnd))
+  } else {
+    1.0
+  };
   let size = create_info.size;
   let position = create_info.position.unwrap_or(
     PhysicalPosition::new(
@@ -142,17 +205,22 @@ fn on_create(hwnd: HWND, msg: u32, w_param: WPARAM, l_param: LPARAM) -> LRESULT
   );
 
   // create state
-  let input = Input::new();
   let state = Arc::new(Internal {
     hinstance: create_struct.hInstance,
     hwnd,
+    class_name: create_info.class_name.clone(),
     class_atom: create_info.class_atom,
     sync: create_info.sync.clone(),
     thread: Mutex::new(None),
+    handles: AtomicUsize::new(0),
     data: Mutex::new(Data {
-      title: create_info.title.clone(),
+      title: create_info.title.clone().into(),
       subtitle: Default::default(),
+      title_separator: " — ".into(),
       theme: Default::default(),
+      effective_theme: Default::default(),
+      accent_colors: system_accent_colors(),
+      manage_titlebar_theme: create_info.settings.manage_titlebar_theme,
       style: create_info.style.clone(),
       scale_factor,
       last_windowed_position: position,
@@ -164,14 +232,55 @@ fn on_create(hwnd: HWND, msg: u32, w_param: WPARAM, l_param: LPARAM) -> LRESULT
         last_position: PhysicalPosition::default(),
         selected_icon: CursorIcon::Default,
       },
+      cursor_snapshot: None,
       flow: create_info.settings.flow,
       close_on_x: create_info.settings.close_on_x,
+      alt_f4: create_info.settings.alt_f4,
+      dpi_scaling: create_info.settings.dpi_scaling,
+      minimized_poll_interval: create_info.settings.minimized_poll_interval,
+      raw_text: create_info.settings.raw_text,
+      initial_state_messages: create_info.settings.initial_state_messages,
+      initial_messages: VecDeque::new(),
       stage: Stage::Setup,
-      input,
+      exit_reason: None,
+      close_pending: false,
+      procedure_released: false,
+      input: InputBuffers::default(),
+      #[cfg(feature = "diagnostics")]
+      message_history: VecDeque::with_capacity(super::diagnostics::MESSAGE_HISTORY_LEN),
       requested_redraw: false,
+      idle: false,
+      continuous_redraw: false,
+      game_mode_hint: false,
+      reveal_on_present: create_info.settings.reveal_after_first_present,
+      flashing: false,
+      exclusive_display: None,
+      click_activation: create_info.settings.click_activation,
+      auto_dismiss: create_info.settings.auto_dismiss,
+      dismiss_capture: false,
+      dismiss_attached_thread: None,
+      eaten_click: None,
+      resize_border: None,
+      min_inner_size: create_info.settings.min_inner_size,
+      max_inner_size: create_info.settings.max_inner_size,
+      dwm_margins: None,
+      message_filter: None,
+      subscriptions: Default::default(),
+      window_region: None,
+      window_icon: create_info.settings.window_icon.clone(),
+      window_hicon: None,
+      state_persistence: create_info.settings.state_persistence.clone(),
+      monitor: None,
     }),
   });
 
+  // for session lock notifications
+  if let Err(error) = unsafe {
+    WTSRegisterSessionNotification(hwnd, RemoteDesktop::NOTIFY_FOR_THIS_SESSION)
+  } {
+    tracing::warn!("{error}");
+  }
+
   // create data ptr
   let user_data = UserData {
     state: state.clone(),
@@ -183,16 +292,89 @@ fn on_create(hwnd: HWND, msg: u32, w_param: WPARAM, l_param: LPARAM) -> LRESULT
 
   tracing::trace!("[`{}`]: finalizing window settings", create_info.title);
 
-  let window = Window(state.clone());
+  let window = Window::new_handle(state.clone());
   window.force_set_theme(create_info.settings.theme);
 
   if let Some(position) = create_info.position {
     Command::SetPosition(position).send(hwnd);
+    // the window is still hidden, so moving it to a monitor with another DPI
+    // doesn't reliably send WM_DPICHANGED. Pick up the new scale factor before
+    // a logical size is converted with the old one.
+    if create_info.settings.dpi_scaling {
+      window.0.data.lock().unwrap().scale_factor = dpi_to_scale_factor(hwnd_dpi(hwnd));
+    }
   }
   Command::SetSize(size).send(hwnd);
   Command::SetDecorations(create_info.settings.decorations).send(hwnd);
+  if create_info.settings.borderless_shadow {
+    Command::SetDwmMargins(None).send(hwnd);
+  }
+  if create_info.settings.enabled_buttons != WindowButtons::ALL {
+    Command::SetEnabledButtons(create_info.settings.enabled_buttons).send(hwnd);
+  }
+  if create_info.settings.app_user_model_id.is_some() {
+    Command::SetAppUserModelId(create_info.settings.app_user_model_id.clone()).send(hwnd);
+  }
+  if create_info.settings.window_icon.is_some() {
+    Command::SetWindowIcon(create_info.settings.window_icon.clone()).send(hwnd);
+  }
+  if create_info.settings.transparency_color_key.is_some() {
+    Command::SetTransparencyColorKey(create_info.settings.transparency_color_key)
+      .send(hwnd);
+  }
+  if let Some(message_id) = create_info.settings.single_instance {
+    // later launches find the window by this property
+    if let Err(error) =
+      unsafe { SetPropW(hwnd, INSTANCE_PROPERTY, HANDLE(message_id as isize)) }
+    {
+      tracing::error!("{error}");
+    }
+  }
+  // a saved placement wins over the size and position given to the builder
+  let placement = create_info
+    .settings
+    .state_persistence
+    .as_ref()
+    .and_then(StatePersistence::load)
+    .filter(|placement| {
+      let on_screen = is_rect_on_screen(placement.normal_rect);
+      if !on_screen {
+        tracing::debug!("[`{}`]: saved placement is off screen", create_info.title);
+      }
+      on_screen
+    });
+  if let Some(placement) = placement {
+    Command::SetNormalRect(placement.normal_rect).send(hwnd);
+  }
   Command::SetVisibility(create_info.settings.visibility).send(hwnd);
-  Command::SetFullscreen(create_info.settings.fullscreen).send(hwnd);
+  // maximizing shows the window
+  if placement.is_some_and(|placement| placement.maximized)
+    && create_info.settings.visibility == Visibility::Shown
+    && create_info.settings.fullscreen.is_none()
+  {
+    window.0.data.lock().unwrap().style.maximized = true;
+    Command::SetMaximized(true).send(hwnd);
+  }
+  Command::SetFullscreen {
+    fullscreen: create_info.settings.fullscreen,
+    apply_cursor: false,
+  }
+  .send(hwnd);
+  if let Some(mode) = create_info.settings.attention_on_start {
+    Command::Flash(mode).send(hwnd);
+  }
+
+  // WM_MOUSEMOVE only arrives once the cursor moves, so a window created under
+  // a resting cursor has to check for itself
+  if is_cursor_over_client_area(hwnd) {
+    let position = window.cursor_client_position();
+    {
+      let mut data = window.0.data.lock().unwrap();
+      data.cursor.inside_window = true;
+      data.cursor.last_position = position;
+    }
+    track_mouse_leave(hwnd);
+  }
 
   window.0.data.lock().unwrap().stage = Stage::Ready;
 
@@ -0,0 +1,138 @@
+use windows::Win32::Graphics::Gdi::{
+  self,
+  CombineRgn,
+  CreateEllipticRgn,
+  CreateRectRgn,
+  CreateRoundRectRgn,
+  DeleteObject,
+  HRGN,
+};
+
+use super::data::{PhysicalPosition, PhysicalSize, Position, Size};
+
+/// A shape to clip the window to, e.g. for a non-rectangular splash screen.
+/// See [`Window::set_window_region`](crate::Window::set_window_region).
+///
+/// Coordinates are relative to the top-left corner of the window frame, not
+/// the client area. Shapes given in logical units are rebuilt whenever the
+/// scale factor of the window changes, so they keep covering the same content.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Region {
+  /// A rectangle whose corners are rounded by ellipses of size `corner`.
+  RoundedRect {
+    position: Position,
+    size: Size,
+    corner: Size,
+  },
+  /// The ellipse that fits the rectangle.
+  Ellipse { position: Position, size: Size },
+  /// The union of the rectangles. No rectangles hides the whole window.
+  Rects(Vec<(Position, Size)>),
+}
+
+impl Region {
+  pub fn rounded_rect(
+    position: impl Into<Position>,
+    size: impl Into<Size>,
+    corner: impl Into<Size>,
+  ) -> Self {
+    Self::RoundedRect {
+      position: position.into(),
+      size: size.into(),
+      corner: corner.into(),
+    }
+  }
+
+  pub fn ellipse(position: impl Into<Position>, size: impl Into<Size>) -> Self {
+    Self::Ellipse {
+      position: position.into(),
+      size: size.into(),
+    }
+  }
+
+  pub fn rects<P: Into<Position>, S: Into<Size>>(
+    rects: impl IntoIterator<Item = (P, S)>,
+  ) -> Self {
+    Self::Rects(
+      rects
+        .into_iter()
+        .map(|(position, size)| (position.into(), size.into()))
+        .collect(),
+    )
+  }
+
+  /// Whether the region has to be rebuilt when the scale factor changes.
+  pub(crate) fn is_logical(&self) -> bool {
+    let is_logical = |position: &Position, size: &Size| -> bool {
+      matches!(position, Position::Logical(_)) || matches!(size, Size::Logical(_))
+    };
+
+    match self {
+      Self::RoundedRect {
+        position,
+        size,
+        corner,
+      } => is_logical(position, size) || matches!(corner, Size::Logical(_)),
+      Self::Ellipse { position, size } => is_logical(position, size),
+      Self::Rects(rects) => rects
+        .iter()
+        .any(|(position, size)| is_logical(position, size)),
+    }
+  }
+
+  /// Creates the GDI region in physical pixels, owned by the caller. Returns
+  /// [`None`] if the system is out of GDI resources.
+  pub(crate) fn create(&self, scale_factor: f64) -> Option<HRGN> {
+    let bounds = |position: &Position, size: &Size| {
+      let PhysicalPosition { x, y } = position.as_physical(scale_factor);
+      let PhysicalSize { width, height } = size.as_physical(scale_factor);
+      (x, y, x + width as i32, y + height as i32)
+    };
+
+    let hrgn = match self {
+      Self::RoundedRect {
+        position,
+        size,
+        corner,
+      } => {
+        let (left, top, right, bottom) = bounds(position, size);
+        let corner = corner.as_physical(scale_factor);
+        unsafe {
+          CreateRoundRectRgn(
+            left,
+            top,
+            right,
+            bottom,
+            corner.width as i32,
+            corner.height as i32,
+          )
+        }
+      }
+      Self::Ellipse { position, size } => {
+        let (left, top, right, bottom) = bounds(position, size);
+        unsafe { CreateEllipticRgn(left, top, right, bottom) }
+      }
+      Self::Rects(rects) => {
+        let hrgn = unsafe { CreateRectRgn(0, 0, 0, 0) };
+        if hrgn.is_invalid() {
+          return None;
+        }
+        for (position, size) in rects {
+          let (left, top, right, bottom) = bounds(position, size);
+          let rect = unsafe { CreateRectRgn(left, top, right, bottom) };
+          if rect.is_invalid() {
+            unsafe { DeleteObject(hrgn) };
+            return None;
+          }
+          unsafe {
+            CombineRgn(hrgn, hrgn, rect, Gdi::RGN_OR);
+            DeleteObject(rect);
+          }
+        }
+        hrgn
+      }
+    };
+
+    (!hrgn.is_invalid()).then_some(hrgn)
+  }
+}
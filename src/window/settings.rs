@@ -1,42 +1,146 @@
+use std::time::Duration;
+
+use windows::Win32::Foundation::HWND;
+
 use super::{
-  data::{CursorMode, Flow, Fullscreen, LogicalSize, Position, Size, Theme, Visibility},
+  data::{
+    ClickActivation,
+    Color,
+    CursorMode,
+    DpiAwareness,
+    FlashMode,
+    Flow,
+    Fullscreen,
+    LogicalSize,
+    Position,
+    Size,
+    StallPolicy,
+    Theme,
+    Visibility,
+    WindowButtons,
+  },
+  icon::Icon,
+  persistence::StatePersistence,
   Window,
 };
-use crate::error::WindowError;
+use crate::{error::WindowError, single_instance::SingleInstance};
 
 /// Optional onfiguration for the window to be built.
 #[derive(Debug, Clone)]
 pub struct WindowSettings {
   pub flow: Flow,
   pub theme: Theme,
+  pub manage_titlebar_theme: bool,
   pub visibility: Visibility,
   pub decorations: Visibility,
+  pub borderless_shadow: bool,
   pub resizeable: bool,
+  pub min_inner_size: Option<Size>,
+  pub max_inner_size: Option<Size>,
+  pub focusable: bool,
+  pub popup: bool,
+  pub auto_dismiss: bool,
+  pub click_activation: ClickActivation,
+  pub no_redirection_bitmap: bool,
+  pub transparency_color_key: Option<Color>,
   pub fullscreen: Option<Fullscreen>,
   pub cursor_mode: CursorMode,
   pub close_on_x: bool,
+  pub alt_f4: bool,
+  pub enabled_buttons: WindowButtons,
+  pub raw_text: bool,
+  pub initial_state_messages: bool,
+  pub app_user_model_id: Option<String>,
+  pub window_icon: Option<Icon>,
+  pub attention_on_start: Option<FlashMode>,
+  pub message_buffer: usize,
+  pub reveal_after_first_present: bool,
+  pub stall_timeout: Option<Duration>,
+  pub stall_policy: StallPolicy,
+  pub dpi_scaling: bool,
+  pub dpi_awareness: DpiAwareness,
+  pub minimized_poll_interval: Duration,
+  pub(crate) owner: Option<HWND>,
+  pub(crate) state_persistence: Option<StatePersistence>,
+  /// The registered message of a [`SingleInstance`], if the window receives
+  /// later launches.
+  pub(crate) single_instance: Option<u32>,
 }
 
 impl Default for WindowSettings {
   fn default() -> Self {
     let flow = Flow::default();
     let theme = Theme::default();
+    let manage_titlebar_theme = true;
     let fullscreen = None;
     let cursor_mode = CursorMode::default();
     let visibility = Visibility::default();
     let decorations = Visibility::default();
+    let borderless_shadow = false;
     let resizeable = true;
+    let min_inner_size = None;
+    let max_inner_size = None;
+    let focusable = true;
+    let popup = false;
+    let auto_dismiss = false;
+    let click_activation = ClickActivation::default();
+    let no_redirection_bitmap = false;
+    let transparency_color_key = None;
     let close_on_x = true;
+    let alt_f4 = true;
+    let enabled_buttons = WindowButtons::default();
+    let raw_text = false;
+    let initial_state_messages = false;
+    let app_user_model_id = None;
+    let window_icon = None;
+    let attention_on_start = None;
+    let message_buffer = 0;
+    let reveal_after_first_present = false;
+    let stall_timeout = Some(Duration::from_secs(10));
+    let stall_policy = StallPolicy::default();
+    let dpi_scaling = true;
+    let dpi_awareness = DpiAwareness::default();
+    let minimized_poll_interval = Duration::from_millis(50);
+    let owner = None;
+    let state_persistence = None;
+    let single_instance = None;
 
     Self {
       flow,
       theme,
+      manage_titlebar_theme,
       visibility,
       decorations,
+      borderless_shadow,
       close_on_x,
+      alt_f4,
+      enabled_buttons,
       fullscreen,
       resizeable,
+      min_inner_size,
+      max_inner_size,
+      focusable,
+      popup,
+      auto_dismiss,
+      click_activation,
+      no_redirection_bitmap,
+      transparency_color_key,
       cursor_mode,
+      raw_text,
+      initial_state_messages,
+      app_user_model_id,
+      window_icon,
+      attention_on_start,
+      message_buffer,
+      reveal_after_first_present,
+      stall_timeout,
+      stall_policy,
+      dpi_scaling,
+      dpi_awareness,
+      minimized_poll_interval,
+      owner,
+      state_persistence,
+      single_instance,
     }
   }
 }
@@ -52,6 +156,13 @@ impl WindowSettings {
     self
   }
 
+  /// Whether witer sets the dark mode title bar attribute to match the theme.
+  /// Disable this for custom frames that draw their own caption.
+  pub fn with_manage_titlebar_theme(mut self, manage: bool) -> Self {
+    self.manage_titlebar_theme = manage;
+    self
+  }
+
   pub fn with_visibility(mut self, visibility: Visibility) -> Self {
     self.visibility = visibility;
     self
@@ -62,6 +173,20 @@ impl WindowSettings {
     self
   }
 
+  /// Draw the client area over the whole window, frame included, while
+  /// keeping the system drop shadow, Aero Snap, and the minimize and maximize
+  /// animations. Hidden decorations lose all of those, since the frame styles
+  /// they depend on are removed. Keep decorations shown with this.
+  ///
+  /// The frame no longer takes part in hit testing, so the window can't be
+  /// resized from its edges or dragged by a caption on its own. Use
+  /// [`Window::set_resize_border`] for the edges, which works within the
+  /// client area.
+  pub fn with_borderless_shadow(mut self, borderless_shadow: bool) -> Self {
+    self.borderless_shadow = borderless_shadow;
+    self
+  }
+
   pub fn with_fullscreen(mut self, fullscreen: Option<Fullscreen>) -> Self {
     self.fullscreen = fullscreen;
 
@@ -73,22 +198,257 @@ impl WindowSettings {
     self
   }
 
+  /// Whether [`Message::CloseRequested`](crate::Message::CloseRequested)
+  /// closes the window right away. When disabled, the request stays pending
+  /// until the app answers it with
+  /// [`Window::confirm_close`](crate::Window::confirm_close) or
+  /// [`Window::cancel_close`](crate::Window::cancel_close), e.g. after asking
+  /// to save unsaved changes.
   pub fn with_close_on_x(mut self, close_on_x: bool) -> Self {
     self.close_on_x = close_on_x;
     self
   }
 
+  /// Whether Alt+F4 requests the window be closed. Disable this together with
+  /// the close button and `close_on_x` to block every close path, e.g. for
+  /// kiosk apps.
+  pub fn with_alt_f4(mut self, alt_f4: bool) -> Self {
+    self.alt_f4 = alt_f4;
+    self
+  }
+
+  /// See [`Window::set_enabled_buttons`].
+  pub fn with_enabled_buttons(mut self, buttons: WindowButtons) -> Self {
+    self.enabled_buttons = buttons;
+    self
+  }
+
   pub fn with_resizeable(mut self, resizeable: bool) -> Self {
     self.resizeable = resizeable;
     self
   }
+
+  /// The smallest inner size the user can resize the window to. See
+  /// [`Window::set_min_inner_size`].
+  pub fn with_min_inner_size(mut self, size: impl Into<Size>) -> Self {
+    self.min_inner_size = Some(size.into());
+    self
+  }
+
+  /// The largest inner size the user can resize the window to. See
+  /// [`Window::set_max_inner_size`].
+  pub fn with_max_inner_size(mut self, size: impl Into<Size>) -> Self {
+    self.max_inner_size = Some(size.into());
+    self
+  }
+
+  /// Whether the window can be activated. Non-focusable windows still receive
+  /// mouse input, but never take focus or the foreground from other windows.
+  pub fn with_focusable(mut self, focusable: bool) -> Self {
+    self.focusable = focusable;
+    self
+  }
+
+  /// Make the window a borderless popup, such as a tooltip or dropdown menu,
+  /// which isn't shown on the taskbar. Popups aren't focusable, so showing
+  /// one doesn't take the focus from its owner; call
+  /// [`WindowSettings::with_focusable`] afterwards to change that. Usually
+  /// combined with [`WindowSettings::with_owner`] and
+  /// [`WindowSettings::with_auto_dismiss`], and shown with
+  /// [`Window::show_popup_at`].
+  pub fn with_popup(mut self, popup: bool) -> Self {
+    self.popup = popup;
+    if popup {
+      self.focusable = false;
+    }
+    self
+  }
+
+  /// Send [`Message::DismissRequested`](crate::Message::DismissRequested)
+  /// when the user clicks outside of the shown popup or switches to another
+  /// app, so it can be hidden or dropped like a menu. While shown, the popup
+  /// captures the mouse to notice the click. Only applies to popups.
+  pub fn with_auto_dismiss(mut self, auto_dismiss: bool) -> Self {
+    self.auto_dismiss = auto_dismiss;
+    self
+  }
+
+  /// What happens when the user clicks the window while it is inactive.
+  /// Ignored for windows that aren't focusable.
+  pub fn with_click_activation(mut self, click_activation: ClickActivation) -> Self {
+    self.click_activation = click_activation;
+    self
+  }
+
+  /// Create the window with `WS_EX_NOREDIRECTIONBITMAP`, so no GDI redirection
+  /// surface is allocated for it. Meant for windows presented exclusively
+  /// through DirectComposition or a flip-model swapchain (e.g. wgpu's DX12
+  /// backend), where it saves memory and a copy per frame.
+  ///
+  /// GDI drawing, `PrintWindow` capture, and background erasing won't show
+  /// anything for such windows. This can only be chosen at creation.
+  pub fn with_no_redirection_bitmap(mut self, no_redirection_bitmap: bool) -> Self {
+    self.no_redirection_bitmap = no_redirection_bitmap;
+    self
+  }
+
+  /// Deliver control characters (backspace, escape, …) through
+  /// [`Message::Text`](crate::Message::Text) instead of only printable text.
+  pub fn with_raw_text(mut self, raw_text: bool) -> Self {
+    self.raw_text = raw_text;
+    self
+  }
+
+  /// Start the message stream with
+  /// [`Message::ScaleFactorChanged`](crate::Message::ScaleFactorChanged),
+  /// [`Message::Resized`](crate::Message::Resized), and
+  /// [`Message::Focus`](crate::Message::Focus) describing the window as it is,
+  /// right after [`Message::Created`](crate::Message::Created). Off by default
+  /// for now, but will default to on in the next breaking release.
+  pub fn with_initial_state_messages(mut self, enabled: bool) -> Self {
+    self.initial_state_messages = enabled;
+    self
+  }
+
+  /// Give the window its own AppUserModelID, so it gets its own taskbar
+  /// group, jump list, and pin instead of sharing those of the executable.
+  pub fn with_app_user_model_id(mut self, id: impl Into<String>) -> Self {
+    self.app_user_model_id = Some(id.into());
+    self
+  }
+
+  /// Let the window thread queue up to `size` messages ahead of the main
+  /// thread instead of waiting for each one to be taken. This keeps the window
+  /// responsive while the main thread stalls briefly (e.g. a slow frame), at
+  /// the cost of the main thread seeing messages a few frames late, so state
+  /// queried from the window can be ahead of the message being handled. The
+  /// default of `0` keeps both threads in lockstep. Has no effect when the
+  /// window runs on the current thread.
+  pub fn with_message_buffer(mut self, size: usize) -> Self {
+    self.message_buffer = size;
+    self
+  }
+
+  /// How long the window thread waits for the main thread to take a message
+  /// before it considers the main thread stalled and applies the
+  /// [`StallPolicy`], so the window stays responsive if the app stops
+  /// iterating without closing it. Defaults to 10 seconds. `None` waits
+  /// forever.
+  pub fn with_stall_timeout(mut self, timeout: Option<Duration>) -> Self {
+    self.stall_timeout = timeout;
+    self
+  }
+
+  /// What happens to new messages while the main thread is stalled. See
+  /// [`WindowSettings::with_stall_timeout`].
+  pub fn with_stall_policy(mut self, policy: StallPolicy) -> Self {
+    self.stall_policy = policy;
+    self
+  }
+
+  /// The icon shown in the title bar and on the taskbar button. See
+  /// [`Window::set_window_icon`].
+  pub fn with_window_icon(mut self, icon: Option<Icon>) -> Self {
+    self.window_icon = icon;
+    self
+  }
+
+  /// Flash the window right after it is created, e.g. for a window opened in
+  /// the background. Like [`Window::flash`], this does nothing if the window
+  /// is active by then, and a hidden window has no taskbar button to flash.
+  pub fn with_attention_on_start(mut self, mode: FlashMode) -> Self {
+    self.attention_on_start = Some(mode);
+    self
+  }
+
+  /// Make every pixel of `color` fully transparent and click-through. See
+  /// [`Window::set_transparency_color_key`].
+  pub fn with_transparency_color_key(mut self, color: Color) -> Self {
+    self.transparency_color_key = Some(color);
+    self
+  }
+
+  /// Keep the window hidden until the first frame is presented and
+  /// [`Window::notify_presented`] is called, instead of flashing white.
+  /// Overrides the initial visibility.
+  pub fn with_reveal_after_first_present(mut self, reveal: bool) -> Self {
+    self.reveal_after_first_present = reveal;
+    self
+  }
+
+  /// Whether the window follows the DPI of its monitor. When disabled the
+  /// non-client area isn't scaled, the window isn't resized when it moves to a
+  /// monitor with a different DPI, and [`Window::scale_factor`] is always
+  /// `1.0`, so sizes and positions map to pixels exactly. The window stays
+  /// per-monitor aware, so the system won't bitmap-stretch the window either;
+  /// scaling the content is up to the app, and text may look tiny on high DPI
+  /// displays if it doesn't.
+  pub fn with_dpi_scaling(mut self, dpi_scaling: bool) -> Self {
+    self.dpi_scaling = dpi_scaling;
+    self
+  }
+
+  /// The DPI awareness the window thread sets before creating the window.
+  /// This doesn't depend on the awareness of the process, which can't be
+  /// changed once set, e.g. by the host of a plugin. See [`DpiAwareness`].
+  pub fn with_dpi_awareness(mut self, awareness: DpiAwareness) -> Self {
+    self.dpi_awareness = awareness;
+    self
+  }
+
+  /// How long a [`Flow::Poll`] loop waits for a message before yielding
+  /// [`LoopMessage::Empty`](crate::LoopMessage::Empty) while the window is
+  /// minimized, as nothing can be drawn anyway. Messages still wake the loop
+  /// right away. [`Duration::ZERO`] keeps polling at full speed.
+  pub fn with_minimized_poll_interval(mut self, interval: Duration) -> Self {
+    self.minimized_poll_interval = interval;
+    self
+  }
+
+  /// Make the window owned by `owner`. An owned window always stays above its
+  /// owner in the z-order and is minimized along with it. Owned windows
+  /// should be dropped before their owner.
+  pub fn with_owner(mut self, owner: &Window) -> Self {
+    self.owner = Some(owner.0.hwnd);
+    self
+  }
+
+  /// Remember where the window was across sessions. On creation, `load` is
+  /// called with `key` and the [`WindowPlacement`](crate::WindowPlacement) it
+  /// returns replaces the size and position, as long as the window would still
+  /// be reachable on a connected monitor. When the window closes, `save` is
+  /// called with `key` and the current placement. witer doesn't store
+  /// anything itself; the callbacks decide where the text goes.
+  ///
+  /// A saved maximized state is only restored for windows created visible.
+  pub fn with_state_persistence(
+    mut self,
+    key: &str,
+    load: impl Fn(&str) -> Option<String> + Send + Sync + 'static,
+    save: impl Fn(&str, &str) + Send + Sync + 'static,
+  ) -> Self {
+    self.state_persistence = Some(StatePersistence::new(key, load, save));
+    self
+  }
+
+  /// Let later launches of the app reach this window through
+  /// [`SingleInstance::notify_existing`]. Their arguments arrive as
+  /// [`Message::SecondInstanceLaunched`](crate::Message::SecondInstanceLaunched).
+  /// Only meant for windows of the instance which claimed the app id first.
+  pub fn with_single_instance(mut self, instance: &SingleInstance) -> Self {
+    self.single_instance = Some(instance.message_id());
+    self
+  }
 }
 
+#[derive(Debug)]
 pub struct WindowBuilder {
   title: String,
   size: Size,
   position: Option<Position>,
   settings: WindowSettings,
+  /// Returned by [`WindowBuilder::build`], so the builder methods can chain.
+  error: Option<WindowError>,
 }
 
 impl Default for WindowBuilder {
@@ -98,6 +458,7 @@ impl Default for WindowBuilder {
       size: LogicalSize::new(800.0, 500.0).into(),
       position: None,
       settings: WindowSettings::default(),
+      error: None,
     }
   }
 }
@@ -142,6 +503,13 @@ impl WindowBuilder {
     self
   }
 
+  /// Whether witer sets the dark mode title bar attribute to match the theme.
+  /// Disable this for custom frames that draw their own caption.
+  pub fn with_manage_titlebar_theme(mut self, manage: bool) -> Self {
+    self.settings = self.settings.with_manage_titlebar_theme(manage);
+    self
+  }
+
   pub fn with_visibility(mut self, visibility: Visibility) -> Self {
     self.settings = self.settings.with_visibility(visibility);
     self
@@ -152,6 +520,12 @@ impl WindowBuilder {
     self
   }
 
+  /// See [`WindowSettings::with_borderless_shadow`].
+  pub fn with_borderless_shadow(mut self, borderless_shadow: bool) -> Self {
+    self.settings = self.settings.with_borderless_shadow(borderless_shadow);
+    self
+  }
+
   pub fn with_fullscreen(mut self, fullscreen: Option<Fullscreen>) -> Self {
     self.settings = self.settings.with_fullscreen(fullscreen);
 
@@ -163,17 +537,226 @@ impl WindowBuilder {
     self
   }
 
+  /// See [`WindowSettings::with_close_on_x`].
   pub fn with_close_on_x(mut self, close_on_x: bool) -> Self {
     self.settings = self.settings.with_close_on_x(close_on_x);
     self
   }
 
+  /// Whether Alt+F4 requests the window be closed. Disable this together with
+  /// the close button and `close_on_x` to block every close path, e.g. for
+  /// kiosk apps.
+  pub fn with_alt_f4(mut self, alt_f4: bool) -> Self {
+    self.settings = self.settings.with_alt_f4(alt_f4);
+    self
+  }
+
+  /// See [`Window::set_enabled_buttons`].
+  pub fn with_enabled_buttons(mut self, buttons: WindowButtons) -> Self {
+    self.settings = self.settings.with_enabled_buttons(buttons);
+    self
+  }
+
   pub fn with_resizeable(mut self, resizeable: bool) -> Self {
     self.settings = self.settings.with_resizeable(resizeable);
     self
   }
 
+  /// See [`WindowSettings::with_min_inner_size`].
+  pub fn with_min_inner_size(mut self, size: impl Into<Size>) -> Self {
+    self.settings = self.settings.with_min_inner_size(size);
+    self
+  }
+
+  /// See [`WindowSettings::with_max_inner_size`].
+  pub fn with_max_inner_size(mut self, size: impl Into<Size>) -> Self {
+    self.settings = self.settings.with_max_inner_size(size);
+    self
+  }
+
+  /// Whether the window can be activated. Non-focusable windows still receive
+  /// mouse input, but never take focus or the foreground from other windows.
+  pub fn with_focusable(mut self, focusable: bool) -> Self {
+    self.settings = self.settings.with_focusable(focusable);
+    self
+  }
+
+  /// Make the window a borderless popup. See
+  /// [`WindowSettings::with_popup`].
+  pub fn with_popup(mut self, popup: bool) -> Self {
+    self.settings = self.settings.with_popup(popup);
+    self
+  }
+
+  /// See [`WindowSettings::with_auto_dismiss`].
+  pub fn with_auto_dismiss(mut self, auto_dismiss: bool) -> Self {
+    self.settings = self.settings.with_auto_dismiss(auto_dismiss);
+    self
+  }
+
+  /// What happens when the user clicks the window while it is inactive.
+  /// Ignored for windows that aren't focusable.
+  pub fn with_click_activation(mut self, click_activation: ClickActivation) -> Self {
+    self.settings = self.settings.with_click_activation(click_activation);
+    self
+  }
+
+  /// Create the window without a GDI redirection surface. See
+  /// [`WindowSettings::with_no_redirection_bitmap`].
+  pub fn with_no_redirection_bitmap(mut self, no_redirection_bitmap: bool) -> Self {
+    self.settings = self
+      .settings
+      .with_no_redirection_bitmap(no_redirection_bitmap);
+    self
+  }
+
+  /// Deliver control characters (backspace, escape, …) through
+  /// [`Message::Text`](crate::Message::Text) instead of only printable text.
+  pub fn with_raw_text(mut self, raw_text: bool) -> Self {
+    self.settings = self.settings.with_raw_text(raw_text);
+    self
+  }
+
+  /// Start the message stream with
+  /// [`Message::ScaleFactorChanged`](crate::Message::ScaleFactorChanged),
+  /// [`Message::Resized`](crate::Message::Resized), and
+  /// [`Message::Focus`](crate::Message::Focus) describing the window as it is,
+  /// right after [`Message::Created`](crate::Message::Created). Off by default
+  /// for now, but will default to on in the next breaking release.
+  pub fn with_initial_state_messages(mut self, enabled: bool) -> Self {
+    self.settings = self.settings.with_initial_state_messages(enabled);
+    self
+  }
+
+  /// Give the window its own AppUserModelID, so it gets its own taskbar
+  /// group, jump list, and pin instead of sharing those of the executable.
+  pub fn with_app_user_model_id(mut self, id: impl Into<String>) -> Self {
+    self.settings = self.settings.with_app_user_model_id(id);
+    self
+  }
+
+  /// Let the window thread queue up to `size` messages ahead of the main
+  /// thread instead of waiting for each one to be taken. See
+  /// [`WindowSettings::with_message_buffer`].
+  pub fn with_message_buffer(mut self, size: usize) -> Self {
+    self.settings = self.settings.with_message_buffer(size);
+    self
+  }
+
+  /// How long the window thread waits for the main thread to take a message
+  /// before it considers it stalled. See
+  /// [`WindowSettings::with_stall_timeout`].
+  pub fn with_stall_timeout(mut self, timeout: Option<Duration>) -> Self {
+    self.settings = self.settings.with_stall_timeout(timeout);
+    self
+  }
+
+  /// What happens to new messages while the main thread is stalled. See
+  /// [`WindowSettings::with_stall_timeout`].
+  pub fn with_stall_policy(mut self, policy: StallPolicy) -> Self {
+    self.settings = self.settings.with_stall_policy(policy);
+    self
+  }
+
+  /// See [`WindowSettings::with_window_icon`].
+  pub fn with_window_icon(mut self, icon: Option<Icon>) -> Self {
+    self.settings = self.settings.with_window_icon(icon);
+    self
+  }
+
+  /// See [`WindowSettings::with_attention_on_start`].
+  pub fn with_attention_on_start(mut self, mode: FlashMode) -> Self {
+    self.settings = self.settings.with_attention_on_start(mode);
+    self
+  }
+
+  /// Use the RGBA pixels as the window icon. See [`Icon::from_rgba`], whose
+  /// error is returned from [`WindowBuilder::build`].
+  pub fn with_icon(self, width: u32, height: u32, rgba: Vec<u8>) -> Self {
+    match Icon::from_rgba(rgba, width, height) {
+      Ok(icon) => self.with_window_icon(Some(icon)),
+      Err(error) => Self {
+        error: Some(error),
+        ..self
+      },
+    }
+  }
+
+  /// Make every pixel of `color` fully transparent and click-through. See
+  /// [`Window::set_transparency_color_key`].
+  pub fn with_transparency_color_key(mut self, color: Color) -> Self {
+    self.settings = self.settings.with_transparency_color_key(color);
+    self
+  }
+
+  /// Keep the window hidden until the first frame is presented. See
+  /// [`WindowSettings::with_reveal_after_first_present`].
+  pub fn with_reveal_after_first_present(mut self, reveal: bool) -> Self {
+    self.settings = self.settings.with_reveal_after_first_present(reveal);
+    self
+  }
+
+  /// See [`WindowSettings::with_dpi_scaling`].
+  pub fn with_dpi_scaling(mut self, dpi_scaling: bool) -> Self {
+    self.settings = self.settings.with_dpi_scaling(dpi_scaling);
+    self
+  }
+
+  /// See [`WindowSettings::with_dpi_awareness`].
+  pub fn with_dpi_awareness(mut self, awareness: DpiAwareness) -> Self {
+    self.settings = self.settings.with_dpi_awareness(awareness);
+    self
+  }
+
+  /// See [`WindowSettings::with_minimized_poll_interval`].
+  pub fn with_minimized_poll_interval(mut self, interval: Duration) -> Self {
+    self.settings = self.settings.with_minimized_poll_interval(interval);
+    self
+  }
+
+  /// Make the window owned by `owner`. An owned window always stays above its
+  /// owner in the z-order and is minimized along with it. Owned windows
+  /// should be dropped before their owner.
+  pub fn with_owner(mut self, owner: &Window) -> Self {
+    self.settings = self.settings.with_owner(owner);
+    self
+  }
+
+  /// See [`WindowSettings::with_state_persistence`].
+  pub fn with_state_persistence(
+    mut self,
+    key: &str,
+    load: impl Fn(&str) -> Option<String> + Send + Sync + 'static,
+    save: impl Fn(&str, &str) + Send + Sync + 'static,
+  ) -> Self {
+    self.settings = self.settings.with_state_persistence(key, load, save);
+    self
+  }
+
+  /// See [`WindowSettings::with_single_instance`].
+  pub fn with_single_instance(mut self, instance: &SingleInstance) -> Self {
+    self.settings = self.settings.with_single_instance(instance);
+    self
+  }
+
   pub fn build(self) -> Result<Window, WindowError> {
+    if let Some(error) = self.error {
+      return Err(error);
+    }
     Window::new(self.title, self.size, self.position, self.settings)
   }
+
+  /// Build the window on the calling thread instead of spawning a dedicated
+  /// window thread. Messages for every window on the calling thread are pumped
+  /// while iterating over the window, so this suits hosts which already own
+  /// the thread's message loop, or simple single-threaded apps.
+  ///
+  /// The window must be iterated over and dropped on the thread which built
+  /// it.
+  pub fn build_on_current_thread(self) -> Result<Window, WindowError> {
+    if let Some(error) = self.error {
+      return Err(error);
+    }
+    Window::new_on_current_thread(self.title, self.size, self.position, self.settings)
+  }
 }
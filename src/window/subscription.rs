@@ -0,0 +1,349 @@
+use std::panic::{self, AssertUnwindSafe};
+
+use super::message::Message;
+
+/// Which messages a callback registered with [`Window::on`](crate::Window::on)
+/// is called for.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct MessageKindSet {
+  /// Messages for which [`Message::is_input`] is `true`.
+  pub input: bool,
+  /// Messages for which [`Message::is_window_event`] is `true`.
+  pub window: bool,
+  /// [`Message::Paint`].
+  pub paint: bool,
+  /// [`Message::Loop`].
+  pub loop_messages: bool,
+  /// [`Message::App`].
+  pub app: bool,
+  /// Everything else.
+  pub other: bool,
+}
+
+impl MessageKindSet {
+  pub const ALL: Self = Self {
+    input: true,
+    window: true,
+    paint: true,
+    loop_messages: true,
+    app: true,
+    other: true,
+  };
+  pub const NONE: Self = Self {
+    input: false,
+    window: false,
+    paint: false,
+    loop_messages: false,
+    app: false,
+    other: false,
+  };
+
+  pub fn contains(&self, message: &Message) -> bool {
+    if message.is_input() {
+      self.input
+    } else if message.is_window_event() {
+      self.window
+    } else {
+      match message {
+        Message::Paint => self.paint,
+        Message::Loop(_) => self.loop_messages,
+        Message::App { .. } => self.app,
+        _ => self.other,
+      }
+    }
+  }
+}
+
+impl Default for MessageKindSet {
+  fn default() -> Self {
+    Self::ALL
+  }
+}
+
+/// What a callback registered with [`Window::on`](crate::Window::on) wants
+/// done with the message.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Control {
+  /// Call the next callback and then yield the message from the iterator.
+  #[default]
+  Continue,
+  /// Skip the remaining callbacks and don't yield the message. Ignored for
+  /// [`Message::Loop`], which the loop relies on.
+  Handled,
+}
+
+/// Identifies a callback for [`Window::unsubscribe`](crate::Window::unsubscribe).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+type Callback = Box<dyn FnMut(&Message) -> Control + Send>;
+
+pub(crate) struct Subscription {
+  id: SubscriptionId,
+  kinds: MessageKindSet,
+  callback: Callback,
+}
+
+/// The callbacks of a window, called in the order they were registered.
+#[derive(Default)]
+pub(crate) struct Subscriptions {
+  next_id: u64,
+  active: Vec<Subscription>,
+  /// Set while the callbacks are taken out to be called.
+  dispatching: bool,
+  /// Unsubscribed while the callbacks were taken out.
+  removed: Vec<SubscriptionId>,
+}
+
+impl std::fmt::Debug for Subscriptions {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("Subscriptions")
+      .field("count", &self.active.len())
+      .field("dispatching", &self.dispatching)
+      .finish()
+  }
+}
+
+impl Subscriptions {
+  pub fn subscribe(
+    &mut self,
+    kinds: MessageKindSet,
+    callback: impl FnMut(&Message) -> Control + Send + 'static,
+  ) -> SubscriptionId {
+    let id = SubscriptionId(self.next_id);
+    self.next_id += 1;
+    self.active.push(Subscription {
+      id,
+      kinds,
+      callback: Box::new(callback),
+    });
+    id
+  }
+
+  pub fn unsubscribe(&mut self, id: SubscriptionId) {
+    if let Some(index) = self.active.iter().position(|sub| sub.id == id) {
+      self.active.remove(index);
+    } else if self.dispatching {
+      self.removed.push(id);
+    }
+  }
+
+  pub fn is_dispatching(&self) -> bool {
+    self.dispatching
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.active.is_empty()
+  }
+
+  /// Takes the callbacks out, so they can be called without holding the lock
+  /// they are kept behind. Hand them back with [`Subscriptions::finish`].
+  pub fn begin(&mut self) -> Vec<Subscription> {
+    self.dispatching = true;
+    std::mem::take(&mut self.active)
+  }
+
+  /// Puts the callbacks taken by [`Subscriptions::begin`] back, ahead of any
+  /// registered in the meantime and without those unsubscribed in the
+  /// meantime.
+  pub fn finish(&mut self, mut taken: Vec<Subscription>) {
+    let removed = std::mem::take(&mut self.removed);
+    taken.retain(|sub| !removed.contains(&sub.id));
+    taken.append(&mut self.active);
+    self.active = taken;
+    self.dispatching = false;
+  }
+}
+
+/// Calls every matching callback in order. A callback that panics is removed
+/// and the panic logged, so one broken callback can't take down the loop.
+pub(crate) fn dispatch(
+  subscriptions: &mut Vec<Subscription>,
+  message: &Message,
+) -> Control {
+  let mut control = Control::Continue;
+  let mut panicked = Vec::new();
+
+  for sub in subscriptions.iter_mut() {
+    if !sub.kinds.contains(message) {
+      continue;
+    }
+    match panic::catch_unwind(AssertUnwindSafe(|| (sub.callback)(message))) {
+      Ok(Control::Handled) if !message.is_loop() => {
+        control = Control::Handled;
+        break;
+      }
+      Ok(_) => (),
+      Err(_) => {
+        tracing::error!("message callback {:?} panicked, removing it", sub.id);
+        panicked.push(sub.id);
+      }
+    }
+  }
+
+  subscriptions.retain(|sub| !panicked.contains(&sub.id));
+  control
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::{Arc, Mutex};
+
+  use super::*;
+  use crate::{window::message::LoopMessage, PhysicalSize};
+
+  /// Subscribes a callback that records `name` and answers with `control`.
+  fn record(
+    subscriptions: &mut Subscriptions,
+    log: &Arc<Mutex<Vec<&'static str>>>,
+    name: &'static str,
+    kinds: MessageKindSet,
+    control: Control,
+  ) -> SubscriptionId {
+    let log = log.clone();
+    subscriptions.subscribe(kinds, move |_| {
+      log.lock().unwrap().push(name);
+      control
+    })
+  }
+
+  fn run(subscriptions: &mut Subscriptions, message: &Message) -> Control {
+    let mut taken = subscriptions.begin();
+    let control = dispatch(&mut taken, message);
+    subscriptions.finish(taken);
+    control
+  }
+
+  #[test]
+  fn kind_set_filtering() {
+    let resized = Message::Resized(PhysicalSize::new(1, 1));
+    let text = Message::Text("a".into());
+    let app = Message::App {
+      code: 0,
+      wparam: 0,
+      lparam: 0,
+    };
+    let idle = Message::Loop(LoopMessage::Idle);
+
+    let input_only = MessageKindSet {
+      input: true,
+      ..MessageKindSet::NONE
+    };
+    assert!(input_only.contains(&text));
+    assert!(!input_only.contains(&resized));
+    assert!(!input_only.contains(&Message::Paint));
+
+    let window_only = MessageKindSet {
+      window: true,
+      ..MessageKindSet::NONE
+    };
+    assert!(window_only.contains(&resized));
+    assert!(!window_only.contains(&text));
+
+    let everything_but_loop = MessageKindSet {
+      loop_messages: false,
+      ..MessageKindSet::ALL
+    };
+    assert!(!everything_but_loop.contains(&idle));
+    assert!(everything_but_loop.contains(&app));
+    assert!(everything_but_loop.contains(&Message::Command));
+
+    for message in [
+      &resized,
+      &text,
+      &app,
+      &idle,
+      &Message::Paint,
+      &Message::Command,
+    ] {
+      assert!(MessageKindSet::ALL.contains(message));
+      assert!(!MessageKindSet::NONE.contains(message));
+    }
+    assert_eq!(MessageKindSet::default(), MessageKindSet::ALL);
+  }
+
+  #[test]
+  fn callbacks_run_in_registration_order() {
+    let log = Arc::default();
+    let mut subscriptions = Subscriptions::default();
+    for name in ["first", "second", "third"] {
+      record(&mut subscriptions, &log, name, MessageKindSet::ALL, Control::Continue);
+    }
+
+    assert_eq!(run(&mut subscriptions, &Message::Paint), Control::Continue);
+    assert_eq!(*log.lock().unwrap(), ["first", "second", "third"]);
+  }
+
+  #[test]
+  fn callbacks_skip_other_kinds() {
+    let log = Arc::default();
+    let mut subscriptions = Subscriptions::default();
+    let paint = MessageKindSet {
+      paint: true,
+      ..MessageKindSet::NONE
+    };
+    record(&mut subscriptions, &log, "paint", paint, Control::Handled);
+    record(&mut subscriptions, &log, "all", MessageKindSet::ALL, Control::Continue);
+
+    assert_eq!(run(&mut subscriptions, &Message::CloseRequested), Control::Continue);
+    assert_eq!(*log.lock().unwrap(), ["all"]);
+  }
+
+  #[test]
+  fn handled_skips_the_remaining_callbacks() {
+    let log = Arc::default();
+    let mut subscriptions = Subscriptions::default();
+    record(&mut subscriptions, &log, "first", MessageKindSet::ALL, Control::Continue);
+    record(&mut subscriptions, &log, "handler", MessageKindSet::ALL, Control::Handled);
+    record(&mut subscriptions, &log, "last", MessageKindSet::ALL, Control::Continue);
+
+    assert_eq!(run(&mut subscriptions, &Message::Paint), Control::Handled);
+    assert_eq!(*log.lock().unwrap(), ["first", "handler"]);
+  }
+
+  #[test]
+  fn loop_messages_ignore_handled() {
+    let log = Arc::default();
+    let mut subscriptions = Subscriptions::default();
+    record(&mut subscriptions, &log, "handler", MessageKindSet::ALL, Control::Handled);
+    record(&mut subscriptions, &log, "last", MessageKindSet::ALL, Control::Continue);
+
+    let control = run(&mut subscriptions, &Message::Loop(LoopMessage::Empty));
+    assert_eq!(control, Control::Continue);
+    assert_eq!(*log.lock().unwrap(), ["handler", "last"]);
+  }
+
+  #[test]
+  fn panicking_callbacks_are_removed() {
+    let log = Arc::default();
+    let mut subscriptions = Subscriptions::default();
+    subscriptions.subscribe(MessageKindSet::ALL, |_| panic!("broken callback"));
+    record(&mut subscriptions, &log, "after", MessageKindSet::ALL, Control::Continue);
+
+    assert_eq!(run(&mut subscriptions, &Message::Paint), Control::Continue);
+    assert_eq!(run(&mut subscriptions, &Message::Paint), Control::Continue);
+    // the rest keep running, the broken one only ran once
+    assert_eq!(*log.lock().unwrap(), ["after", "after"]);
+    assert_eq!(subscriptions.active.len(), 1);
+  }
+
+  #[test]
+  fn changes_during_dispatch_apply_afterwards() {
+    let log = Arc::default();
+    let mut subscriptions = Subscriptions::default();
+    let first =
+      record(&mut subscriptions, &log, "first", MessageKindSet::ALL, Control::Continue);
+    record(&mut subscriptions, &log, "second", MessageKindSet::ALL, Control::Continue);
+
+    let mut taken = subscriptions.begin();
+    assert!(subscriptions.is_dispatching());
+    subscriptions.unsubscribe(first);
+    record(&mut subscriptions, &log, "new", MessageKindSet::ALL, Control::Continue);
+    dispatch(&mut taken, &Message::Paint);
+    subscriptions.finish(taken);
+    assert!(!subscriptions.is_dispatching());
+
+    run(&mut subscriptions, &Message::Paint);
+    assert_eq!(*log.lock().unwrap(), ["first", "second", "second", "new"]);
+  }
+}